@@ -1,16 +1,52 @@
 use crate::feed::Article;
 use crate::stock::DailyPrice;
 use crate::storage::Investment;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
 use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashSet};
+use std::io;
+use std::ops::Range;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ArticleMention {
+    #[serde(flatten)]
     pub article: Article,
     pub ticker: String,
     pub sentiment: Sentiment,
+    pub score: SentimentScore,
+    /// Which identifier of the investment was found in the article
+    pub matched_by: MatchKind,
+    /// The text that matched, as written in the article
+    pub matched_term: String,
+    /// Text surrounding the first match
+    pub snippet: String,
+    /// Sources of near-duplicate articles collapsed into this mention
+    pub also_in: Vec<String>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchKind {
+    Ticker,
+    Name,
+    Isin,
+    Cusip,
+}
+
+impl std::fmt::Display for MatchKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MatchKind::Ticker => write!(f, "ticker"),
+            MatchKind::Name => write!(f, "name"),
+            MatchKind::Isin => write!(f, "ISIN"),
+            MatchKind::Cusip => write!(f, "CUSIP"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum Sentiment {
     Positive,
     Negative,
@@ -27,13 +63,74 @@ impl std::fmt::Display for Sentiment {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct Correlation {
     pub date: String,
+    /// Trading day whose price move is paired with the article
+    pub trading_date: Option<String>,
     pub article_title: String,
     pub sentiment: Sentiment,
     pub price: Option<f64>,
     pub price_change: Option<f64>,
+    /// Benchmark's percent change on the same day
+    pub benchmark_change: Option<f64>,
+    /// `price_change` minus `benchmark_change`
+    pub excess_change: Option<f64>,
+}
+
+/// Overall verdict on how a ticker's price reacted to its news.
+#[derive(Debug)]
+pub struct ReactionSummary {
+    pub positive: usize,
+    pub negative: usize,
+    pub neutral: usize,
+    /// None when there are no correlations; Neutral when positive and negative tie
+    pub dominant: Option<Sentiment>,
+    /// Average price change (%) after positive news, on the trading day
+    /// each mention was paired with: `lag` days after it was published, as
+    /// passed to [`correlate`]
+    pub avg_change_positive: Option<f64>,
+    /// Average price change (%) after negative news, paired the same way
+    pub avg_change_negative: Option<f64>,
+}
+
+/// Characters that may appear immediately around a ticker for it to count
+/// as a match. Whitespace and the start/end of the text always count.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TickerBoundaries {
+    /// e.g. `$` in "$AAPL", `(` in "(AAPL)", `:` in "NASDAQ:AAPL"
+    pub before: String,
+    /// e.g. `)` in "(AAPL)", `'` in "AAPL's", `.` at the end of a sentence
+    pub after: String,
+}
+
+impl Default for TickerBoundaries {
+    fn default() -> Self {
+        TickerBoundaries {
+            before: "$([{:\"'\u{201c}\u{2018}".to_string(),
+            after: ")]}:.,;!?\"'\u{201d}\u{2019}".to_string(),
+        }
+    }
+}
+
+/// Key used to section scan output.
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum GroupBy {
+    Feed,
+    Ticker,
+    Date,
+    Sentiment,
+}
+
+/// Mention sentiment counts for a single source feed.
+#[derive(Debug, Serialize)]
+pub struct SourceSentiment {
+    pub source: String,
+    pub positive: usize,
+    pub negative: usize,
+    pub neutral: usize,
+    /// Positive minus negative mentions
+    pub net: i64,
 }
 
 const POSITIVE_WORDS: &[&str] = &[
@@ -41,6 +138,7 @@ const POSITIVE_WORDS: &[&str] = &[
     "profit", "profits", "beat", "beats", "bullish", "growth", "growing",
     "rally", "rallies", "soar", "soars", "soaring", "jump", "jumps",
     "record", "high", "upgrade", "upgrades", "strong", "success", "win",
+    "gained", "surged", "rose", "beaten", "rallied", "soared", "jumped",
 ];
 
 const NEGATIVE_WORDS: &[&str] = &[
@@ -48,46 +146,203 @@ const NEGATIVE_WORDS: &[&str] = &[
     "miss", "misses", "bearish", "decline", "declines", "declining", "crash",
     "crashes", "plunge", "plunges", "plunging", "sink", "sinks", "sinking",
     "low", "downgrade", "downgrades", "weak", "fail", "fails", "cut", "cuts",
+    "fell", "dropped", "missed", "declined", "crashed", "plunged", "sank",
 ];
 
-pub fn find_mentions(articles: &[Article], investments: &[Investment]) -> Vec<ArticleMention> {
-    let mut mentions = Vec::new();
+/// Words counted as positive or negative by `score_sentiment`.
+#[derive(Debug, Clone)]
+pub struct Lexicon {
+    positive: HashSet<String>,
+    negative: HashSet<String>,
+}
 
-    for article in articles {
-        let text = format!(
-            "{} {}",
-            article.title,
-            article.content.as_deref().unwrap_or("")
+impl Default for Lexicon {
+    /// The built-in financial news word lists.
+    fn default() -> Self {
+        Lexicon::new(
+            POSITIVE_WORDS.iter().map(|w| w.to_string()),
+            NEGATIVE_WORDS.iter().map(|w| w.to_string()),
         )
-        .to_uppercase();
+    }
+}
+
+impl Lexicon {
+    pub fn new(
+        positive: impl IntoIterator<Item = String>,
+        negative: impl IntoIterator<Item = String>,
+    ) -> Self {
+        let mut lexicon = Lexicon {
+            positive: HashSet::new(),
+            negative: HashSet::new(),
+        };
+        lexicon.extend(positive, negative);
+        lexicon
+    }
 
-        for investment in investments {
-            let ticker_pattern = format!(r"\b{}\b", regex::escape(&investment.ticker));
-            let ticker_re = Regex::new(&ticker_pattern).unwrap();
+    /// Adds words to the lists. A word added to one list is removed from the
+    /// other, so users can reclassify a default word.
+    pub fn extend(
+        &mut self,
+        positive: impl IntoIterator<Item = String>,
+        negative: impl IntoIterator<Item = String>,
+    ) {
+        for word in positive {
+            let word = word.to_lowercase();
+            self.negative.remove(&word);
+            self.positive.insert(word);
+        }
+        for word in negative {
+            let word = word.to_lowercase();
+            self.positive.remove(&word);
+            self.negative.insert(word);
+        }
+    }
+}
 
-            let mut found = ticker_re.is_match(&text);
+/// Builds a regex matching `ticker` surrounded by whitespace, the ends of the
+/// text, or one of the configured boundary characters, so with the default
+/// boundaries cashtags like `$AAPL` match but `AAPLE` does not. The ticker
+/// itself is capture group 1.
+pub fn ticker_regex(ticker: &str, boundaries: &TickerBoundaries) -> Regex {
+    let class = |chars: &str| {
+        chars
+            .chars()
+            .map(|c| regex::escape(&c.to_string()))
+            .collect::<String>()
+    };
+    let pattern = format!(
+        r"(?:^|[\s{}])({})(?:$|[\s{}])",
+        class(&boundaries.before),
+        regex::escape(ticker),
+        class(&boundaries.after)
+    );
+    Regex::new(&pattern).expect("escaped ticker pattern is valid")
+}
+
+/// Tickers shorter than this are ambiguous in strict matching mode.
+const STRICT_MIN_TICKER_LEN: usize = 3;
+
+/// Whether strict matching should only accept `ticker` as a cashtag: it is
+/// short enough to be an ordinary word, or on the user's stoplist.
+fn is_ambiguous(ticker: &str, stoplist: &[String]) -> bool {
+    ticker.chars().count() < STRICT_MIN_TICKER_LEN
+        || stoplist.iter().any(|t| t.eq_ignore_ascii_case(ticker))
+}
+
+/// Compiled patterns for each way an investment can be mentioned, in the
+/// order they are tried.
+struct InvestmentMatcher<'a> {
+    investment: &'a Investment,
+    patterns: Vec<(MatchKind, Regex)>,
+}
 
-            if !found {
-                if let Some(ref name) = investment.name {
-                    let name_pattern = format!(r"\b{}\b", regex::escape(&name.to_uppercase()));
-                    if let Ok(name_re) = Regex::new(&name_pattern) {
-                        found = name_re.is_match(&text);
-                    }
+impl<'a> InvestmentMatcher<'a> {
+    fn new(
+        investment: &'a Investment,
+        boundaries: &TickerBoundaries,
+        strict: Option<&[String]>,
+    ) -> Self {
+        let ticker = match strict {
+            Some(stoplist) if is_ambiguous(&investment.ticker, stoplist) => {
+                format!("${}", investment.ticker)
+            }
+            _ => investment.ticker.clone(),
+        };
+        let mut patterns = vec![(MatchKind::Ticker, ticker_regex(&ticker, boundaries))];
+        let identifiers = [
+            (MatchKind::Name, &investment.name),
+            (MatchKind::Isin, &investment.isin),
+            (MatchKind::Cusip, &investment.cusip),
+        ];
+        for (kind, identifier) in identifiers {
+            if let Some(identifier) = identifier {
+                let pattern = format!(r"\b{}\b", regex::escape(&identifier.to_uppercase()));
+                if let Ok(re) = Regex::new(&pattern) {
+                    patterns.push((kind, re));
                 }
             }
+        }
+        InvestmentMatcher {
+            investment,
+            patterns,
+        }
+    }
+
+    /// The first identifier found in uppercased `text`, with the byte range
+    /// of the match.
+    fn find(&self, text: &str) -> Option<(MatchKind, Range<usize>)> {
+        self.patterns.iter().find_map(|(kind, re)| {
+            let caps = re.captures(text)?;
+            let m = caps.get(1).or_else(|| caps.get(0))?;
+            Some((*kind, m.range()))
+        })
+    }
+}
 
-            if found {
-                let full_text = format!(
-                    "{} {}",
-                    article.title,
-                    article.content.as_deref().unwrap_or("")
-                );
-                let sentiment = analyze_sentiment(&full_text);
+/// Uppercases `text`, also returning the byte offset in `text` of every byte
+/// of the result (plus one past the end), since uppercasing can change the
+/// length of non-ASCII characters.
+fn uppercase_with_offsets(text: &str) -> (String, Vec<usize>) {
+    let mut upper = String::with_capacity(text.len());
+    let mut offsets = Vec::with_capacity(text.len() + 1);
+    for (i, c) in text.char_indices() {
+        for u in c.to_uppercase() {
+            upper.push(u);
+            offsets.resize(upper.len(), i);
+        }
+    }
+    offsets.push(text.len());
+    (upper, offsets)
+}
+
+/// Characters of context shown on each side of a mention's match.
+const MENTION_CONTEXT: usize = 75;
+
+/// Finds articles mentioning each investment by ticker or identifier.
+///
+/// With `strict` set to a stoplist of ambiguous tickers, tickers on it or
+/// shorter than three letters only count in cashtag form (`$ON`) or when the
+/// investment's name or other identifiers appear.
+pub fn find_mentions(
+    articles: &[Article],
+    investments: &[Investment],
+    boundaries: &TickerBoundaries,
+    lexicon: &Lexicon,
+    strict: Option<&[String]>,
+) -> Vec<ArticleMention> {
+    let matchers: Vec<InvestmentMatcher> = investments
+        .iter()
+        .map(|investment| InvestmentMatcher::new(investment, boundaries, strict))
+        .collect();
+    let mut mentions = Vec::new();
+
+    for article in articles {
+        let full_text = format!(
+            "{} {}",
+            article.title,
+            article
+                .content
+                .as_deref()
+                .map(crate::feed::strip_html)
+                .unwrap_or_default()
+        );
+        let (text, offsets) = uppercase_with_offsets(&full_text);
+        let mut score = None;
+
+        for matcher in &matchers {
+            if let Some((matched_by, range)) = matcher.find(&text) {
+                let (start, end) = (offsets[range.start], offsets[range.end]);
+                let score = *score.get_or_insert_with(|| score_sentiment(&full_text, lexicon));
 
                 mentions.push(ArticleMention {
                     article: article.clone(),
-                    ticker: investment.ticker.clone(),
-                    sentiment,
+                    ticker: matcher.investment.ticker.clone(),
+                    sentiment: score.sentiment(),
+                    score,
+                    matched_by,
+                    matched_term: full_text[start..end].to_string(),
+                    snippet: snippet(&full_text, start, end, MENTION_CONTEXT),
+                    also_in: Vec::new(),
                 });
             }
         }
@@ -96,74 +351,1451 @@ pub fn find_mentions(articles: &[Article], investments: &[Investment]) -> Vec<Ar
     mentions
 }
 
+/// Words that flip the polarity of a sentiment word shortly after them, as in
+/// "did not rise" or "failed to beat". Contractions like "didn't" count too.
+const NEGATION_WORDS: &[&str] = &["not", "no", "never", "without", "failed"];
+
+/// How many tokens before a sentiment word a negation still applies to.
+const NEGATION_WINDOW: usize = 3;
+
+fn is_negation(token: &str) -> bool {
+    NEGATION_WORDS.contains(&token) || token.ends_with("n't")
+}
+
+/// Splits lowercased text into words, keeping apostrophes so contractions
+/// stay whole.
+fn tokenize(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .replace('\u{2019}', "'")
+        .split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .map(|t| t.trim_matches('\''))
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Sentiment word counts for a text and a score combining them.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct SentimentScore {
+    pub positive: u32,
+    pub negative: u32,
+    /// From -1.0 (negative) to 1.0 (positive); grows in magnitude with the
+    /// number of sentiment words, not just their balance
+    pub score: f64,
+}
+
+impl SentimentScore {
+    /// Classifies the score: any net positive or negative lean counts.
+    pub fn sentiment(&self) -> Sentiment {
+        if self.score > 0.0 {
+            Sentiment::Positive
+        } else if self.score < 0.0 {
+            Sentiment::Negative
+        } else {
+            Sentiment::Neutral
+        }
+    }
+}
+
+/// Dampens the score of texts with only a few sentiment words, so a single
+/// "beat" scores lower than a string of them.
+const SCORE_SMOOTHING: f64 = 2.0;
+
+/// Classifies `text` with the built-in word lists. Use [`score_sentiment`]
+/// for a custom lexicon or the word counts behind the result.
 pub fn analyze_sentiment(text: &str) -> Sentiment {
-    let lower = text.to_lowercase();
+    score_sentiment(text, &Lexicon::default()).sentiment()
+}
 
-    let positive_count = POSITIVE_WORDS
-        .iter()
-        .filter(|&&word| {
-            let pattern = format!(r"\b{}\b", word);
-            Regex::new(&pattern)
-                .map(|re| re.is_match(&lower))
-                .unwrap_or(false)
-        })
-        .count();
+pub fn score_sentiment(text: &str, lexicon: &Lexicon) -> SentimentScore {
+    let tokens = tokenize(text);
+    let mut positive = 0;
+    let mut negative = 0;
 
-    let negative_count = NEGATIVE_WORDS
-        .iter()
-        .filter(|&&word| {
-            let pattern = format!(r"\b{}\b", word);
-            Regex::new(&pattern)
-                .map(|re| re.is_match(&lower))
-                .unwrap_or(false)
-        })
-        .count();
+    for (i, token) in tokens.iter().enumerate() {
+        let polarity = if lexicon.positive.contains(token) {
+            1
+        } else if lexicon.negative.contains(token) {
+            -1
+        } else {
+            continue;
+        };
+        let negated = tokens[i.saturating_sub(NEGATION_WINDOW)..i]
+            .iter()
+            .any(|t| is_negation(t));
+        if (polarity > 0) != negated {
+            positive += 1;
+        } else {
+            negative += 1;
+        }
+    }
 
-    if positive_count > negative_count {
-        Sentiment::Positive
-    } else if negative_count > positive_count {
-        Sentiment::Negative
-    } else {
-        Sentiment::Neutral
+    let score =
+        (positive as f64 - negative as f64) / (positive as f64 + negative as f64 + SCORE_SMOOTHING);
+    SentimentScore {
+        positive,
+        negative,
+        score,
     }
 }
 
-pub fn correlate(
-    mentions: &[ArticleMention],
-    prices: &[DailyPrice],
-) -> Vec<Correlation> {
+/// Pairs each mention with the price move `lag` days after its publish
+/// date. When that day isn't a trading day (a weekend or holiday), the next
+/// trading day in `prices` within a few days is used instead. A lag that
+/// would move past the last representable date leaves the mention without
+/// a price.
+pub fn correlate(mentions: &[ArticleMention], prices: &[DailyPrice], lag: i64) -> Vec<Correlation> {
     let mut correlations = Vec::new();
 
     for mention in mentions {
-        let article_date = mention
-            .article
-            .published
-            .as_deref()
-            .and_then(|d| d.split_whitespace().next())
-            .unwrap_or("");
-
-        let price_entry = prices.iter().find(|p| p.date == article_date);
-
-        let price_change = if let Some(entry) = price_entry {
-            let idx = prices.iter().position(|p| p.date == entry.date).unwrap_or(0);
-            if idx > 0 {
-                let prev = prices[idx - 1].close;
-                Some(((entry.close - prev) / prev) * 100.0)
-            } else {
-                None
-            }
-        } else {
-            None
-        };
+        let article_date = mention.article.published.map(|p| p.date_naive());
+
+        let idx = article_date
+            .and_then(|date| date.checked_add_signed(Duration::try_days(lag)?))
+            .and_then(|day| trading_day_index(prices, day));
+        let price_entry = idx.map(|i| &prices[i]);
+        let price_change = idx.and_then(|i| {
+            let prev = prices[i.checked_sub(1)?].close;
+            Some(((prices[i].close - prev) / prev) * 100.0)
+        });
 
         correlations.push(Correlation {
-            date: article_date.to_string(),
+            date: article_date.map(|d| d.to_string()).unwrap_or_default(),
+            trading_date: price_entry.map(|p| p.date.clone()),
             article_title: mention.article.title.clone(),
             sentiment: mention.sentiment,
             price: price_entry.map(|p| p.close),
             price_change,
+            benchmark_change: None,
+            excess_change: None,
         });
     }
 
     correlations
 }
+
+/// Longest stretch of non-trading days `trading_day_index` skips over: a
+/// long weekend with a holiday on either side.
+const MAX_TRADING_GAP_DAYS: i64 = 4;
+
+/// Index of the first trading day in `prices` (sorted oldest first) on or
+/// after `date` and at most `MAX_TRADING_GAP_DAYS` later, or None if there
+/// is none, e.g. because `prices` ends before it or has a gap.
+fn trading_day_index(prices: &[DailyPrice], date: NaiveDate) -> Option<usize> {
+    let latest = date
+        .checked_add_signed(Duration::days(MAX_TRADING_GAP_DAYS))
+        .unwrap_or(NaiveDate::MAX);
+    let (index, day) = prices.iter().enumerate().find_map(|(i, p)| {
+        let day = NaiveDate::parse_from_str(&p.date, "%Y-%m-%d").ok()?;
+        (day >= date).then_some((i, day))
+    })?;
+    (day <= latest).then_some(index)
+}
+
+/// Pearson correlation between mention sentiment (+1 positive, 0 neutral,
+/// -1 negative) and the price change `lag` days later, over mentions with a
+/// known change. None with fewer than two pairs or when either side never
+/// varies.
+pub fn pearson_correlation(
+    mentions: &[ArticleMention],
+    prices: &[DailyPrice],
+    lag: i64,
+) -> Option<f64> {
+    let pairs: Vec<(f64, f64)> = correlate(mentions, prices, lag)
+        .iter()
+        .filter_map(|c| {
+            let sentiment = match c.sentiment {
+                Sentiment::Positive => 1.0,
+                Sentiment::Negative => -1.0,
+                Sentiment::Neutral => 0.0,
+            };
+            Some((sentiment, c.price_change?))
+        })
+        .collect();
+    pearson(&pairs)
+}
+
+/// Default age in days at which a mention counts half as much in
+/// `sentiment_trend`.
+pub const DEFAULT_HALF_LIFE_DAYS: f64 = 7.0;
+/// `sentiment_trend` values closer to zero than this read as neutral.
+const NEUTRAL_TREND: f64 = 0.1;
+
+/// Overall news sentiment from -1.0 to 1.0: the average of the mentions'
+/// scores, each weighted by `0.5^(age / half_life_days)` so recent articles
+/// count more. Mentions without a publish date are left out; None when no
+/// mention has one.
+pub fn sentiment_trend(
+    mentions: &[ArticleMention],
+    now: DateTime<Utc>,
+    half_life_days: f64,
+) -> Option<f64> {
+    let (mut weighted, mut total_weight) = (0.0, 0.0);
+    for mention in mentions {
+        let Some(published) = mention.article.published else {
+            continue;
+        };
+        let age_days = (now - published).num_seconds().max(0) as f64 / 86_400.0;
+        let weight = 0.5f64.powf(age_days / half_life_days);
+        weighted += weight * mention.score.score;
+        total_weight += weight;
+    }
+    (total_weight > 0.0).then(|| weighted / total_weight)
+}
+
+/// Key of the `mention_histogram` bucket for mentions without a date.
+pub const UNDATED_BUCKET: &str = "unknown";
+
+/// Counts mentions per publish day (`YYYY-MM-DD`, UTC) as (positive,
+/// negative, neutral). Undated mentions go in the [`UNDATED_BUCKET`], which
+/// sorts after every date.
+///
+/// ```
+/// use chrono::{TimeZone, Utc};
+/// use rss_reader::analysis::{find_mentions, mention_histogram, Lexicon, TickerBoundaries};
+/// use rss_reader::{Article, Investment};
+///
+/// let article = |title: &str, day: Option<u32>| Article {
+///     id: None,
+///     title: title.to_string(),
+///     link: None,
+///     author: None,
+///     categories: Vec::new(),
+///     published: day.map(|d| Utc.with_ymd_and_hms(2025, 1, d, 12, 0, 0).unwrap()),
+///     content: None,
+///     enclosures: Vec::new(),
+///     source: "https://example.com/feed".to_string(),
+/// };
+/// let articles = [
+///     article("AAPL surges", Some(6)),
+///     article("AAPL falls", Some(6)),
+///     article("AAPL gains again", Some(6)),
+///     article("AAPL holds event", Some(7)),
+///     article("AAPL drops", None),
+/// ];
+/// let mentions = find_mentions(
+///     &articles,
+///     &[Investment::new("AAPL")],
+///     &TickerBoundaries::default(),
+///     &Lexicon::default(),
+///     None,
+/// );
+///
+/// let histogram = mention_histogram(&mentions);
+/// let days: Vec<_> = histogram.iter().map(|(d, c)| (d.as_str(), *c)).collect();
+/// assert_eq!(
+///     days,
+///     [("2025-01-06", (2, 1, 0)), ("2025-01-07", (0, 0, 1)), ("unknown", (0, 1, 0))]
+/// );
+/// ```
+pub fn mention_histogram(mentions: &[ArticleMention]) -> BTreeMap<String, (usize, usize, usize)> {
+    let mut histogram = BTreeMap::new();
+    for mention in mentions {
+        let day = match mention.article.published {
+            Some(published) => published.format("%Y-%m-%d").to_string(),
+            None => UNDATED_BUCKET.to_string(),
+        };
+        let counts: &mut (usize, usize, usize) = histogram.entry(day).or_default();
+        match mention.sentiment {
+            Sentiment::Positive => counts.0 += 1,
+            Sentiment::Negative => counts.1 += 1,
+            Sentiment::Neutral => counts.2 += 1,
+        }
+    }
+    histogram
+}
+
+/// Widest bar [`histogram_bars`] draws; busier days are scaled down.
+pub const HISTOGRAM_WIDTH: usize = 40;
+
+/// Lengths of the positive, negative and neutral parts of each day's bar in
+/// a drawing of `histogram`: one character per mention when the busiest day
+/// fits in [`HISTOGRAM_WIDTH`], otherwise scaled down, keeping at least one
+/// character for any non-zero count.
+pub fn histogram_bars(
+    histogram: &BTreeMap<String, (usize, usize, usize)>,
+) -> Vec<(&str, (usize, usize, usize))> {
+    let max = histogram
+        .values()
+        .map(|(pos, neg, neu)| pos + neg + neu)
+        .max()
+        .unwrap_or(0);
+    let width = |count: usize| {
+        if max <= HISTOGRAM_WIDTH {
+            count
+        } else {
+            (count * HISTOGRAM_WIDTH).div_ceil(max)
+        }
+    };
+    histogram
+        .iter()
+        .map(|(day, &(pos, neg, neu))| (day.as_str(), (width(pos), width(neg), width(neu))))
+        .collect()
+}
+
+/// Describes a `sentiment_trend` value, e.g. "leaning positive".
+pub fn describe_trend(trend: f64) -> &'static str {
+    if trend >= NEUTRAL_TREND {
+        "leaning positive"
+    } else if trend <= -NEUTRAL_TREND {
+        "leaning negative"
+    } else {
+        "neutral"
+    }
+}
+
+fn pearson(pairs: &[(f64, f64)]) -> Option<f64> {
+    if pairs.len() < 2 {
+        return None;
+    }
+    let n = pairs.len() as f64;
+    let mean_x = pairs.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = pairs.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let (mut cov, mut var_x, mut var_y) = (0.0, 0.0, 0.0);
+    for (x, y) in pairs {
+        cov += (x - mean_x) * (y - mean_y);
+        var_x += (x - mean_x).powi(2);
+        var_y += (y - mean_y).powi(2);
+    }
+    if var_x == 0.0 || var_y == 0.0 {
+        return None;
+    }
+    Some(cov / (var_x.sqrt() * var_y.sqrt()))
+}
+
+/// Percent change of the close on `date` versus the previous trading day.
+pub fn daily_change(prices: &[DailyPrice], date: &str) -> Option<f64> {
+    let idx = prices.iter().position(|p| p.date == date)?;
+    let prev = prices[idx.checked_sub(1)?].close;
+    Some(((prices[idx].close - prev) / prev) * 100.0)
+}
+
+/// Fills in each correlation's benchmark and excess (relative) change for
+/// days where both the ticker and the benchmark have a daily change.
+pub fn apply_benchmark(correlations: &mut [Correlation], benchmark: &[DailyPrice]) {
+    for corr in correlations.iter_mut() {
+        corr.benchmark_change = corr
+            .trading_date
+            .as_deref()
+            .and_then(|date| daily_change(benchmark, date));
+        corr.excess_change = match (corr.price_change, corr.benchmark_change) {
+            (Some(change), Some(bench)) => Some(change - bench),
+            _ => None,
+        };
+    }
+}
+
+/// Writes each ticker's correlations as CSV with a header row, one row per
+/// article. Fields containing commas or quotes are quoted.
+pub fn write_correlations_csv<W: io::Write>(
+    writer: W,
+    correlations: &[(&str, Vec<Correlation>)],
+) -> csv::Result<()> {
+    #[derive(Serialize)]
+    struct Row<'a> {
+        ticker: &'a str,
+        date: &'a str,
+        sentiment: Sentiment,
+        price: Option<f64>,
+        price_change: Option<f64>,
+        title: &'a str,
+    }
+
+    let mut csv = csv::Writer::from_writer(writer);
+    for (ticker, corr) in correlations
+        .iter()
+        .flat_map(|(ticker, rows)| rows.iter().map(move |corr| (*ticker, corr)))
+    {
+        csv.serialize(Row {
+            ticker,
+            date: &corr.date,
+            sentiment: corr.sentiment,
+            price: corr.price,
+            price_change: corr.price_change,
+            title: &corr.article_title,
+        })?;
+    }
+    csv.flush()?;
+    Ok(())
+}
+
+/// Aggregates mention sentiment per source feed, most bullish first.
+pub fn sentiment_by_source(mentions: &[ArticleMention]) -> Vec<SourceSentiment> {
+    let mut sources: Vec<SourceSentiment> = Vec::new();
+
+    for mention in mentions {
+        let idx = match sources
+            .iter()
+            .position(|s| s.source == mention.article.source)
+        {
+            Some(idx) => idx,
+            None => {
+                sources.push(SourceSentiment {
+                    source: mention.article.source.clone(),
+                    positive: 0,
+                    negative: 0,
+                    neutral: 0,
+                    net: 0,
+                });
+                sources.len() - 1
+            }
+        };
+
+        let entry = &mut sources[idx];
+        match mention.sentiment {
+            Sentiment::Positive => entry.positive += 1,
+            Sentiment::Negative => entry.negative += 1,
+            Sentiment::Neutral => entry.neutral += 1,
+        }
+        entry.net = entry.positive as i64 - entry.negative as i64;
+    }
+
+    sources.sort_by_key(|s| Reverse(s.net));
+    sources
+}
+
+/// Jaccard similarity of the lowercase word sets of two titles, from 0.0
+/// (no words in common) to 1.0 (same words).
+pub fn title_similarity(a: &str, b: &str) -> f64 {
+    let words = |s: &str| -> HashSet<String> {
+        s.split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .map(|w| w.to_lowercase())
+            .collect()
+    };
+    let (a, b) = (words(a), words(b));
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+/// Collapses mentions of the same ticker whose titles are at least
+/// `threshold` similar, keeping the first and recording the other
+/// articles' sources in its `also_in`.
+pub fn collapse_similar(mentions: Vec<ArticleMention>, threshold: f64) -> Vec<ArticleMention> {
+    let mut kept: Vec<ArticleMention> = Vec::new();
+    for mention in mentions {
+        let duplicate_of = kept.iter_mut().find(|k| {
+            k.ticker == mention.ticker
+                && title_similarity(&k.article.title, &mention.article.title) >= threshold
+        });
+        match duplicate_of {
+            Some(representative) => {
+                let source = mention.article.source;
+                if source != representative.article.source
+                    && !representative.also_in.contains(&source)
+                {
+                    representative.also_in.push(source);
+                }
+            }
+            None => kept.push(mention),
+        }
+    }
+    kept
+}
+
+/// Keeps at most `max` mentions per ticker, preferring the most recently
+/// published. Returns the kept mentions, grouped by ticker in order of first
+/// appearance, and the number dropped for each ticker that overflowed.
+pub fn cap_per_ticker(
+    mentions: Vec<ArticleMention>,
+    max: usize,
+) -> (Vec<ArticleMention>, Vec<(String, usize)>) {
+    let mut by_ticker: Vec<(String, Vec<ArticleMention>)> = Vec::new();
+    for mention in mentions {
+        match by_ticker.iter_mut().find(|(t, _)| *t == mention.ticker) {
+            Some((_, group)) => group.push(mention),
+            None => by_ticker.push((mention.ticker.clone(), vec![mention])),
+        }
+    }
+
+    let mut kept = Vec::new();
+    let mut overflow = Vec::new();
+    for (ticker, mut group) in by_ticker {
+        group.sort_by_key(|m| Reverse(m.article.published));
+        if group.len() > max {
+            overflow.push((ticker, group.len() - max));
+            group.truncate(max);
+        }
+        kept.extend(group);
+    }
+
+    (kept, overflow)
+}
+
+/// Splits mentions into labelled sections, each sorted newest first.
+///
+/// Sentiment sections always come out as Positive, Negative, Neutral (empty
+/// ones included); date sections run newest first; feed and ticker sections
+/// keep the order in which they first appear.
+pub fn group_mentions(
+    mentions: &[ArticleMention],
+    by: GroupBy,
+) -> Vec<(String, Vec<&ArticleMention>)> {
+    let mut groups: Vec<(String, Vec<&ArticleMention>)> = match by {
+        GroupBy::Sentiment => [Sentiment::Positive, Sentiment::Negative, Sentiment::Neutral]
+            .iter()
+            .map(|s| (s.to_string(), Vec::new()))
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    for mention in mentions {
+        let key = match by {
+            GroupBy::Feed => mention.article.source.clone(),
+            GroupBy::Ticker => mention.ticker.clone(),
+            GroupBy::Date => mention
+                .article
+                .published
+                .map_or_else(|| "No date".to_string(), |p| p.date_naive().to_string()),
+            GroupBy::Sentiment => mention.sentiment.to_string(),
+        };
+        match groups.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, members)) => members.push(mention),
+            None => groups.push((key, vec![mention])),
+        }
+    }
+
+    for (_, members) in groups.iter_mut() {
+        // Undated articles sort last since None < Some
+        members.sort_by_key(|m| Reverse(m.article.published));
+    }
+    if by == GroupBy::Date {
+        groups.sort_by(|a, b| {
+            (a.0 == "No date")
+                .cmp(&(b.0 == "No date"))
+                .then_with(|| b.0.cmp(&a.0))
+        });
+    }
+
+    groups
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Trend {
+    Up,
+    Down,
+    Flat,
+}
+
+impl std::fmt::Display for Trend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Trend::Up => write!(f, "uptrend"),
+            Trend::Down => write!(f, "downtrend"),
+            Trend::Flat => write!(f, "flat"),
+        }
+    }
+}
+
+/// Smaller moves over the whole window than this (in percent) count as flat.
+const FLAT_CHANGE_PCT: f64 = 1.0;
+/// Days in the short moving average the latest close is compared against.
+pub const TREND_SMA_DAYS: usize = 7;
+
+/// Where a price series is heading over its whole window.
+#[derive(Debug)]
+pub struct TrendSummary {
+    /// Percent change from the first close to the last
+    pub change: f64,
+    /// Short moving average the latest close is compared against
+    pub sma: f64,
+    pub last_close: f64,
+    pub trend: Trend,
+}
+
+/// Classifies a series as trending when the overall change and the latest
+/// close's position relative to the short moving average agree.
+pub fn trend_summary(prices: &[DailyPrice]) -> Option<TrendSummary> {
+    let change = crate::stock::percent_change(prices)?;
+    let sma = crate::stock::sma(prices, TREND_SMA_DAYS)?;
+    let last_close = prices.last()?.close;
+    let trend = if change >= FLAT_CHANGE_PCT && last_close > sma {
+        Trend::Up
+    } else if change <= -FLAT_CHANGE_PCT && last_close < sma {
+        Trend::Down
+    } else {
+        Trend::Flat
+    };
+    Some(TrendSummary {
+        change,
+        sma,
+        last_close,
+        trend,
+    })
+}
+
+pub fn summarize_reactions(correlations: &[Correlation]) -> ReactionSummary {
+    let count = |sentiment: Sentiment| {
+        correlations
+            .iter()
+            .filter(|c| c.sentiment == sentiment)
+            .count()
+    };
+    let average_change = |sentiment: Sentiment| {
+        let changes: Vec<f64> = correlations
+            .iter()
+            .filter(|c| c.sentiment == sentiment)
+            .filter_map(|c| c.price_change)
+            .collect();
+        if changes.is_empty() {
+            None
+        } else {
+            Some(changes.iter().sum::<f64>() / changes.len() as f64)
+        }
+    };
+
+    let positive = count(Sentiment::Positive);
+    let negative = count(Sentiment::Negative);
+    let neutral = count(Sentiment::Neutral);
+
+    let dominant = if correlations.is_empty() {
+        None
+    } else if positive > negative {
+        Some(Sentiment::Positive)
+    } else if negative > positive {
+        Some(Sentiment::Negative)
+    } else {
+        Some(Sentiment::Neutral)
+    };
+
+    ReactionSummary {
+        positive,
+        negative,
+        neutral,
+        dominant,
+        avg_change_positive: average_change(Sentiment::Positive),
+        avg_change_negative: average_change(Sentiment::Negative),
+    }
+}
+
+/// An article matching a search, with the text around the first match.
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    #[serde(flatten)]
+    pub article: Article,
+    pub snippet: String,
+}
+
+/// Characters of context shown on each side of a search match.
+const SNIPPET_CONTEXT: usize = 40;
+
+/// Finds articles whose title or content matches `query`, ignoring case.
+/// The query is a literal string unless `regex` is set. Content is searched
+/// with its HTML markup stripped.
+pub fn search_articles(
+    articles: &[Article],
+    query: &str,
+    regex: bool,
+) -> Result<Vec<SearchHit>, regex::Error> {
+    let pattern = if regex {
+        query.to_string()
+    } else {
+        regex::escape(query)
+    };
+    let re = regex::RegexBuilder::new(&pattern)
+        .case_insensitive(true)
+        .build()?;
+
+    let hits = articles
+        .iter()
+        .filter_map(|article| {
+            let content = article.content.as_deref().map(crate::feed::strip_html);
+            let snippet = [Some(article.title.as_str()), content.as_deref()]
+                .into_iter()
+                .flatten()
+                .find_map(|text| {
+                    re.find(text)
+                        .map(|m| snippet(text, m.start(), m.end(), SNIPPET_CONTEXT))
+                })?;
+            Some(SearchHit {
+                article: article.clone(),
+                snippet,
+            })
+        })
+        .collect();
+    Ok(hits)
+}
+
+/// The matched text with up to `context` characters either side, on a
+/// single line and marked with ellipses where the text was cut.
+fn snippet(text: &str, start: usize, end: usize, context: usize) -> String {
+    let before: Vec<char> = text[..start].chars().collect();
+    let after: Vec<char> = text[end..].chars().collect();
+    let lead = before.len().saturating_sub(context);
+    let tail = after.len().min(context);
+    let window = format!(
+        "{}{}{}",
+        before[lead..].iter().collect::<String>(),
+        &text[start..end],
+        after[..tail].iter().collect::<String>(),
+    );
+    format!(
+        "{}{}{}",
+        if lead > 0 { "…" } else { "" },
+        window.split_whitespace().collect::<Vec<_>>().join(" "),
+        if tail < after.len() { "…" } else { "" },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn article(title: &str, source: &str, day: Option<u32>) -> Article {
+        Article {
+            id: None,
+            title: title.to_string(),
+            link: None,
+            author: None,
+            categories: Vec::new(),
+            published: day.and_then(|d| Utc.with_ymd_and_hms(2026, 10, d, 12, 0, 0).single()),
+            content: None,
+            enclosures: Vec::new(),
+            source: source.to_string(),
+        }
+    }
+
+    fn mentions_of(articles: &[Article], tickers: &[&str]) -> Vec<ArticleMention> {
+        let investments: Vec<_> = tickers.iter().map(|t| Investment::new(t)).collect();
+        find_mentions(
+            articles,
+            &investments,
+            &TickerBoundaries::default(),
+            &Lexicon::default(),
+            None,
+        )
+    }
+
+    #[test]
+    fn recent_negative_news_outweighs_older_positive_news() {
+        let now = Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap();
+        let articles = [
+            article("AAPL surges", "https://a.com/rss", Some(2)),
+            article("AAPL plunges", "https://a.com/rss", Some(15)),
+        ];
+        let mentions = mentions_of(&articles, &["AAPL"]);
+        let trend = sentiment_trend(&mentions, now, DEFAULT_HALF_LIFE_DAYS).unwrap();
+        assert!(trend < -NEUTRAL_TREND, "{}", trend);
+        assert_eq!(describe_trend(trend), "leaning negative");
+
+        // With a long half-life the two nearly cancel out.
+        let flat = sentiment_trend(&mentions, now, 10_000.0).unwrap();
+        assert!(flat.abs() < 0.01, "{}", flat);
+        assert_eq!(describe_trend(flat), "neutral");
+
+        let undated = mentions_of(
+            &[article("AAPL surges", "https://a.com/rss", None)],
+            &["AAPL"],
+        );
+        assert_eq!(sentiment_trend(&undated, now, DEFAULT_HALF_LIFE_DAYS), None);
+    }
+
+    #[test]
+    fn sentiment_by_source_counts_and_orders_by_net() {
+        let articles = [
+            article("AAPL falls", "https://a.com/rss", None),
+            article("AAPL drops again", "https://a.com/rss", None),
+            article("AAPL surges", "https://b.com/rss", None),
+            article("AAPL holds event", "https://b.com/rss", None),
+            article("AAPL gains", "https://a.com/rss", None),
+        ];
+        let sources = sentiment_by_source(&mentions_of(&articles, &["AAPL"]));
+        let summary: Vec<_> = sources
+            .iter()
+            .map(|s| (s.source.as_str(), s.positive, s.negative, s.neutral, s.net))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("https://b.com/rss", 1, 0, 1, 1),
+                ("https://a.com/rss", 1, 2, 0, -1)
+            ]
+        );
+    }
+
+    fn sections(groups: &[(String, Vec<&ArticleMention>)]) -> Vec<(String, Vec<String>)> {
+        groups
+            .iter()
+            .map(|(key, members)| {
+                let titles = members.iter().map(|m| m.article.title.clone()).collect();
+                (key.clone(), titles)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn group_mentions_by_sentiment_keeps_every_section() {
+        let articles = [
+            article("AAPL falls", "https://a.com/rss", Some(1)),
+            article("AAPL surges", "https://a.com/rss", Some(2)),
+            article("AAPL gains", "https://a.com/rss", Some(3)),
+        ];
+        let mentions = mentions_of(&articles, &["AAPL"]);
+        let groups = sections(&group_mentions(&mentions, GroupBy::Sentiment));
+        let expected: [(&str, &[&str]); 3] = [
+            ("Positive", &["AAPL gains", "AAPL surges"]),
+            ("Negative", &["AAPL falls"]),
+            ("Neutral", &[]),
+        ];
+        assert_eq!(groups.len(), expected.len());
+        for ((key, titles), (expected_key, expected_titles)) in groups.iter().zip(expected) {
+            assert_eq!(key, expected_key);
+            assert_eq!(titles, expected_titles);
+        }
+    }
+
+    #[test]
+    fn group_mentions_by_date_runs_newest_first_with_undated_last() {
+        let articles = [
+            article("AAPL one", "https://a.com/rss", None),
+            article("AAPL two", "https://a.com/rss", Some(1)),
+            article("AAPL three", "https://a.com/rss", Some(5)),
+        ];
+        let mentions = mentions_of(&articles, &["AAPL"]);
+        let keys: Vec<_> = group_mentions(&mentions, GroupBy::Date)
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect();
+        assert_eq!(keys, ["2026-10-05", "2026-10-01", "No date"]);
+    }
+
+    #[test]
+    fn group_mentions_by_ticker_keeps_first_appearance_order() {
+        let articles = [
+            article("MSFT up", "https://a.com/rss", Some(1)),
+            article("AAPL and MSFT", "https://a.com/rss", Some(2)),
+        ];
+        let mentions = mentions_of(&articles, &["AAPL", "MSFT"]);
+        let groups = sections(&group_mentions(&mentions, GroupBy::Ticker));
+        let keys: Vec<_> = groups.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, ["MSFT", "AAPL"]);
+        assert_eq!(groups[0].1, ["AAPL and MSFT", "MSFT up"]);
+    }
+
+    fn price(date: &str, close: f64) -> DailyPrice {
+        DailyPrice {
+            date: date.to_string(),
+            open: None,
+            high: None,
+            low: None,
+            close,
+            volume: None,
+        }
+    }
+
+    fn day(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn summarize_reactions_averages_changes_per_sentiment() {
+        let prices = [
+            price("2026-10-01", 100.0),
+            price("2026-10-02", 110.0),
+            price("2026-10-05", 99.0),
+            price("2026-10-06", 108.9),
+        ];
+        let articles = [
+            article("AAPL surges", "https://a.com/rss", Some(2)),
+            article("AAPL gains", "https://a.com/rss", Some(6)),
+            article("AAPL falls", "https://a.com/rss", Some(5)),
+            article("AAPL holds event", "https://a.com/rss", None),
+        ];
+        let correlations = correlate(&mentions_of(&articles, &["AAPL"]), &prices, 0);
+        let summary = summarize_reactions(&correlations);
+        assert_eq!(
+            (summary.positive, summary.negative, summary.neutral),
+            (2, 1, 1)
+        );
+        assert_eq!(summary.dominant, Some(Sentiment::Positive));
+        assert!((summary.avg_change_positive.unwrap() - 10.0).abs() < 1e-9);
+        assert!((summary.avg_change_negative.unwrap() + 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn summarize_reactions_of_nothing_has_no_dominant_sentiment() {
+        let summary = summarize_reactions(&[]);
+        assert_eq!(summary.dominant, None);
+        assert_eq!(summary.avg_change_positive, None);
+    }
+
+    #[test]
+    fn trading_day_index_skips_weekends() {
+        let prices = [price("2026-10-09", 10.0), price("2026-10-12", 11.0)];
+        assert_eq!(trading_day_index(&prices, day("2026-10-09")), Some(0));
+        // Saturday and Sunday roll over to Monday.
+        assert_eq!(trading_day_index(&prices, day("2026-10-10")), Some(1));
+        assert_eq!(trading_day_index(&prices, day("2026-10-11")), Some(1));
+        assert_eq!(trading_day_index(&prices, day("2026-10-13")), None);
+    }
+
+    #[test]
+    fn trading_day_index_gives_up_after_a_few_days() {
+        let prices = [price("2026-09-01", 10.0), price("2026-10-12", 11.0)];
+        assert_eq!(trading_day_index(&prices, day("2026-10-08")), Some(1));
+        assert_eq!(trading_day_index(&prices, day("2026-10-07")), None);
+        assert_eq!(trading_day_index(&prices, day("2026-09-15")), None);
+    }
+
+    #[test]
+    fn analyze_sentiment_uses_the_default_lexicon() {
+        assert_eq!(
+            analyze_sentiment("Shares surge after profits beat forecasts"),
+            Sentiment::Positive
+        );
+        assert_eq!(
+            analyze_sentiment("Stock plunges as sales sink"),
+            Sentiment::Negative
+        );
+        assert_eq!(
+            analyze_sentiment("The company held its annual meeting"),
+            Sentiment::Neutral
+        );
+    }
+
+    #[test]
+    fn score_grows_with_the_number_of_sentiment_words() {
+        let lexicon = Lexicon::default();
+        let one = score_sentiment("profits surge", &lexicon);
+        let many = score_sentiment("profits surge, gains beat forecasts", &lexicon);
+        assert!(one.score > 0.0);
+        assert!(many.score > one.score);
+        assert!(many.score < 1.0);
+    }
+
+    #[test]
+    fn negation_flips_polarity() {
+        let score = score_sentiment("sales did not surge", &Lexicon::default());
+        assert_eq!((score.positive, score.negative), (0, 1));
+        assert_eq!(score.sentiment(), Sentiment::Negative);
+    }
+
+    #[test]
+    fn ticker_regex_accepts_delimiters_but_not_longer_words() {
+        let regex = ticker_regex("AAPL", &TickerBoundaries::default());
+        for text in [
+            "(AAPL)",
+            "NASDAQ:AAPL",
+            "$AAPL",
+            "AAPL's",
+            "Buy AAPL.",
+            "AAPL",
+        ] {
+            assert!(regex.is_match(text), "{:?} should match", text);
+        }
+        for text in ["AAPLE", "XAAPL", "#AAPL", "AAPL-B"] {
+            assert!(!regex.is_match(text), "{:?} should not match", text);
+        }
+    }
+
+    #[test]
+    fn ticker_regex_uses_configured_boundaries() {
+        let boundaries = TickerBoundaries {
+            before: "#".to_string(),
+            after: String::new(),
+        };
+        let regex = ticker_regex("AAPL", &boundaries);
+        assert!(regex.is_match("#AAPL rallies"));
+        assert!(!regex.is_match("$AAPL rallies"));
+        assert!(!regex.is_match("AAPL."));
+        assert_eq!(&regex.captures("see #AAPL").unwrap()[1], "AAPL");
+    }
+
+    #[test]
+    fn isin_and_cusip_match_the_right_investment() {
+        let apple = Investment {
+            isin: Some("US0378331005".to_string()),
+            ..Investment::new("AAPL")
+        };
+        let microsoft = Investment {
+            cusip: Some("594918104".to_string()),
+            ..Investment::new("MSFT")
+        };
+        let articles = [
+            article("Fund adds us0378331005 to its holdings", "a", None),
+            article("Filing lists 594918104 among top positions", "b", None),
+            article("Filing lists 5949181045 among positions", "c", None),
+        ];
+        let mentions = find_mentions(
+            &articles,
+            &[apple, microsoft],
+            &TickerBoundaries::default(),
+            &Lexicon::default(),
+            None,
+        );
+        let found: Vec<_> = mentions
+            .iter()
+            .map(|m| (m.ticker.as_str(), m.matched_by, m.matched_term.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("AAPL", MatchKind::Isin, "us0378331005"),
+                ("MSFT", MatchKind::Cusip, "594918104")
+            ]
+        );
+    }
+
+    #[test]
+    fn cap_per_ticker_keeps_the_newest_and_counts_the_rest() {
+        let articles = [
+            article("AAPL old", "a", Some(1)),
+            article("MSFT news", "a", Some(2)),
+            article("AAPL newest", "a", Some(5)),
+            article("AAPL newer", "a", Some(3)),
+        ];
+        let mentions = mentions_of(&articles, &["AAPL", "MSFT"]);
+        assert_eq!(mentions.len(), 4);
+
+        let (kept, overflow) = cap_per_ticker(mentions, 2);
+        let titles: Vec<_> = kept.iter().map(|m| m.article.title.as_str()).collect();
+        assert_eq!(titles, ["AAPL newest", "AAPL newer", "MSFT news"]);
+        assert_eq!(overflow, [("AAPL".to_string(), 1)]);
+    }
+
+    #[test]
+    fn title_similarity_ignores_case_and_punctuation() {
+        assert_eq!(title_similarity("Apple beats!", "apple BEATS"), 1.0);
+        assert_eq!(title_similarity("Apple beats", "Tesla misses"), 0.0);
+        assert_eq!(title_similarity("", "..."), 0.0);
+        assert_eq!(title_similarity("a b c", "a b d"), 0.5);
+    }
+
+    #[test]
+    fn near_identical_titles_collapse_but_distinct_ones_do_not() {
+        let articles = [
+            article(
+                "AAPL shares jump after record iPhone sales",
+                "reuters",
+                None,
+            ),
+            article(
+                "AAPL shares jump after record iPhone sales, analysts say",
+                "yahoo",
+                None,
+            ),
+            article("AAPL faces antitrust probe in Europe", "bloomberg", None),
+        ];
+        let collapsed = collapse_similar(mentions_of(&articles, &["AAPL"]), 0.6);
+        let kept: Vec<_> = collapsed
+            .iter()
+            .map(|m| (m.article.source.as_str(), m.also_in.clone()))
+            .collect();
+        assert_eq!(
+            kept,
+            [
+                ("reuters", vec!["yahoo".to_string()]),
+                ("bloomberg", Vec::new())
+            ]
+        );
+    }
+
+    #[test]
+    fn benchmark_gives_excess_change_on_shared_trading_days() {
+        let prices = [
+            price("2026-10-01", 100.0),
+            price("2026-10-02", 110.0),
+            price("2026-10-05", 99.0),
+        ];
+        let benchmark = [price("2026-10-01", 1000.0), price("2026-10-02", 1020.0)];
+        let articles = [
+            article("AAPL surges", "https://a.com/rss", Some(2)),
+            article("AAPL falls", "https://a.com/rss", Some(5)),
+        ];
+        let mut correlations = correlate(&mentions_of(&articles, &["AAPL"]), &prices, 0);
+        apply_benchmark(&mut correlations, &benchmark);
+
+        let first = &correlations[0];
+        assert!((first.benchmark_change.unwrap() - 2.0).abs() < 1e-9);
+        assert!((first.excess_change.unwrap() - 8.0).abs() < 1e-9);
+        // The benchmark has no close for 2026-10-05 to compare against.
+        assert_eq!(correlations[1].benchmark_change, None);
+        assert_eq!(correlations[1].excess_change, None);
+    }
+
+    #[test]
+    fn daily_change_needs_a_previous_close() {
+        let prices = [price("2026-10-01", 50.0), price("2026-10-02", 45.0)];
+        assert_eq!(daily_change(&prices, "2026-10-02"), Some(-10.0));
+        assert_eq!(daily_change(&prices, "2026-10-01"), None);
+        assert_eq!(daily_change(&prices, "2026-10-03"), None);
+    }
+
+    fn with_content(title: &str, content: &str) -> Article {
+        Article {
+            content: Some(content.to_string()),
+            ..article(title, "https://a.com/rss", None)
+        }
+    }
+
+    #[test]
+    fn search_matches_title_or_stripped_content_ignoring_case() {
+        let articles = [
+            with_content("Rust 2.0 released", "Big news"),
+            with_content("Weekly roundup", "<p>Also: <b>RUST</b> conf dates</p>"),
+            with_content("Python news", "Nothing here"),
+        ];
+        let hits = search_articles(&articles, "rust", false).unwrap();
+        let found: Vec<_> = hits
+            .iter()
+            .map(|h| (h.article.title.as_str(), h.snippet.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("Rust 2.0 released", "Rust 2.0 released"),
+                ("Weekly roundup", "Also: RUST conf dates")
+            ]
+        );
+    }
+
+    #[test]
+    fn search_treats_queries_literally_unless_regex() {
+        let articles = [with_content("C++ tips", ""), with_content("Cats", "")];
+        assert_eq!(search_articles(&articles, "c++", false).unwrap().len(), 1);
+        assert_eq!(search_articles(&articles, "^ca", true).unwrap().len(), 1);
+        assert!(search_articles(&articles, "(", true).is_err());
+    }
+
+    #[test]
+    fn snippet_marks_cut_text() {
+        let text = "one two three four five six seven";
+        let start = text.find("four").unwrap();
+        assert_eq!(snippet(text, start, start + 4, 6), "…three four five…");
+        assert_eq!(
+            snippet(text, 0, 3, 100),
+            "one two three four five six seven"
+        );
+    }
+
+    #[test]
+    fn negation_covers_the_requested_phrases() {
+        let lexicon = Lexicon::default();
+        let sentiment = |text| score_sentiment(text, &lexicon).sentiment();
+        assert_eq!(sentiment("not a strong quarter"), Sentiment::Negative);
+        assert_eq!(sentiment("failed to beat estimates"), Sentiment::Negative);
+        assert_eq!(sentiment("Revenue didn't rise"), Sentiment::Negative);
+        assert_eq!(sentiment("shares surged"), Sentiment::Positive);
+        // Too far back to apply.
+        assert_eq!(
+            sentiment("no doubt the company's shares surged"),
+            Sentiment::Positive
+        );
+    }
+
+    #[test]
+    fn custom_words_change_the_outcome() {
+        let text = "The drug got FDA approval";
+        let mut lexicon = Lexicon::default();
+        assert_eq!(
+            score_sentiment(text, &lexicon).sentiment(),
+            Sentiment::Neutral
+        );
+        lexicon.extend(["Approval".to_string()], []);
+        assert_eq!(
+            score_sentiment(text, &lexicon).sentiment(),
+            Sentiment::Positive
+        );
+
+        // Moving a default word to the other list reclassifies it.
+        lexicon.extend([], ["approval".to_string()]);
+        assert_eq!(
+            score_sentiment(text, &lexicon).sentiment(),
+            Sentiment::Negative
+        );
+    }
+
+    #[test]
+    fn find_mentions_counts_cashtags() {
+        let articles = [
+            article("$TSLA jumps", "a", None),
+            article("Buying more TSLA", "a", None),
+            article("TSLAQ is not a ticker we hold", "a", None),
+        ];
+        let mentions = mentions_of(&articles, &["TSLA"]);
+        let found: Vec<_> = mentions
+            .iter()
+            .map(|m| (m.article.title.as_str(), m.matched_term.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [("$TSLA jumps", "TSLA"), ("Buying more TSLA", "TSLA")]
+        );
+    }
+
+    fn strict_mentions(articles: &[Article], investments: &[Investment]) -> Vec<String> {
+        let stoplist = ["ALL".to_string(), "CEO".to_string()];
+        find_mentions(
+            articles,
+            investments,
+            &TickerBoundaries::default(),
+            &Lexicon::default(),
+            Some(&stoplist),
+        )
+        .into_iter()
+        .map(|m| m.article.title)
+        .collect()
+    }
+
+    #[test]
+    fn strict_mode_needs_a_cashtag_or_name_for_ambiguous_tickers() {
+        let allstate = Investment {
+            name: Some("Allstate".to_string()),
+            ..Investment::new("ALL")
+        };
+        let articles = [
+            article("All stocks fell", "a", None),
+            article("$ALL rallies", "a", None),
+            article("ALL: Allstate raises outlook", "a", None),
+        ];
+        assert_eq!(
+            strict_mentions(&articles, &[allstate]),
+            ["$ALL rallies", "ALL: Allstate raises outlook"]
+        );
+        assert_eq!(mentions_of(&articles, &["ALL"]).len(), 3);
+    }
+
+    #[test]
+    fn strict_mode_treats_short_tickers_as_ambiguous() {
+        let articles = [
+            article("It is what it is", "a", None),
+            article("$IT upgraded", "a", None),
+        ];
+        assert_eq!(
+            strict_mentions(&articles, &[Investment::new("IT")]),
+            ["$IT upgraded"]
+        );
+    }
+
+    #[test]
+    fn strict_mode_applies_the_stoplist_to_longer_tickers() {
+        let articles = [
+            article("CEO steps down", "a", None),
+            article("$CEO beats", "a", None),
+            article("AAPL beats", "a", None),
+        ];
+        let investments = [Investment::new("CEO"), Investment::new("AAPL")];
+        assert_eq!(
+            strict_mentions(&articles, &investments),
+            ["$CEO beats", "AAPL beats"]
+        );
+    }
+
+    #[test]
+    fn mention_snippet_surrounds_the_matched_term() {
+        let filler = "Markets were mixed today as investors weighed the latest data. ".repeat(3);
+        let articles = [Article {
+            content: Some(format!(
+                "{}Shares of apple rose after the launch. {}",
+                filler, filler
+            )),
+            ..article("Market wrap", "a", None)
+        }];
+        let apple = Investment {
+            name: Some("Apple".to_string()),
+            ..Investment::new("AAPL")
+        };
+        let mentions = find_mentions(
+            &articles,
+            &[apple],
+            &TickerBoundaries::default(),
+            &Lexicon::default(),
+            None,
+        );
+        let mention = &mentions[0];
+        assert_eq!(mention.matched_by, MatchKind::Name);
+        assert_eq!(mention.matched_term, "apple");
+        assert!(mention.snippet.contains("Shares of apple rose"));
+        assert!(mention.snippet.starts_with('…') && mention.snippet.ends_with('…'));
+        assert!(mention.snippet.chars().count() <= 2 * MENTION_CONTEXT + "apple".len() + 2);
+    }
+
+    #[test]
+    fn matched_term_survives_length_changing_uppercase() {
+        let articles = [article("Straße report: AAPL's outlook", "a", None)];
+        let mentions = mentions_of(&articles, &["AAPL"]);
+        assert_eq!(mentions[0].matched_term, "AAPL");
+        assert!(mentions[0].snippet.starts_with("Straße"));
+    }
+
+    #[test]
+    fn correlations_are_written_as_csv() {
+        let row = |title: &str, sentiment, price, price_change| Correlation {
+            date: "2026-10-02".to_string(),
+            trading_date: Some("2026-10-02".to_string()),
+            article_title: title.to_string(),
+            sentiment,
+            price,
+            price_change,
+            benchmark_change: None,
+            excess_change: None,
+        };
+        let correlations = [
+            (
+                "AAPL",
+                vec![row(
+                    "AAPL surges, analysts cheer",
+                    Sentiment::Positive,
+                    Some(102.0),
+                    Some(2.0),
+                )],
+            ),
+            (
+                "MSFT",
+                vec![row("No price yet", Sentiment::Neutral, None, None)],
+            ),
+        ];
+        let mut out = Vec::new();
+        write_correlations_csv(&mut out, &correlations).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "ticker,date,sentiment,price,price_change,title",
+                "AAPL,2026-10-02,Positive,102.0,2.0,\"AAPL surges, analysts cheer\"",
+                "MSFT,2026-10-02,Neutral,,,No price yet",
+            ]
+        );
+    }
+
+    /// Prices rising 2% on 2026-10-02 and falling 2% on 2026-10-05, with
+    /// one article on each of those days.
+    fn two_day_moves(first: &str, second: &str) -> Option<f64> {
+        let prices = [
+            price("2026-10-01", 100.0),
+            price("2026-10-02", 102.0),
+            price("2026-10-05", 99.96),
+        ];
+        let articles = [
+            article(first, "https://a.com/rss", Some(2)),
+            article(second, "https://a.com/rss", Some(5)),
+        ];
+        pearson_correlation(&mentions_of(&articles, &["AAPL"]), &prices, 0)
+    }
+
+    #[test]
+    fn pearson_is_one_when_sentiment_matches_moves() {
+        let r = two_day_moves("AAPL surges", "AAPL plunges").unwrap();
+        assert!((r - 1.0).abs() < 1e-9, "{}", r);
+    }
+
+    #[test]
+    fn pearson_is_minus_one_when_sentiment_opposes_moves() {
+        let r = two_day_moves("AAPL plunges", "AAPL surges").unwrap();
+        assert!((r + 1.0).abs() < 1e-9, "{}", r);
+    }
+
+    #[test]
+    fn pearson_needs_varying_pairs() {
+        assert_eq!(two_day_moves("AAPL surges", "AAPL soars"), None);
+        assert_eq!(pearson(&[(1.0, 2.0)]), None);
+        assert_eq!(pearson(&[]), None);
+    }
+
+    #[test]
+    fn correlate_pairs_articles_with_their_utc_trading_day() {
+        let prices = [price("2026-10-01", 100.0), price("2026-10-02", 105.0)];
+        let mut late = article("AAPL surges", "a", None);
+        late.published = "2026-10-01T22:30:00-04:00".parse().ok();
+        let mut early = article("AAPL rises", "a", None);
+        early.published = "2026-10-02T09:30:00Z".parse().ok();
+        let correlations = correlate(&mentions_of(&[late, early], &["AAPL"]), &prices, 0);
+        let days: Vec<_> = correlations
+            .iter()
+            .map(|c| (c.date.as_str(), c.trading_date.as_deref(), c.price_change))
+            .collect();
+        assert_eq!(
+            days,
+            [
+                ("2026-10-02", Some("2026-10-02"), Some(5.0)),
+                ("2026-10-02", Some("2026-10-02"), Some(5.0))
+            ]
+        );
+    }
+
+    #[test]
+    fn histogram_bars_scale_only_past_the_width() {
+        let mut histogram = BTreeMap::new();
+        histogram.insert("2026-10-01".to_string(), (3, 1, 0));
+        histogram.insert("2026-10-02".to_string(), (0, 0, 2));
+        assert_eq!(
+            histogram_bars(&histogram),
+            [("2026-10-01", (3, 1, 0)), ("2026-10-02", (0, 0, 2))]
+        );
+
+        histogram.insert("2026-10-03".to_string(), (HISTOGRAM_WIDTH * 2, 0, 0));
+        assert_eq!(
+            histogram_bars(&histogram),
+            [
+                ("2026-10-01", (2, 1, 0)),
+                ("2026-10-02", (0, 0, 1)),
+                ("2026-10-03", (HISTOGRAM_WIDTH, 0, 0))
+            ]
+        );
+    }
+
+    #[test]
+    fn correlate_with_a_lag_uses_the_move_that_many_days_later() {
+        let prices = [
+            price("2026-10-01", 100.0),
+            price("2026-10-02", 105.0),
+            price("2026-10-05", 94.5),
+        ];
+        let articles = [article("AAPL surges", "a", Some(1))];
+        let correlations = correlate(&mentions_of(&articles, &["AAPL"]), &prices, 1);
+        assert_eq!(correlations[0].trading_date.as_deref(), Some("2026-10-02"));
+        assert_eq!(correlations[0].price_change, Some(5.0));
+    }
+
+    #[test]
+    fn correlate_rolls_a_lagged_weekend_over_to_monday() {
+        let prices = [
+            price("2026-10-01", 100.0),
+            price("2026-10-02", 105.0),
+            price("2026-10-05", 94.5),
+        ];
+        // Friday plus one day is Saturday 2026-10-03.
+        let articles = [article("AAPL surges", "a", Some(2))];
+        let correlations = correlate(&mentions_of(&articles, &["AAPL"]), &prices, 1);
+        assert_eq!(correlations[0].trading_date.as_deref(), Some("2026-10-05"));
+        assert!((correlations[0].price_change.unwrap() + 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn correlate_with_an_out_of_range_lag_has_no_price() {
+        let prices = [price("2026-10-01", 100.0), price("2026-10-02", 105.0)];
+        let articles = [article("AAPL surges", "a", Some(1))];
+        for lag in [i64::MAX, i64::MIN] {
+            let correlations = correlate(&mentions_of(&articles, &["AAPL"]), &prices, lag);
+            assert_eq!(correlations[0].price_change, None);
+        }
+    }
+
+    fn series(closes: &[f64]) -> Vec<DailyPrice> {
+        closes
+            .iter()
+            .enumerate()
+            .map(|(i, &close)| price(&format!("2026-10-{:02}", i + 1), close))
+            .collect()
+    }
+
+    #[test]
+    fn trend_summary_classifies_up_down_and_flat() {
+        let trend = |closes: &[f64]| trend_summary(&series(closes)).map(|t| t.trend);
+        assert_eq!(trend(&[100.0, 101.0, 103.0, 106.0]), Some(Trend::Up));
+        assert_eq!(trend(&[100.0, 98.0, 96.0, 94.0]), Some(Trend::Down));
+        // Small overall change.
+        assert_eq!(trend(&[100.0, 100.2, 100.5]), Some(Trend::Flat));
+        // Up over the window but back below the moving average.
+        assert_eq!(
+            trend(&[100.0, 120.0, 130.0, 125.0, 110.0]),
+            Some(Trend::Flat)
+        );
+        assert_eq!(trend(&[]), None);
+    }
+}
@@ -0,0 +1,70 @@
+use crate::feed::FeedResult;
+use crate::storage;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The last successfully fetched copy of a feed with the validators needed
+/// to ask the server whether it has changed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedFeed {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+    pub result: FeedResult,
+    /// When the feed was last fetched or revalidated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_fetched: Option<DateTime<Utc>>,
+    /// Set on the copy handed back for a `304 Not Modified`; never stored
+    #[serde(skip)]
+    pub not_modified: bool,
+}
+
+/// Fetched feeds persisted in `cache.json` next to the config, keyed by URL.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct FeedCache {
+    feeds: HashMap<String, CachedFeed>,
+}
+
+impl FeedCache {
+    pub fn load(dir: &Path) -> io::Result<Self> {
+        let path = cache_path(dir);
+        if !path.exists() {
+            return Ok(FeedCache::default());
+        }
+        let content = fs::read_to_string(&path)?;
+        serde_json::from_str(&content).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Writes the cache to a temporary file and renames it into place.
+    pub fn save(&self, dir: &Path) -> io::Result<()> {
+        let path = cache_path(dir);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string(self)?;
+        storage::write_atomic(&path, content.as_bytes())
+    }
+
+    pub fn get(&self, url: &str) -> Option<&CachedFeed> {
+        self.feeds.get(url)
+    }
+
+    pub fn insert(&mut self, url: &str, feed: CachedFeed) {
+        self.feeds.insert(url.to_string(), feed);
+    }
+
+    /// Every cached feed with its URL.
+    #[cfg(feature = "sqlite")]
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &CachedFeed)> {
+        self.feeds.iter().map(|(url, feed)| (url.as_str(), feed))
+    }
+}
+
+fn cache_path(dir: &Path) -> PathBuf {
+    dir.join("cache.json")
+}
@@ -0,0 +1,108 @@
+use crate::history::MentionRecord;
+use futures::stream::{self, StreamExt};
+use tokio::process::Command;
+
+/// Maximum number of hook processes running at once.
+const EXEC_CONCURRENCY: usize = 4;
+
+/// Runs `command` through the shell once per mention, passing the mention's
+/// details as environment variables:
+///
+/// - `AARON_RSS_TICKER`
+/// - `AARON_RSS_SENTIMENT`
+/// - `AARON_RSS_TITLE`
+/// - `AARON_RSS_LINK` (empty if the article has no link)
+/// - `AARON_RSS_DATE` (empty if the article has no publish date)
+///
+/// Failures are reported on stderr and don't stop the remaining runs.
+pub async fn run_for_mentions(command: &str, mentions: &[MentionRecord]) {
+    stream::iter(mentions)
+        .for_each_concurrent(EXEC_CONCURRENCY, |mention| async move {
+            let result = shell(command)
+                .env("AARON_RSS_TICKER", &mention.ticker)
+                .env("AARON_RSS_SENTIMENT", mention.sentiment.to_string())
+                .env("AARON_RSS_TITLE", &mention.title)
+                .env("AARON_RSS_LINK", mention.link.as_deref().unwrap_or(""))
+                .env("AARON_RSS_DATE", mention.published.as_deref().unwrap_or(""))
+                .status()
+                .await;
+            match result {
+                Ok(status) if !status.success() => eprintln!(
+                    "Command for [{}] {} exited with {}",
+                    mention.ticker, mention.title, status
+                ),
+                Ok(_) => {}
+                Err(e) => eprintln!("Error running command for [{}]: {}", mention.ticker, e),
+            }
+        })
+        .await;
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(command);
+    cmd
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.arg("/C").arg(command);
+    cmd
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::analysis::Sentiment;
+
+    fn record(ticker: &str, title: &str, link: Option<&str>) -> MentionRecord {
+        MentionRecord {
+            ticker: ticker.to_string(),
+            sentiment: Sentiment::Positive,
+            title: title.to_string(),
+            link: link.map(str::to_string),
+            published: Some("2026-10-16 09:30".to_string()),
+            recorded: "2026-10-16".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn passes_each_mention_in_the_environment() {
+        let log = rss_reader::test_dir("exec-env").join("calls.log");
+        let command = format!(
+            r#"printf '%s|%s|%s|%s|%s\n' "$AARON_RSS_TICKER" "$AARON_RSS_SENTIMENT" "$AARON_RSS_TITLE" "$AARON_RSS_LINK" "$AARON_RSS_DATE" >> '{}'"#,
+            log.display()
+        );
+        let mentions = [
+            record("AAPL", "Apple's \"big\" day", Some("https://example.com/a")),
+            record("MSFT", "Microsoft update", None),
+        ];
+        run_for_mentions(&command, &mentions).await;
+
+        let content = std::fs::read_to_string(&log).unwrap();
+        let mut calls: Vec<_> = content.lines().collect();
+        calls.sort();
+        assert_eq!(
+            calls,
+            [
+                "AAPL|Positive|Apple's \"big\" day|https://example.com/a|2026-10-16 09:30",
+                "MSFT|Positive|Microsoft update||2026-10-16 09:30"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn failing_commands_do_not_stop_the_rest() {
+        let log = rss_reader::test_dir("exec-failures").join("calls.log");
+        let command = format!(r#"echo "$AARON_RSS_TICKER" >> '{}'; exit 3"#, log.display());
+        let mentions: Vec<_> = ["A", "B", "C", "D", "E", "F"]
+            .iter()
+            .map(|t| record(t, "Title", None))
+            .collect();
+        run_for_mentions(&command, &mentions).await;
+        let content = std::fs::read_to_string(&log).unwrap();
+        assert_eq!(content.lines().count(), mentions.len());
+    }
+}
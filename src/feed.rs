@@ -1,24 +1,587 @@
+use crate::cache::CachedFeed;
+use crate::http::{HttpOptions, HttpTransport, Transport, TransportError};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, TimeDelta, Utc, Weekday};
+use encoding_rs::{Encoding, WINDOWS_1252};
 use feed_rs::parser;
+use futures::stream::{self, StreamExt};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashSet;
 use std::error::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::LazyLock;
+use std::time::Duration;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Article {
+    /// The entry's own guid/id, or its link when the feed gives none
+    #[serde(default)]
+    pub id: Option<String>,
     pub title: String,
     pub link: Option<String>,
-    pub published: Option<String>,
+    /// Authors' names, comma-separated
+    #[serde(default)]
+    pub author: Option<String>,
+    /// Category/tag labels the feed gives the entry
+    #[serde(default)]
+    pub categories: Vec<String>,
+    pub published: Option<DateTime<Utc>>,
     pub content: Option<String>,
+    /// Attached media such as podcast episodes
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub enclosures: Vec<Enclosure>,
+    /// URL of the feed this article came from
+    #[serde(rename = "feed", alias = "source")]
+    pub source: String,
+}
+
+/// A media file attached to an entry: an RSS `<enclosure>`, Media RSS
+/// content or an Atom link with `rel="enclosure"`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Enclosure {
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    /// Size in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub length: Option<u64>,
 }
 
-#[derive(Debug)]
+impl Enclosure {
+    /// Audio or video, or of unknown type (podcast feeds don't always say).
+    pub fn is_playable(&self) -> bool {
+        self.mime_type
+            .as_deref()
+            .is_none_or(|m| m.starts_with("audio/") || m.starts_with("video/"))
+    }
+}
+
+/// How publish times are shown to the user.
+pub const PUBLISHED_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+impl Article {
+    /// Publish time formatted for display, if known.
+    pub fn published_display(&self) -> Option<String> {
+        self.published
+            .map(|p| p.format(PUBLISHED_FORMAT).to_string())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FeedResult {
     pub title: String,
     pub articles: Vec<Article>,
+    /// Where the feed was served from after redirects; empty for copies
+    /// cached before this was recorded
+    #[serde(default)]
+    pub final_url: String,
+    /// Problems with individual entries that were skipped or patched up
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+/// Summary of a fetched feed shown by `list --stats`.
+#[derive(Debug, PartialEq)]
+pub struct FeedStats {
+    pub title: String,
+    pub articles: usize,
+    /// Publish date of the newest article; None if no article is dated
+    pub newest: Option<DateTime<Utc>>,
+}
+
+impl FeedStats {
+    pub fn from_result(result: &FeedResult) -> Self {
+        FeedStats {
+            title: result.title.clone(),
+            articles: result.articles.len(),
+            newest: result.articles.iter().filter_map(|a| a.published).max(),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum FeedError {
+    #[error("network error: {0}")]
+    Network(String),
+    #[error("timed out after {}s", .0.as_secs())]
+    Timeout(Duration),
+    /// Unexpected HTTP status
+    #[error("HTTP status {0}")]
+    Http(u16),
+    /// HTTP 429
+    #[error("rate limited (HTTP 429)")]
+    RateLimited,
+    /// HTTP 404 or 410
+    #[error("feed not found")]
+    NotFound,
+    /// The body is not a valid RSS/Atom feed
+    #[error("invalid feed: {0}")]
+    Parse(String),
+    /// The server answered with an empty body
+    #[error("empty response")]
+    Empty,
+    /// The URL is an HTML page; holds the feed URLs it advertises
+    #[error("{}", describe_html_page(.0))]
+    HtmlPage(Vec<String>),
+}
+
+fn describe_html_page(candidates: &[String]) -> String {
+    if candidates.is_empty() {
+        "this is an HTML page that links to no feeds".to_string()
+    } else {
+        format!(
+            "this is an HTML page linking to feeds: {}",
+            candidates.join(", ")
+        )
+    }
+}
+
+impl FeedError {
+    fn from_status(status: u16) -> Self {
+        match status {
+            404 | 410 => FeedError::NotFound,
+            429 => FeedError::RateLimited,
+            _ => FeedError::Http(status),
+        }
+    }
+}
+
+impl FeedResult {
+    /// The URL the feed moved to, if fetching `url` was redirected.
+    pub fn redirected_to(&self, url: &str) -> Option<&str> {
+        let moved = !self.final_url.is_empty() && !same_feed_url(&self.final_url, url);
+        moved.then_some(self.final_url.as_str())
+    }
+}
+
+/// How a feed fared when checked by `list --validate`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FeedHealth {
+    Ok,
+    /// HTTP 404 or 410: the feed no longer exists
+    Gone,
+    /// Any other unsuccessful HTTP status, including rate limiting
+    HttpError,
+    /// The response isn't a feed, or is empty
+    ParseError,
+    /// Network error or timeout
+    Unreachable,
+}
+
+impl FeedHealth {
+    pub const ALL: [FeedHealth; 5] = [
+        FeedHealth::Ok,
+        FeedHealth::Gone,
+        FeedHealth::HttpError,
+        FeedHealth::ParseError,
+        FeedHealth::Unreachable,
+    ];
+
+    pub fn classify(result: &Result<FeedResult, FeedError>) -> Self {
+        match result {
+            Ok(_) => FeedHealth::Ok,
+            Err(FeedError::NotFound) => FeedHealth::Gone,
+            Err(FeedError::Http(_) | FeedError::RateLimited) => FeedHealth::HttpError,
+            Err(FeedError::Parse(_) | FeedError::Empty | FeedError::HtmlPage(_)) => {
+                FeedHealth::ParseError
+            }
+            Err(FeedError::Network(_) | FeedError::Timeout(_)) => FeedHealth::Unreachable,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FeedHealth::Ok => "OK",
+            FeedHealth::Gone => "gone",
+            FeedHealth::HttpError => "HTTP error",
+            FeedHealth::ParseError => "parse error",
+            FeedHealth::Unreachable => "unreachable",
+        }
+    }
 }
 
-pub async fn fetch_feed(url: &str) -> Result<FeedResult, Box<dyn Error>> {
-    let response = reqwest::get(url).await?;
-    let bytes = response.bytes().await?;
-    let feed = parser::parse(&bytes[..])?;
+#[derive(Debug, PartialEq)]
+pub enum FeedUrlError {
+    Invalid(url::ParseError),
+    /// Anything other than http or https
+    UnsupportedScheme(String),
+    MissingHost,
+}
+
+impl fmt::Display for FeedUrlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeedUrlError::Invalid(url::ParseError::RelativeUrlWithoutBase) => {
+                write!(f, "not a URL (expected e.g. https://example.com/feed.xml)")
+            }
+            FeedUrlError::Invalid(e) => write!(f, "invalid URL: {}", e),
+            FeedUrlError::UnsupportedScheme(scheme) => {
+                write!(f, "unsupported scheme '{}' (use http or https)", scheme)
+            }
+            FeedUrlError::MissingHost => write!(f, "URL has no host"),
+        }
+    }
+}
+
+impl Error for FeedUrlError {}
+
+/// Checks that `s` is an http(s) URL with a host and returns it normalized:
+/// scheme and host lowercased, an empty path written as `/` and any
+/// fragment dropped, so equivalent spellings compare equal.
+pub fn normalize_feed_url(s: &str) -> Result<String, FeedUrlError> {
+    let mut url = url::Url::parse(s.trim()).map_err(FeedUrlError::Invalid)?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(FeedUrlError::UnsupportedScheme(url.scheme().to_string()));
+    }
+    if url.host_str().is_none_or(str::is_empty) {
+        return Err(FeedUrlError::MissingHost);
+    }
+    url.set_fragment(None);
+    Ok(url.into())
+}
+
+/// Whether two feed URLs are the same once normalized. URLs that don't
+/// parse are compared as written.
+pub fn same_feed_url(a: &str, b: &str) -> bool {
+    match (normalize_feed_url(a), normalize_feed_url(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+impl From<TransportError> for FeedError {
+    fn from(e: TransportError) -> Self {
+        match e {
+            TransportError::Timeout(after) => FeedError::Timeout(after),
+            TransportError::Network(msg) => FeedError::Network(msg),
+        }
+    }
+}
+
+/// Number of articles kept per feed unless a caller asks for more.
+pub const DEFAULT_ARTICLE_LIMIT: usize = 10;
+
+/// Characters of an article's content kept unless [`FetchOptions`] say
+/// otherwise.
+pub const DEFAULT_MAX_CONTENT_LEN: usize = 10_000;
+
+/// How fetched feeds are processed.
+#[derive(Debug, Clone)]
+pub struct FetchOptions {
+    /// Characters of article content kept, as by [`truncate_content`]; 0
+    /// keeps it all
+    pub max_content_len: usize,
+    /// Headers sent with every feed request, after the browser-like
+    /// defaults and before each feed's own
+    pub headers: Vec<(String, String)>,
+}
+
+impl Default for FetchOptions {
+    fn default() -> Self {
+        FetchOptions {
+            max_content_len: DEFAULT_MAX_CONTENT_LEN,
+            headers: Vec::new(),
+        }
+    }
+}
+
+/// Shortens `content` to at most `max` characters, ending it with an
+/// ellipsis when anything was cut. A `max` of 0 leaves it alone.
+///
+/// ```
+/// use rss_reader::feed::truncate_content;
+///
+/// let mut content = "Café déjà vu".to_string();
+/// truncate_content(&mut content, 5);
+/// assert_eq!(content, "Café…");
+/// ```
+pub fn truncate_content(content: &mut String, max: usize) {
+    if max == 0 {
+        return;
+    }
+    let mut chars = content.char_indices();
+    let Some((cut, _)) = chars.nth(max - 1) else {
+        return;
+    };
+    if chars.next().is_none() {
+        return;
+    }
+    content.truncate(cut);
+    content.push('…');
+}
+
+/// Fetches and parses a feed, sending `headers` (e.g. from
+/// `Feed::request_headers`) on top of [`request_headers`], and keeps at most
+/// `limit` entries (all of them when `limit` is None).
+///
+/// Requests go through `transport`, so tests can serve canned documents:
+///
+/// ```
+/// use async_trait::async_trait;
+/// use rss_reader::feed::{fetch_feed_with, FetchOptions};
+/// use rss_reader::http::{Response, Transport, TransportError};
+///
+/// struct Canned(&'static str);
+///
+/// #[async_trait]
+/// impl Transport for Canned {
+///     async fn get(&self, url: &str, _: &[(&str, &str)]) -> Result<Response, TransportError> {
+///         Ok(Response {
+///             url: url.to_string(),
+///             status: 200,
+///             headers: Vec::new(),
+///             body: self.0.as_bytes().to_vec(),
+///         })
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let rss = Canned(
+///     r#"<rss version="2.0"><channel><title>RSS</title>
+///         <item><title>First</title></item><item><title>Second</title></item>
+///       </channel></rss>"#,
+/// );
+/// let atom = Canned(
+///     r#"<feed xmlns="http://www.w3.org/2005/Atom"><title>Atom</title>
+///         <entry><id>1</id><title>Only</title><updated>2025-01-06T14:00:00Z</updated></entry>
+///       </feed>"#,
+/// );
+/// let json = Canned(
+///     r#"{"version": "https://jsonfeed.org/version/1.1", "title": "JSON",
+///         "items": [{"id": "1", "title": "Item", "content_text": "Body"}]}"#,
+/// );
+///
+/// let options = FetchOptions::default();
+/// let result = fetch_feed_with(&rss, "https://example.com/rss", &[], &options, Some(1)).await.unwrap();
+/// assert_eq!((result.title.as_str(), result.articles.len()), ("RSS", 1));
+/// let result = fetch_feed_with(&atom, "https://example.com/atom", &[], &options, None).await.unwrap();
+/// assert_eq!(result.articles[0].title, "Only");
+/// let result = fetch_feed_with(&json, "https://example.com/json", &[], &options, None).await.unwrap();
+/// assert_eq!(result.articles[0].content.as_deref(), Some("Body"));
+/// # }
+/// ```
+pub async fn fetch_feed_with(
+    transport: &dyn Transport,
+    url: &str,
+    headers: &[(String, String)],
+    options: &FetchOptions,
+    limit: Option<usize>,
+) -> Result<FeedResult, FeedError> {
+    let mut result = fetch_feed_conditional(transport, url, headers, options, None)
+        .await?
+        .result;
+    if let Some(limit) = limit {
+        result.articles.truncate(limit);
+    }
+    Ok(result)
+}
+
+/// Headers sent with every feed request unless replaced by the user's own.
+/// Some CDNs turn away requests that don't look like they come from a
+/// browser.
+const BROWSER_HEADERS: &[(&str, &str)] = &[
+    (
+        "Accept",
+        "application/rss+xml, application/atom+xml, application/feed+json, \
+         application/xml;q=0.9, text/xml;q=0.9, */*;q=0.8",
+    ),
+    ("Accept-Language", "en-US,en;q=0.9"),
+];
+
+/// The headers for a request to the feed at `url`: browser-like `Accept`
+/// and `Accept-Language` values, a `Referer` of the site's home page, then
+/// `extra` (for every feed) and finally `feed_headers`. A later header
+/// replaces an earlier one with the same name.
+///
+/// ```
+/// use rss_reader::feed::request_headers;
+///
+/// let feed_headers = [("accept-language".to_string(), "de-DE".to_string())];
+/// let headers = request_headers("https://example.com/news/feed.xml", &[], &feed_headers);
+/// let get = |name: &str| {
+///     headers.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, v)| v.as_str())
+/// };
+/// assert_eq!(get("Accept-Language"), Some("de-DE"));
+/// assert_eq!(get("Referer"), Some("https://example.com/"));
+/// assert!(get("Accept").is_some_and(|v| v.starts_with("application/rss+xml")));
+/// assert_eq!(headers.len(), 3);
+/// ```
+pub fn request_headers(
+    url: &str,
+    extra: &[(String, String)],
+    feed_headers: &[(String, String)],
+) -> Vec<(String, String)> {
+    let mut headers: Vec<(String, String)> = BROWSER_HEADERS
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+    let origin = url::Url::parse(url).ok().map(|url| url.origin());
+    if let Some(origin) = origin.filter(|o| o.is_tuple()) {
+        headers.push((
+            "Referer".to_string(),
+            format!("{}/", origin.ascii_serialization()),
+        ));
+    }
+    for (name, value) in extra.iter().chain(feed_headers) {
+        headers.retain(|(n, _)| !n.eq_ignore_ascii_case(name));
+        headers.push((name.clone(), value.clone()));
+    }
+    headers
+}
+
+/// Fetches every entry of the feed at `url` over a fresh HTTP client with
+/// the default options. Callers making more than one request should build an
+/// `HttpTransport` once and use [`fetch_feed_with`], so connections are
+/// reused.
+pub async fn fetch_feed(url: &str) -> Result<FeedResult, FeedError> {
+    let transport = HttpTransport::new(&HttpOptions::default())
+        .map_err(|e| FeedError::Network(e.to_string()))?;
+    fetch_feed_with(&transport, url, &[], &FetchOptions::default(), None).await
+}
+
+/// Fetches every entry of a feed. When a previous copy is given its
+/// validators are sent along, and on `304 Not Modified` that copy is returned
+/// instead of parsing again.
+///
+/// feed_rs detects the format from the body, so RSS, Atom and JSON Feed
+/// (jsonfeed.org 1.0/1.1) documents all come through the same path; JSON
+/// Feed's `content_text`/`content_html` end up in `Article::content`.
+pub async fn fetch_feed_conditional(
+    transport: &dyn Transport,
+    url: &str,
+    headers: &[(String, String)],
+    options: &FetchOptions,
+    cached: Option<&CachedFeed>,
+) -> Result<CachedFeed, FeedError> {
+    let headers = request_headers(url, &options.headers, headers);
+    let mut headers: Vec<(&str, &str)> = headers
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_str()))
+        .collect();
+    if let Some(cached) = cached {
+        if let Some(etag) = &cached.etag {
+            headers.push(("If-None-Match", etag.as_str()));
+        }
+        if let Some(last_modified) = &cached.last_modified {
+            headers.push(("If-Modified-Since", last_modified.as_str()));
+        }
+    }
+
+    let response = transport.get(url, &headers).await?;
+    if let (304, Some(cached)) = (response.status, cached) {
+        return Ok(CachedFeed {
+            last_fetched: Some(Utc::now()),
+            not_modified: true,
+            ..cached.clone()
+        });
+    }
+    if !response.is_success() {
+        return Err(FeedError::from_status(response.status));
+    }
+    let body = to_utf8(
+        &response.body,
+        response.header("Content-Type").and_then(charset),
+    );
+    let result = parse_feed(&body, url, options.max_content_len)?;
+    Ok(CachedFeed {
+        etag: response.header("ETag").map(str::to_string),
+        last_modified: response.header("Last-Modified").map(str::to_string),
+        result: FeedResult {
+            final_url: response.url.clone(),
+            ..result
+        },
+        last_fetched: Some(Utc::now()),
+        not_modified: false,
+    })
+}
+
+/// The `charset` parameter of a Content-Type header value, e.g. `ISO-8859-1`
+/// in `text/xml; charset="ISO-8859-1"`.
+fn charset(content_type: &str) -> Option<&str> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("charset")
+            .then(|| value.trim().trim_matches('"'))
+    })
+}
+
+/// Length of the XML declaration the document starts with, when that
+/// declaration names the document's encoding, which the feed parser honors
+/// by itself.
+fn encoding_declaration_len(body: &[u8]) -> Option<usize> {
+    let rest = body.strip_prefix(b"<?xml")?;
+    let end = rest.windows(2).position(|w| w == b"?>")?;
+    rest[..end]
+        .windows(9)
+        .any(|w| w == b"encoding=")
+        .then_some(b"<?xml".len() + end + b"?>".len())
+}
+
+/// Transcodes a feed body to UTF-8. A `charset` from the Content-Type
+/// header wins over anything the document says about itself: the body is
+/// decoded with it and an XML encoding declaration is dropped, so the feed
+/// parser doesn't decode it a second time. Without one, UTF-8 bodies, those
+/// with a byte order mark and those with an XML encoding declaration are
+/// returned unchanged, and anything else is read as Windows-1252, the usual
+/// superset of ISO-8859-1.
+///
+/// ```
+/// use rss_reader::feed::to_utf8;
+///
+/// let latin1 = b"<rss><channel><title>Caf\xe9 \xe0 Z\xfcrich</title></channel></rss>";
+/// assert_eq!(
+///     to_utf8(latin1, Some("ISO-8859-1")).as_ref(),
+///     "<rss><channel><title>Café à Zürich</title></channel></rss>".as_bytes()
+/// );
+/// assert_eq!(to_utf8("Café".as_bytes(), None).as_ref(), "Café".as_bytes());
+/// ```
+pub fn to_utf8<'a>(body: &'a [u8], charset: Option<&str>) -> Cow<'a, [u8]> {
+    let from_header = charset.and_then(|label| Encoding::for_label(label.as_bytes()));
+    if let Some(encoding) = from_header.filter(|_| Encoding::for_bom(body).is_none()) {
+        let (text, _, _) = encoding.decode(body);
+        return match (encoding_declaration_len(text.as_bytes()), text) {
+            (Some(len), text) => Cow::Owned(text.as_bytes()[len..].to_vec()),
+            (None, Cow::Borrowed(_)) => Cow::Borrowed(body),
+            (None, Cow::Owned(text)) => Cow::Owned(text.into_bytes()),
+        };
+    }
+    if std::str::from_utf8(body).is_ok()
+        || encoding_declaration_len(body).is_some()
+        || Encoding::for_bom(body).is_some()
+    {
+        return Cow::Borrowed(body);
+    }
+    let (text, _, _) = WINDOWS_1252.decode(body);
+    Cow::Owned(text.into_owned().into_bytes())
+}
+
+/// Parses a feed document fetched from (or standing in for) `url`, which is
+/// recorded as each article's source, cutting article content at
+/// `max_content_len` characters. Bodies in other encodings than UTF-8 are
+/// transcoded as by [`to_utf8`].
+pub fn parse_feed(body: &[u8], url: &str, max_content_len: usize) -> Result<FeedResult, FeedError> {
+    if body.trim_ascii().is_empty() {
+        return Err(FeedError::Empty);
+    }
+    let body = to_utf8(body, None);
+    let (feed, mut warnings) = match parse_document(body.as_ref()) {
+        Ok(feed) => (feed, Vec::new()),
+        Err(e) => {
+            let body = String::from_utf8_lossy(&body);
+            if looks_like_html(&body) {
+                return Err(FeedError::HtmlPage(discover_feeds(&body, url)));
+            }
+            match salvage_feed(&body) {
+                Some((feed, warnings)) if !feed.entries.is_empty() => (feed, warnings),
+                _ => return Err(FeedError::Parse(e.to_string())),
+            }
+        }
+    };
 
     let title = feed
         .title
@@ -28,29 +591,1236 @@ pub async fn fetch_feed(url: &str) -> Result<FeedResult, Box<dyn Error>> {
     let articles = feed
         .entries
         .into_iter()
-        .take(10)
-        .map(|entry| {
-            let title = entry
-                .title
-                .map(|t| t.content)
-                .unwrap_or_else(|| "Untitled".to_string());
-            let link = entry.links.first().map(|l| l.href.clone());
-            let published = entry
-                .published
-                .or(entry.updated)
-                .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string());
-            let content = entry
+        .enumerate()
+        .map(|(i, entry)| {
+            let title = match entry.title.map(|t| t.content) {
+                Some(title) if !title.trim().is_empty() => title,
+                _ => {
+                    warnings.push(format!("entry {} has no title", i + 1));
+                    "Untitled".to_string()
+                }
+            };
+            let (attached, links): (Vec<_>, Vec<_>) = entry
+                .links
+                .iter()
+                .partition(|l| l.rel.as_deref() == Some("enclosure"));
+            let link = links.first().map(|l| l.href.clone());
+            let mut enclosures: Vec<Enclosure> = entry
+                .media
+                .iter()
+                .flat_map(|m| &m.content)
+                .filter_map(|c| {
+                    Some(Enclosure {
+                        url: c.url.as_ref()?.to_string(),
+                        mime_type: c.content_type.as_ref().map(|t| t.to_string()),
+                        length: c.size,
+                    })
+                })
+                .collect();
+            for l in attached {
+                if !enclosures.iter().any(|e| e.url == l.href) {
+                    enclosures.push(Enclosure {
+                        url: l.href.clone(),
+                        mime_type: l.media_type.clone(),
+                        length: l.length,
+                    });
+                }
+            }
+            let id = Some(entry.id)
+                .filter(|id| !id.trim().is_empty())
+                .or_else(|| link.clone());
+            let authors: Vec<String> = entry.authors.iter().filter_map(author_name).collect();
+            let author = (!authors.is_empty()).then(|| authors.join(", "));
+            let categories = entry
+                .categories
+                .iter()
+                .map(|c| c.label.as_deref().unwrap_or(&c.term).trim().to_string())
+                .filter(|c| !c.is_empty())
+                .collect();
+            let published = entry.published.or(entry.updated);
+            let mut content = entry
                 .summary
                 .map(|s| s.content)
                 .or_else(|| entry.content.and_then(|c| c.body));
+            if let Some(content) = &mut content {
+                truncate_content(content, max_content_len);
+            }
             Article {
+                id,
                 title,
                 link,
+                author,
+                categories,
                 published,
                 content,
+                enclosures,
+                source: url.to_string(),
             }
         })
         .collect();
 
-    Ok(FeedResult { title, articles })
+    Ok(FeedResult {
+        title,
+        articles,
+        final_url: url.to_string(),
+        warnings,
+    })
+}
+
+/// A feed document read from the local machine instead of over HTTP.
+pub enum LocalSource {
+    Stdin,
+    File(PathBuf),
+}
+
+/// Recognizes `-` (standard input), `file://` URLs and paths of existing
+/// files as local feed documents.
+pub fn local_source(arg: &str) -> Option<LocalSource> {
+    if arg == "-" {
+        return Some(LocalSource::Stdin);
+    }
+    if arg.starts_with("file:") {
+        return url::Url::parse(arg)
+            .ok()
+            .and_then(|url| url.to_file_path().ok())
+            .map(LocalSource::File);
+    }
+    let path = Path::new(arg);
+    (!arg.contains("://") && path.is_file()).then(|| LocalSource::File(path.to_path_buf()))
+}
+
+/// A named or numeric character reference such as `&amp;` or `&#8217;`.
+static ENTITY: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"&(#[0-9]+|#[xX][0-9a-fA-F]+|[a-zA-Z]+);").expect("valid entity pattern")
+});
+
+/// Reduces an HTML fragment to plain text: tags are removed, common
+/// entities decoded and runs of whitespace collapsed to single spaces.
+pub fn strip_html(input: &str) -> String {
+    let mut text = String::with_capacity(input.len());
+    let mut in_tag = false;
+    for c in input.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                text.push(' ');
+            }
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+
+    let decoded = ENTITY.replace_all(&text, |caps: &regex::Captures| {
+        let entity = &caps[1];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ => match entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+            {
+                Some(hex) => u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+                None => entity
+                    .strip_prefix('#')
+                    .and_then(|dec| dec.parse().ok())
+                    .and_then(char::from_u32),
+            },
+        };
+        decoded.map_or_else(|| caps[0].to_string(), |c| c.to_string())
+    });
+
+    decoded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Display name for an entry author. RSS `<author>` holds an email address,
+/// conventionally `jane@example.com (Jane Doe)`, which feed_rs stores as the
+/// email of a person named "author"; the name in parentheses is preferred.
+fn author_name(person: &feed_rs::model::Person) -> Option<String> {
+    let name = match (&person.email, person.name.as_str()) {
+        (Some(email), "author") => email
+            .split_once('(')
+            .and_then(|(_, rest)| rest.strip_suffix(')'))
+            .unwrap_or(email),
+        _ => &person.name,
+    };
+    let name = name.trim();
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// Parses a feed document. feed_rs makes up an id for entries without a
+/// guid; here those are left empty so `parse_feed` can use the link instead.
+fn parse_document(body: &[u8]) -> Result<feed_rs::model::Feed, parser::ParseFeedError> {
+    parser::Builder::new()
+        .id_generator(|_, _, _| String::new())
+        .build()
+        .parse(body)
+}
+
+/// Recovers what it can from a feed that failed to parse as a whole by
+/// parsing each entry on its own between the document's header and footer,
+/// so one malformed entry doesn't cost the rest. Returns None if the
+/// document can't be split into entries or its header doesn't parse.
+fn salvage_feed(body: &str) -> Option<(feed_rs::model::Feed, Vec<String>)> {
+    let spans = ["item", "entry"]
+        .iter()
+        .map(|tag| entry_spans(body, tag))
+        .find(|spans| !spans.is_empty())?;
+    let header = &body[..spans.first()?.0];
+    let footer = &body[spans.last()?.1..];
+    let mut feed = parse_document(format!("{}{}", header, footer).as_bytes()).ok()?;
+
+    let mut warnings = Vec::new();
+    for (i, &(start, end)) in spans.iter().enumerate() {
+        let document = format!("{}{}{}", header, &body[start..end], footer);
+        match parse_document(document.as_bytes()) {
+            Ok(parsed) => feed.entries.extend(parsed.entries),
+            Err(e) => warnings.push(format!("skipped malformed entry {}: {}", i + 1, e)),
+        }
+    }
+    Some((feed, warnings))
+}
+
+/// Byte ranges of each `<tag ...>...</tag>` element in `body`, in order.
+fn entry_spans(body: &str, tag: &str) -> Vec<(usize, usize)> {
+    let open = format!("<{}", tag);
+    let close = format!("</{}>", tag);
+    let mut spans = Vec::new();
+    let mut pos = 0;
+    while let Some(found) = body[pos..].find(&open) {
+        let start = pos + found;
+        let after = start + open.len();
+        // Skip longer tag names sharing the prefix, like RSS 1.0's <items>.
+        if !body[after..].starts_with(|c: char| c == '>' || c.is_whitespace()) {
+            pos = after;
+            continue;
+        }
+        let Some(len) = body[after..].find(&close) else {
+            break;
+        };
+        let end = after + len + close.len();
+        spans.push((start, end));
+        pos = end;
+    }
+    spans
+}
+
+fn looks_like_html(body: &str) -> bool {
+    let head: String = body
+        .trim_start()
+        .chars()
+        .take(1024)
+        .collect::<String>()
+        .to_lowercase();
+    head.starts_with("<!doctype html") || head.contains("<html")
+}
+
+/// Elements left out when looking for an article's text on its web page.
+const BOILERPLATE_TAGS: &[&str] = &[
+    "script", "style", "noscript", "nav", "header", "footer", "aside", "form",
+];
+/// Any element in `BOILERPLATE_TAGS`, with its contents.
+static BOILERPLATE: LazyLock<Regex> = LazyLock::new(|| {
+    let blocks: Vec<String> = BOILERPLATE_TAGS
+        .iter()
+        .map(|tag| format!(r"<{0}\b[^>]*>.*?</{0}\s*>", tag))
+        .collect();
+    Regex::new(&format!("(?is){}", blocks.join("|"))).expect("valid boilerplate pattern")
+});
+/// The contents of an `<article>` element, then of a `<main>` element, in
+/// the order `extract_article_text` tries them.
+static CONTAINERS: LazyLock<[Regex; 2]> = LazyLock::new(|| {
+    ["article", "main"].map(|tag| {
+        Regex::new(&format!(r"(?is)<{0}\b[^>]*>(.*)</{0}\s*>", tag))
+            .expect("valid container pattern")
+    })
+});
+static PARAGRAPH: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<p\b[^>]*>(.*?)</p\s*>").expect("valid paragraph pattern"));
+/// Article pages fetched at once by `fill_full_text`.
+const FULL_TEXT_CONCURRENCY: usize = 4;
+
+/// Pulls the main text out of an article's web page: the contents of its
+/// `<article>` element, else of `<main>`, else every `<p>` on the page, after
+/// dropping scripts, navigation, headers, footers and sidebars. Returns None
+/// when that leaves no text.
+pub fn extract_article_text(html: &str) -> Option<String> {
+    let html = BOILERPLATE.replace_all(html, " ");
+
+    for block in CONTAINERS.iter() {
+        if let Some(caps) = block.captures(&html) {
+            let text = strip_html(&caps[1]);
+            if !text.is_empty() {
+                return Some(text);
+            }
+        }
+    }
+
+    let text = PARAGRAPH
+        .captures_iter(&html)
+        .map(|caps| strip_html(&caps[1]))
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    (!text.is_empty()).then_some(text)
+}
+
+/// Replaces each article's content with the text of its linked page when
+/// that text is longer, for feeds that only carry a summary, cut at
+/// `max_content_len` characters. Articles whose page can't be fetched keep
+/// their feed content. Returns how many articles were filled in.
+pub async fn fill_full_text(
+    transport: &dyn Transport,
+    articles: &mut [Article],
+    max_content_len: usize,
+) -> usize {
+    stream::iter(articles.iter_mut())
+        .map(|article| async move {
+            let Some(link) = &article.link else {
+                return false;
+            };
+            let response = match transport.get(link, &[]).await {
+                Ok(response) if response.is_success() => response,
+                _ => return false,
+            };
+            let Some(text) = extract_article_text(&String::from_utf8_lossy(&response.body)) else {
+                return false;
+            };
+            let current = article
+                .content
+                .as_deref()
+                .map_or(0, |c| strip_html(c).len());
+            if text.len() <= current {
+                return false;
+            }
+            let mut text = text;
+            truncate_content(&mut text, max_content_len);
+            article.content = Some(text);
+            true
+        })
+        .buffer_unordered(FULL_TEXT_CONCURRENCY)
+        .filter(|filled| futures::future::ready(*filled))
+        .count()
+        .await
+}
+
+/// `type` values of `<link rel="alternate">` tags that point at feeds.
+const FEED_LINK_TYPES: &[&str] = &[
+    "application/rss+xml",
+    "application/atom+xml",
+    "application/feed+json",
+    "application/json",
+];
+
+static LINK_TAG: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<link\b[^>]*>").expect("valid link pattern"));
+/// An HTML attribute, its value double-quoted, single-quoted or bare.
+static ATTRIBUTE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?is)([a-z][a-z-]*)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s>]+))"#)
+        .expect("valid attribute pattern")
+});
+
+/// Finds the feeds an HTML page advertises through
+/// `<link rel="alternate" type="application/rss+xml" href="...">` tags (and
+/// the Atom and JSON Feed variants), resolving relative hrefs against
+/// `base_url`.
+pub fn discover_feeds(html: &str, base_url: &str) -> Vec<String> {
+    let base = reqwest::Url::parse(base_url).ok();
+
+    let mut feeds = Vec::new();
+    for tag in LINK_TAG.find_iter(html) {
+        let mut rel = String::new();
+        let mut kind = String::new();
+        let mut href = None;
+        for attr in ATTRIBUTE.captures_iter(tag.as_str()) {
+            let value = attr
+                .get(2)
+                .or_else(|| attr.get(3))
+                .or_else(|| attr.get(4))
+                .map_or("", |m| m.as_str())
+                .replace("&amp;", "&");
+            match attr[1].to_lowercase().as_str() {
+                "rel" => rel = value.to_lowercase(),
+                "type" => kind = value.trim().to_lowercase(),
+                "href" => href = Some(value.trim().to_string()),
+                _ => {}
+            }
+        }
+
+        let is_alternate = rel.split_whitespace().any(|r| r == "alternate");
+        let Some(href) = href.filter(|_| is_alternate && FEED_LINK_TYPES.contains(&kind.as_str()))
+        else {
+            continue;
+        };
+        let resolved = match &base {
+            Some(base) => base.join(&href).map(|u| u.to_string()).unwrap_or(href),
+            None => href,
+        };
+        if !feeds.contains(&resolved) {
+            feeds.push(resolved);
+        }
+    }
+    feeds
+}
+
+/// Order in which articles are listed.
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum SortOrder {
+    Newest,
+    Oldest,
+}
+
+/// Sorts articles by publish time in the given direction. Undated articles go
+/// last either way, keeping their relative order.
+pub fn sort_articles(articles: &mut [Article], order: SortOrder) {
+    articles.sort_by(|a, b| match (a.published, b.published) {
+        (Some(a), Some(b)) if order == SortOrder::Newest => b.cmp(&a),
+        (Some(a), Some(b)) => a.cmp(&b),
+        (a, b) => b.is_some().cmp(&a.is_some()),
+    });
+}
+
+/// Keeps only articles with an audio or video attachment.
+pub fn retain_with_media(articles: &mut Vec<Article>) {
+    articles.retain(|a| a.enclosures.iter().any(Enclosure::is_playable));
+}
+
+/// Keeps only articles carrying at least one of `tags`, compared
+/// case-insensitively.
+pub fn retain_tagged(articles: &mut Vec<Article>, tags: &[String]) {
+    articles.retain(|a| {
+        a.categories
+            .iter()
+            .any(|c| tags.iter().any(|t| t.eq_ignore_ascii_case(c)))
+    });
+}
+
+/// Start of the window of articles to show: either a fixed date or a
+/// duration before now.
+#[derive(Debug, Clone, Copy)]
+pub enum Since {
+    Ago(TimeDelta),
+    Date(NaiveDateTime),
+}
+
+impl Since {
+    /// The earliest publish time kept. Fixed dates are taken to be UTC.
+    pub fn cutoff(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            Since::Ago(delta) => now - *delta,
+            Since::Date(date) => date.and_utc(),
+        }
+    }
+}
+
+impl FromStr for Since {
+    type Err = String;
+
+    /// Accepts `YYYY-MM-DD`, `YYYY-MM-DD HH:MM`, or a number followed by
+    /// `m` (minutes), `h` (hours), `d` (days) or `w` (weeks), e.g. `7d`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+            return Ok(Since::Date(
+                date.and_hms_opt(0, 0, 0).expect("midnight is valid"),
+            ));
+        }
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M") {
+            return Ok(Since::Date(datetime));
+        }
+
+        let invalid = || {
+            format!(
+                "expected a date like 2024-01-01 or a duration like 7d, got '{}'",
+                s
+            )
+        };
+        let unit = s.chars().last().ok_or_else(invalid)?;
+        let amount: i64 = s[..s.len() - unit.len_utf8()]
+            .parse()
+            .map_err(|_| invalid())?;
+        let delta = match unit {
+            'm' => TimeDelta::try_minutes(amount),
+            'h' => TimeDelta::try_hours(amount),
+            'd' => TimeDelta::try_days(amount),
+            'w' => TimeDelta::try_weeks(amount),
+            _ => None,
+        };
+        delta.map(Since::Ago).ok_or_else(invalid)
+    }
+}
+
+/// Drops articles published before `cutoff`. Undated articles are kept unless
+/// `require_date` is set.
+pub fn retain_since(articles: &mut Vec<Article>, cutoff: DateTime<Utc>, require_date: bool) {
+    articles.retain(|a| match a.published {
+        Some(published) => published >= cutoff,
+        None => !require_date,
+    });
+}
+
+/// Keeps only articles published on one of `days`. Undated articles are kept.
+pub fn retain_weekdays(articles: &mut Vec<Article>, days: &[Weekday]) {
+    articles.retain(|a| match a.published {
+        Some(published) => days.contains(&published.weekday()),
+        None => true,
+    });
+}
+
+/// Query parameters that only track where a click came from. Any parameter
+/// starting with `utm_` is dropped as well.
+const TRACKING_PARAMS: &[&str] = &["fbclid", "gclid", "mc_cid", "mc_eid"];
+
+/// Normalizes a link for comparison: drops tracking query parameters and
+/// trailing slashes.
+pub fn normalize_url(link: &str) -> String {
+    let Ok(mut url) = reqwest::Url::parse(link.trim()) else {
+        return link.trim().trim_end_matches('/').to_string();
+    };
+    let query: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(key, _)| !key.starts_with("utm_") && !TRACKING_PARAMS.contains(&key.as_ref()))
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    if query.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(query);
+    }
+    url.set_fragment(None);
+    url.as_str().trim_end_matches('/').to_string()
+}
+
+/// Drops articles already seen by id or by normalized link, across any number
+/// of batches.
+#[derive(Debug, Default)]
+pub struct Deduplicator {
+    seen: HashSet<String>,
+}
+
+impl Deduplicator {
+    /// Removes articles matching one seen earlier, keeping first occurrences.
+    pub fn retain_new(&mut self, articles: &mut Vec<Article>) {
+        articles.retain(|article| {
+            let keys: Vec<String> = [
+                article.id.as_ref().map(|id| format!("id:{}", id)),
+                article
+                    .link
+                    .as_ref()
+                    .map(|link| format!("link:{}", normalize_url(link))),
+            ]
+            .into_iter()
+            .flatten()
+            .collect();
+            if keys.iter().any(|key| self.seen.contains(key)) {
+                return false;
+            }
+            self.seen.extend(keys);
+            true
+        });
+    }
+}
+
+/// Collapses articles with the same guid or link, keeping the first.
+pub fn dedup_articles(articles: &mut Vec<Article>) {
+    Deduplicator::default().retain_new(articles);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::StubTransport;
+
+    const URL: &str = "https://example.com/feed.xml";
+
+    async fn fetch(transport: &StubTransport) -> Result<FeedResult, FeedError> {
+        fetch_feed_with(transport, URL, &[], &FetchOptions::default(), None).await
+    }
+
+    const RSS: &str = r#"<?xml version="1.0"?>
+<rss version="2.0"><channel><title>Example News</title>
+<item><title>With guid</title><link>https://example.com/a</link>
+<guid isPermaLink="false">tag:example.com,2026:a</guid></item>
+<item><title>Without guid</title><link>https://example.com/b</link></item>
+<item><title>Neither</title></item>
+</channel></rss>"#;
+
+    /// An article published at `published` (RFC 3339), if given.
+    fn article(title: &str, published: Option<&str>) -> Article {
+        Article {
+            id: None,
+            title: title.to_string(),
+            link: None,
+            author: None,
+            categories: Vec::new(),
+            published: published.map(|p| p.parse().unwrap()),
+            content: None,
+            enclosures: Vec::new(),
+            source: URL.to_string(),
+        }
+    }
+
+    fn titles(articles: &[Article]) -> Vec<&str> {
+        articles.iter().map(|a| a.title.as_str()).collect()
+    }
+
+    #[test]
+    fn parse_feed_uses_guid_then_link_as_id() {
+        let result = parse_feed(RSS.as_bytes(), URL, DEFAULT_MAX_CONTENT_LEN).unwrap();
+        assert_eq!(result.title, "Example News");
+        let ids: Vec<_> = result.articles.iter().map(|a| a.id.as_deref()).collect();
+        assert_eq!(
+            ids,
+            [
+                Some("tag:example.com,2026:a"),
+                Some("https://example.com/b"),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn one_malformed_entry_does_not_lose_the_feed() {
+        let body = r#"<?xml version="1.0"?>
+<rss version="2.0"><channel><title>Example News</title>
+<item><title>Valid</title><link>https://example.com/a</link></item>
+<item><title>Broken &bogus; <<</title><link>https://example.com/b</link></item>
+<item><link>https://example.com/c</link><pubDate>sometime soon</pubDate></item>
+</channel></rss>"#;
+        let result = parse_feed(body.as_bytes(), URL, DEFAULT_MAX_CONTENT_LEN).unwrap();
+        assert_eq!(result.title, "Example News");
+        assert_eq!(titles(&result.articles), ["Valid", "Untitled"]);
+        assert_eq!(result.articles[1].published, None);
+        assert_eq!(result.warnings.len(), 2, "{:?}", result.warnings);
+        assert!(result.warnings[0].starts_with("skipped malformed entry 2: "));
+        assert_eq!(result.warnings[1], "entry 2 has no title");
+    }
+
+    #[test]
+    fn podcast_enclosures_are_kept() {
+        let body = r#"<?xml version="1.0"?>
+<rss version="2.0"><channel><title>Market Podcast</title>
+<item><title>Episode 12</title><link>https://example.com/ep12</link>
+<enclosure url="https://cdn.example.com/ep12.mp3" length="24986239" type="audio/mpeg"/></item>
+<item><title>Show notes</title><link>https://example.com/notes</link></item>
+</channel></rss>"#;
+        let mut articles = parse_feed(body.as_bytes(), URL, DEFAULT_MAX_CONTENT_LEN)
+            .unwrap()
+            .articles;
+        let episode = &articles[0];
+        assert_eq!(episode.link.as_deref(), Some("https://example.com/ep12"));
+        let enclosures: Vec<_> = episode
+            .enclosures
+            .iter()
+            .map(|e| (e.url.as_str(), e.mime_type.as_deref(), e.length))
+            .collect();
+        assert_eq!(
+            enclosures,
+            [(
+                "https://cdn.example.com/ep12.mp3",
+                Some("audio/mpeg"),
+                Some(24986239)
+            )]
+        );
+        assert!(articles[1].enclosures.is_empty());
+
+        retain_with_media(&mut articles);
+        assert_eq!(titles(&articles), ["Episode 12"]);
+    }
+
+    #[test]
+    fn atom_enclosure_links_are_not_the_article_link() {
+        let body = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom"><title>Atom Cast</title><id>urn:cast</id>
+<updated>2026-10-15T12:00:00Z</updated>
+<entry><title>Episode 1</title><id>urn:cast:1</id><updated>2026-10-15T12:00:00Z</updated>
+<link rel="enclosure" href="https://cdn.example.com/1.m4a" type="audio/mp4" length="1000"/>
+<link rel="alternate" href="https://example.com/1"/></entry>
+</feed>"#;
+        let result = parse_feed(body.as_bytes(), URL, DEFAULT_MAX_CONTENT_LEN).unwrap();
+        let episode = &result.articles[0];
+        assert_eq!(episode.link.as_deref(), Some("https://example.com/1"));
+        assert_eq!(episode.enclosures.len(), 1);
+        assert_eq!(episode.enclosures[0].url, "https://cdn.example.com/1.m4a");
+        assert_eq!(episode.enclosures[0].length, Some(1000));
+        assert!(episode.enclosures[0].is_playable());
+    }
+
+    /// An ISO-8859-1 feed whose prolog says `encoding`, if anything.
+    fn latin1_feed(encoding: Option<&str>) -> Vec<u8> {
+        let prolog = match encoding {
+            Some(encoding) => format!(r#"<?xml version="1.0" encoding="{}"?>"#, encoding),
+            None => r#"<?xml version="1.0"?>"#.to_string(),
+        };
+        [
+            prolog.as_bytes(),
+            b"<rss version=\"2.0\"><channel><title>Nachrichten</title>",
+            b"<item><title>Caf\xe9 in Z\xfcrich</title></item>",
+            b"<item><title>D\xe9j\xe0 vu</title></item></channel></rss>",
+        ]
+        .concat()
+    }
+
+    fn latin1_response(content_type: &str, body: Vec<u8>) -> StubTransport {
+        StubTransport::default().respond_with(
+            URL,
+            crate::http::Response {
+                url: URL.to_string(),
+                status: 200,
+                headers: vec![("Content-Type".to_string(), content_type.to_string())],
+                body,
+            },
+        )
+    }
+
+    #[test]
+    fn an_encoding_declared_in_the_prolog_is_honored() {
+        let body = latin1_feed(Some("ISO-8859-1"));
+        assert_eq!(to_utf8(&body, None).as_ref(), body.as_slice());
+        let result = parse_feed(&body, URL, DEFAULT_MAX_CONTENT_LEN).unwrap();
+        assert_eq!(titles(&result.articles), ["Café in Zürich", "Déjà vu"]);
+    }
+
+    #[tokio::test]
+    async fn latin1_bodies_are_decoded_with_the_content_type_charset() {
+        let transport =
+            latin1_response("application/rss+xml; charset=ISO-8859-1", latin1_feed(None));
+        let result = fetch(&transport).await.unwrap();
+        assert_eq!(titles(&result.articles), ["Café in Zürich", "Déjà vu"]);
+    }
+
+    #[tokio::test]
+    async fn the_content_type_charset_wins_over_the_prolog() {
+        let transport = latin1_response(
+            r#"text/xml; charset="iso-8859-1""#,
+            latin1_feed(Some("UTF-8")),
+        );
+        let result = fetch(&transport).await.unwrap();
+        assert_eq!(titles(&result.articles), ["Café in Zürich", "Déjà vu"]);
+
+        let utf8 = RSS
+            .replace(
+                "<?xml version=\"1.0\"?>",
+                r#"<?xml version="1.0" encoding="ISO-8859-1"?>"#,
+            )
+            .replace("Neither", "Café");
+        let transport = latin1_response("text/xml; charset=utf-8", utf8.into_bytes());
+        let result = fetch(&transport).await.unwrap();
+        assert_eq!(titles(&result.articles)[2], "Café");
+    }
+
+    #[test]
+    fn extract_article_text_prefers_article_and_drops_boilerplate() {
+        let html = r#"<html><head><style>p { color: red }</style></head><body>
+            <nav><p>Home</p></nav>
+            <main><p>Main text</p></main>
+            <article><script>track()</script><p>The story</p><aside>Ad</aside></article>
+            <footer><p>Copyright</p></footer></body></html>"#;
+        assert_eq!(extract_article_text(html).as_deref(), Some("The story"));
+    }
+
+    #[test]
+    fn extract_article_text_falls_back_to_paragraphs() {
+        let html = "<div><header><p>Site</p></header><p>One</p><div><p>Two</p></div></div>";
+        assert_eq!(extract_article_text(html).as_deref(), Some("One Two"));
+        assert_eq!(extract_article_text("<nav><p>Only nav</p></nav>"), None);
+    }
+
+    #[tokio::test]
+    async fn full_text_replaces_short_summaries() {
+        let page = r#"<html><body><nav><p>Home | Markets | Login</p></nav>
+            <article><h1>Headline</h1><p>AAPL shares rose after a strong quarter.</p>
+            <p>Analysts raised their targets across the board.</p></article>
+            <footer><p>Copyright 2026</p></footer></body></html>"#;
+        let transport = StubTransport::default()
+            .respond("https://example.com/full", 200, page)
+            .respond("https://example.com/gone", 404, "Not Found");
+        let linked = |title: &str, link: Option<&str>| Article {
+            link: link.map(str::to_string),
+            content: Some("<p>Short summary.</p>".to_string()),
+            ..article(title, None)
+        };
+        let mut articles = vec![
+            linked("Full", Some("https://example.com/full")),
+            linked("Gone", Some("https://example.com/gone")),
+            linked("No link", None),
+        ];
+        let filled = fill_full_text(&transport, &mut articles, DEFAULT_MAX_CONTENT_LEN).await;
+        assert_eq!(filled, 1);
+        let full = articles[0].content.as_deref().unwrap();
+        assert!(full.contains("AAPL shares rose"), "{}", full);
+        assert!(full.contains("raised their targets"), "{}", full);
+        assert!(
+            !full.contains("Login") && !full.contains("Copyright"),
+            "{}",
+            full
+        );
+        assert_eq!(
+            articles[1].content.as_deref(),
+            Some("<p>Short summary.</p>")
+        );
+        assert_eq!(
+            articles[2].content.as_deref(),
+            Some("<p>Short summary.</p>")
+        );
+    }
+
+    #[test]
+    fn local_source_recognizes_stdin_and_existing_files() {
+        assert!(matches!(local_source("-"), Some(LocalSource::Stdin)));
+        let path = crate::test_dir("local-source").join("feed.xml");
+        std::fs::write(&path, RSS).unwrap();
+        assert!(
+            matches!(local_source(path.to_str().unwrap()), Some(LocalSource::File(p)) if p == path)
+        );
+        assert!(local_source(URL).is_none());
+        assert!(local_source("no-such-feed.xml").is_none());
+    }
+
+    #[test]
+    fn discover_feeds_resolves_alternate_links() {
+        let html = r#"<head>
+            <link rel="stylesheet" href="/style.css">
+            <link rel="alternate" type="application/rss+xml" href="/feed.xml">
+            <LINK REL='alternate' TYPE='application/atom+xml' HREF='https://cdn.example.com/atom'>
+            <link rel=alternate type=application/rss+xml href=/feed.xml>
+            </head>"#;
+        assert_eq!(
+            discover_feeds(html, "https://example.com/blog/"),
+            [
+                "https://example.com/feed.xml",
+                "https://cdn.example.com/atom"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn fetches_through_the_given_transport() {
+        let dir = crate::test_dir("feed-fixture-fetch");
+        std::fs::write(
+            dir.join("manifest.json"),
+            format!(r#"{{"{}": "feed.xml"}}"#, URL),
+        )
+        .unwrap();
+        std::fs::write(dir.join("feed.xml"), RSS).unwrap();
+        let transport = crate::http::FixtureTransport::load(&dir).unwrap();
+
+        let result = fetch_feed_with(&transport, URL, &[], &FetchOptions::default(), Some(2))
+            .await
+            .unwrap();
+        assert_eq!(result.title, "Example News");
+        assert_eq!(titles(&result.articles), ["With guid", "Without guid"]);
+        assert!(result.articles.iter().all(|a| a.source == URL));
+    }
+
+    #[tokio::test]
+    async fn missing_feed_is_not_found() {
+        let transport = StubTransport::default().respond(URL, 404, "Not Found");
+        assert!(matches!(fetch(&transport).await, Err(FeedError::NotFound)));
+        let transport = StubTransport::default().respond(URL, 410, "");
+        assert!(matches!(fetch(&transport).await, Err(FeedError::NotFound)));
+    }
+
+    #[tokio::test]
+    async fn other_statuses_are_http_errors() {
+        let transport = StubTransport::default().respond(URL, 500, "oops");
+        assert!(matches!(fetch(&transport).await, Err(FeedError::Http(500))));
+        let transport = StubTransport::default().respond(URL, 429, "");
+        assert!(matches!(
+            fetch(&transport).await,
+            Err(FeedError::RateLimited)
+        ));
+    }
+
+    #[tokio::test]
+    async fn unparseable_feed_is_a_parse_error() {
+        let transport = StubTransport::default().respond(URL, 200, "this is not a feed");
+        assert!(matches!(fetch(&transport).await, Err(FeedError::Parse(_))));
+    }
+
+    #[tokio::test]
+    async fn empty_body_is_empty() {
+        let transport = StubTransport::default().respond(URL, 200, " \n");
+        assert!(matches!(fetch(&transport).await, Err(FeedError::Empty)));
+    }
+
+    #[tokio::test]
+    async fn network_failure_is_a_network_error() {
+        let transport = StubTransport::default();
+        assert!(matches!(
+            fetch(&transport).await,
+            Err(FeedError::Network(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn feed_health_is_classified_from_the_response() {
+        let cases = [
+            (Some((200, RSS)), FeedHealth::Ok),
+            (Some((404, "Not Found")), FeedHealth::Gone),
+            (Some((410, "")), FeedHealth::Gone),
+            (Some((500, "oops")), FeedHealth::HttpError),
+            (Some((429, "")), FeedHealth::HttpError),
+            (Some((200, "this is not a feed")), FeedHealth::ParseError),
+            (Some((200, "")), FeedHealth::ParseError),
+            (None, FeedHealth::Unreachable),
+        ];
+        for (response, expected) in cases {
+            let transport = match response {
+                Some((status, body)) => StubTransport::default().respond(URL, status, body),
+                None => StubTransport::default(),
+            };
+            let health = FeedHealth::classify(&fetch(&transport).await);
+            assert_eq!(health, expected, "{:?}", response);
+        }
+        assert_eq!(
+            FeedHealth::classify(&Err(FeedError::Timeout(Duration::from_secs(5)))),
+            FeedHealth::Unreachable
+        );
+    }
+
+    #[test]
+    fn redirected_to_ignores_equivalent_and_unknown_urls() {
+        let mut result = FeedResult {
+            title: "Example".to_string(),
+            articles: Vec::new(),
+            final_url: String::new(),
+            warnings: Vec::new(),
+        };
+        assert_eq!(result.redirected_to(URL), None);
+        result.final_url = "HTTPS://EXAMPLE.COM/feed.xml".to_string();
+        assert_eq!(result.redirected_to(URL), None);
+        result.final_url = "https://example.com/rss/".to_string();
+        assert_eq!(result.redirected_to(URL), Some("https://example.com/rss/"));
+    }
+
+    #[test]
+    fn feed_stats_count_articles_and_find_the_newest() {
+        let result = FeedResult {
+            title: "Example News".to_string(),
+            articles: vec![
+                article("Older", Some("2026-10-12T08:00:00Z")),
+                article("Undated", None),
+                article("Newest", Some("2026-10-15T18:30:00Z")),
+                article("Middle", Some("2026-10-14T09:00:00Z")),
+            ],
+            final_url: URL.to_string(),
+            warnings: Vec::new(),
+        };
+        let stats = FeedStats::from_result(&result);
+        assert_eq!(stats.title, "Example News");
+        assert_eq!(stats.articles, 4);
+        assert_eq!(stats.newest, Some(utc("2026-10-15T18:30:00Z")));
+
+        let undated = FeedResult {
+            articles: vec![article("Undated", None)],
+            ..result
+        };
+        assert_eq!(FeedStats::from_result(&undated).newest, None);
+    }
+
+    #[test]
+    fn retain_weekdays_drops_weekend_articles_and_keeps_undated() {
+        let mut articles = vec![
+            article("Friday", Some("2026-10-16T12:00:00Z")),
+            article("Saturday", Some("2026-10-17T12:00:00Z")),
+            article("Sunday", Some("2026-10-18T12:00:00Z")),
+            article("Monday", Some("2026-10-19T12:00:00Z")),
+            article("Undated", None),
+        ];
+        let weekdays = [
+            Weekday::Mon,
+            Weekday::Tue,
+            Weekday::Wed,
+            Weekday::Thu,
+            Weekday::Fri,
+        ];
+        retain_weekdays(&mut articles, &weekdays);
+        assert_eq!(titles(&articles), ["Friday", "Monday", "Undated"]);
+    }
+
+    #[test]
+    fn feed_urls_are_validated_and_normalized() {
+        assert_eq!(
+            normalize_feed_url("  HTTPS://Example.COM/Feed.xml#top ").unwrap(),
+            "https://example.com/Feed.xml"
+        );
+        assert_eq!(
+            normalize_feed_url("http://example.com").unwrap(),
+            "http://example.com/"
+        );
+        assert_eq!(
+            normalize_feed_url("not-a-url"),
+            Err(FeedUrlError::Invalid(
+                url::ParseError::RelativeUrlWithoutBase
+            ))
+        );
+        assert_eq!(
+            normalize_feed_url("ftp://example.com/feed.xml"),
+            Err(FeedUrlError::UnsupportedScheme("ftp".to_string()))
+        );
+        assert_eq!(
+            normalize_feed_url("file:///tmp/feed.xml"),
+            Err(FeedUrlError::UnsupportedScheme("file".to_string()))
+        );
+        assert!(matches!(
+            normalize_feed_url("http://"),
+            Err(FeedUrlError::Invalid(_) | FeedUrlError::MissingHost)
+        ));
+        assert_eq!(
+            FeedUrlError::Invalid(url::ParseError::RelativeUrlWithoutBase).to_string(),
+            "not a URL (expected e.g. https://example.com/feed.xml)"
+        );
+    }
+
+    #[test]
+    fn equivalent_feed_urls_are_the_same_feed() {
+        assert!(same_feed_url("http://x.com/", "http://x.com"));
+        assert!(same_feed_url(
+            "HTTP://X.com/feed#latest",
+            "http://x.com/feed"
+        ));
+        assert!(!same_feed_url("http://x.com/feed", "https://x.com/feed"));
+        assert!(!same_feed_url("http://x.com/Feed", "http://x.com/feed"));
+        assert!(same_feed_url("not a url", "not a url"));
+    }
+
+    #[test]
+    fn transport_errors_keep_their_kind() {
+        let timeout = FeedError::from(TransportError::Timeout(Duration::from_secs(15)));
+        assert!(matches!(timeout, FeedError::Timeout(_)));
+        assert_eq!(timeout.to_string(), "timed out after 15s");
+        assert_eq!(FeedError::Http(502).to_string(), "HTTP status 502");
+        assert_eq!(
+            FeedError::HtmlPage(vec![URL.to_string()]).to_string(),
+            format!("this is an HTML page linking to feeds: {}", URL)
+        );
+    }
+
+    fn linked(title: &str, link: &str) -> Article {
+        Article {
+            link: Some(link.to_string()),
+            ..article(title, None)
+        }
+    }
+
+    #[test]
+    fn normalize_url_strips_tracking_and_trailing_slashes() {
+        assert_eq!(
+            normalize_url("https://example.com/story/?utm_source=rss&id=7&fbclid=x#top"),
+            "https://example.com/story/?id=7"
+        );
+        assert_eq!(
+            normalize_url("https://example.com/story/?utm_medium=feed"),
+            "https://example.com/story"
+        );
+        assert_eq!(normalize_url(" not a url/ "), "not a url");
+    }
+
+    #[test]
+    fn dedup_ignores_utm_parameters() {
+        let mut articles = vec![
+            linked("Site", "https://example.com/story"),
+            linked(
+                "Aggregator",
+                "https://example.com/story?utm_source=aggregator",
+            ),
+            linked("Other", "https://example.com/other"),
+        ];
+        dedup_articles(&mut articles);
+        assert_eq!(titles(&articles), ["Site", "Other"]);
+    }
+
+    #[test]
+    fn deduplicator_remembers_ids_across_batches() {
+        let mut dedup = Deduplicator::default();
+        let mut first = vec![Article {
+            id: Some("guid-1".to_string()),
+            ..linked("First", "https://example.com/1")
+        }];
+        dedup.retain_new(&mut first);
+        let mut second = vec![
+            Article {
+                id: Some("guid-1".to_string()),
+                ..linked("Same guid", "https://mirror.example.com/1")
+            },
+            linked("Same link", "https://example.com/1/"),
+            linked("New", "https://example.com/2"),
+        ];
+        dedup.retain_new(&mut second);
+        assert_eq!(titles(&first), ["First"]);
+        assert_eq!(titles(&second), ["New"]);
+    }
+
+    #[test]
+    fn parses_json_feed() {
+        let json = r#"{
+            "version": "https://jsonfeed.org/version/1.1",
+            "title": "JSON News",
+            "items": [
+                {"id": "1", "title": "First", "url": "https://example.com/1",
+                 "date_published": "2026-10-16T09:30:00Z", "content_text": "Plain text"},
+                {"id": "2", "title": "Second", "url": "https://example.com/2",
+                 "content_html": "<p>Some <b>HTML</b></p>"}
+            ]
+        }"#;
+        let result = parse_feed(json.as_bytes(), URL, DEFAULT_MAX_CONTENT_LEN).unwrap();
+        assert_eq!(result.title, "JSON News");
+        assert_eq!(titles(&result.articles), ["First", "Second"]);
+        let first = &result.articles[0];
+        assert_eq!(first.link.as_deref(), Some("https://example.com/1"));
+        assert_eq!(
+            first.published,
+            Some("2026-10-16T09:30:00Z".parse().unwrap())
+        );
+        assert_eq!(first.content.as_deref(), Some("Plain text"));
+        assert!(result.articles[1]
+            .content
+            .as_deref()
+            .is_some_and(|c| c.contains("HTML")));
+    }
+
+    #[tokio::test]
+    async fn html_page_reports_the_feeds_it_links_to() {
+        let html = r#"<!DOCTYPE html><html><head>
+            <link rel="alternate" type="application/atom+xml" href="atom.xml">
+            </head><body>Welcome</body></html>"#;
+        let transport = StubTransport::default().respond(URL, 200, html);
+        match fetch(&transport).await {
+            Err(FeedError::HtmlPage(feeds)) => {
+                assert_eq!(feeds, ["https://example.com/atom.xml"])
+            }
+            other => panic!(
+                "expected an HTML page error, got {:?}",
+                other.map(|r| r.title)
+            ),
+        }
+    }
+
+    #[test]
+    fn strip_html_removes_nested_and_self_closing_tags() {
+        assert_eq!(
+            strip_html("<div><p>Hello <a href=\"/x\"><b>world</b></a></p><br/>Next<img src=\"a.png\" /></div>"),
+            "Hello world Next"
+        );
+        assert_eq!(strip_html("  plain\n\ttext  "), "plain text");
+    }
+
+    #[test]
+    fn strip_html_decodes_entities() {
+        assert_eq!(
+            strip_html("Tom&nbsp;&amp;&nbsp;Jerry&#39;s &lt;show&gt; &quot;live&quot;"),
+            "Tom & Jerry's <show> \"live\""
+        );
+        assert_eq!(strip_html("caf&#xE9; &#8364;5"), "caf\u{e9} \u{20ac}5");
+        assert_eq!(strip_html("&unknown; &#xZZ;"), "&unknown; &#xZZ;");
+    }
+
+    #[test]
+    fn authors_come_from_atom_names_and_rss_emails() {
+        let atom = r#"<?xml version="1.0"?>
+<feed xmlns="http://www.w3.org/2005/Atom"><title>Atom</title><id>urn:feed</id>
+<updated>2026-10-16T09:30:00Z</updated>
+<entry><title>Two authors</title><id>urn:1</id><updated>2026-10-16T09:30:00Z</updated>
+<author><name>Jane Doe</name></author><author><name>John Roe</name></author></entry>
+<entry><title>Anonymous</title><id>urn:2</id><updated>2026-10-16T09:30:00Z</updated></entry>
+</feed>"#;
+        let result = parse_feed(atom.as_bytes(), URL, DEFAULT_MAX_CONTENT_LEN).unwrap();
+        let authors: Vec<_> = result
+            .articles
+            .iter()
+            .map(|a| a.author.as_deref())
+            .collect();
+        assert_eq!(authors, [Some("Jane Doe, John Roe"), None]);
+
+        let rss = r#"<rss version="2.0"><channel><title>RSS</title>
+<item><title>Emailed</title><author>jane@example.com (Jane Doe)</author></item>
+</channel></rss>"#;
+        let result = parse_feed(rss.as_bytes(), URL, DEFAULT_MAX_CONTENT_LEN).unwrap();
+        assert_eq!(result.articles[0].author.as_deref(), Some("Jane Doe"));
+    }
+
+    fn utc(value: &str) -> DateTime<Utc> {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn since_parses_dates_and_durations() {
+        let now = utc("2026-10-16T12:00:00Z");
+        let cutoff = |value: &str| value.parse::<Since>().unwrap().cutoff(now);
+        assert_eq!(cutoff("2026-01-01"), utc("2026-01-01T00:00:00Z"));
+        assert_eq!(cutoff("2026-10-15 08:30"), utc("2026-10-15T08:30:00Z"));
+        assert_eq!(cutoff("30m"), utc("2026-10-16T11:30:00Z"));
+        assert_eq!(cutoff("24h"), utc("2026-10-15T12:00:00Z"));
+        assert_eq!(cutoff(" 7d "), utc("2026-10-09T12:00:00Z"));
+        assert_eq!(cutoff("2w"), utc("2026-10-02T12:00:00Z"));
+        for invalid in ["", "d", "7y", "seven days", "2026-13-01"] {
+            assert!(invalid.parse::<Since>().is_err(), "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn retain_since_keeps_undated_unless_required() {
+        let articles = vec![
+            article("Old", Some("2026-10-01T00:00:00Z")),
+            article("New", Some("2026-10-15T00:00:00Z")),
+            article("Undated", None),
+        ];
+        let cutoff = utc("2026-10-09T00:00:00Z");
+        let mut kept = articles.clone();
+        retain_since(&mut kept, cutoff, false);
+        assert_eq!(titles(&kept), ["New", "Undated"]);
+        let mut kept = articles;
+        retain_since(&mut kept, cutoff, true);
+        assert_eq!(titles(&kept), ["New"]);
+    }
+
+    #[test]
+    fn published_comes_from_pub_date_or_updated_in_utc() {
+        let atom = r#"<?xml version="1.0"?>
+<feed xmlns="http://www.w3.org/2005/Atom"><title>Atom</title><id>urn:feed</id>
+<updated>2026-10-16T09:30:00Z</updated>
+<entry><title>Published</title><id>urn:1</id><published>2026-10-15T23:30:00-02:00</published>
+<updated>2026-10-16T09:30:00Z</updated></entry>
+<entry><title>Updated only</title><id>urn:2</id><updated>2026-10-14T08:00:00Z</updated></entry>
+</feed>"#;
+        let result = parse_feed(atom.as_bytes(), URL, DEFAULT_MAX_CONTENT_LEN).unwrap();
+        let published: Vec<_> = result.articles.iter().map(|a| a.published).collect();
+        assert_eq!(
+            published,
+            [
+                Some(utc("2026-10-16T01:30:00Z")),
+                Some(utc("2026-10-14T08:00:00Z"))
+            ]
+        );
+        assert_eq!(
+            result.articles[0].published_display().as_deref(),
+            Some("2026-10-16 01:30")
+        );
+    }
+
+    #[test]
+    fn sort_articles_puts_undated_last_in_both_directions() {
+        let mut articles = vec![
+            article("Undated", None),
+            article("Middle", Some("2026-10-10T00:00:00Z")),
+            article("Oldest", Some("2026-10-01T00:00:00Z")),
+            article("Also undated", None),
+            article("Newest", Some("2026-10-15T00:00:00Z")),
+        ];
+        sort_articles(&mut articles, SortOrder::Newest);
+        assert_eq!(
+            titles(&articles),
+            ["Newest", "Middle", "Oldest", "Undated", "Also undated"]
+        );
+        sort_articles(&mut articles, SortOrder::Oldest);
+        assert_eq!(
+            titles(&articles),
+            ["Oldest", "Middle", "Newest", "Undated", "Also undated"]
+        );
+    }
 }
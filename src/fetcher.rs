@@ -0,0 +1,252 @@
+use crate::cache::FeedCache;
+use crate::feed::{self, Article, FeedError, FeedResult, FetchOptions};
+use crate::http::Transport;
+use crate::storage::Feed;
+use futures::stream::{self, BoxStream, StreamExt};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Feeds a [`Fetcher`] fetches at once unless told otherwise.
+pub const DEFAULT_CONCURRENCY: usize = 8;
+
+/// Progress of a [`Fetcher`], for callers that want to show it.
+#[derive(Debug)]
+pub enum FetchEvent<'a> {
+    /// The feed was fetched, or revalidated, with this many articles
+    Fetched {
+        url: &'a str,
+        articles: usize,
+        elapsed: Duration,
+    },
+    /// The server reported the cached copy as still current
+    NotModified { url: &'a str },
+    /// The feed couldn't be reached, so its cached copy is used instead
+    Stale { url: &'a str, error: &'a FeedError },
+    /// The linked pages of `filled` of `total` articles were fetched
+    FullText {
+        url: &'a str,
+        filled: usize,
+        total: usize,
+    },
+}
+
+/// Fetches subscribed feeds concurrently, through the feed cache when one
+/// is given.
+#[derive(Clone, Copy)]
+pub struct Fetcher<'a> {
+    pub transport: &'a dyn Transport,
+    pub options: &'a FetchOptions,
+    /// Number of feeds fetched at once
+    pub concurrency: usize,
+    /// Cached copies to revalidate and fall back on; updated with every
+    /// fetch. Saving it is up to the caller.
+    pub cache: Option<&'a Mutex<FeedCache>>,
+    /// Receives each [`FetchEvent`]
+    pub report: Option<&'a (dyn Fn(FetchEvent<'_>) + Sync)>,
+}
+
+impl<'a> Fetcher<'a> {
+    /// A fetcher without a cache or progress reports.
+    pub fn new(transport: &'a dyn Transport, options: &'a FetchOptions) -> Self {
+        Fetcher {
+            transport,
+            options,
+            concurrency: DEFAULT_CONCURRENCY,
+            cache: None,
+            report: None,
+        }
+    }
+
+    fn report(&self, event: FetchEvent<'_>) {
+        if let Some(report) = self.report {
+            report(event);
+        }
+    }
+
+    /// Fetches `feeds`, yielding each with its result cut to `limit`
+    /// articles. When `ordered` is set results come back in the order of
+    /// `feeds`; otherwise each is yielded as soon as its fetch completes.
+    pub fn fetch_feeds(
+        self,
+        feeds: &'a [Feed],
+        limit: Option<usize>,
+        ordered: bool,
+    ) -> BoxStream<'a, (&'a Feed, Result<FeedResult, FeedError>)> {
+        let fetches = stream::iter(feeds).map(move |feed| async move {
+            let started = Instant::now();
+            let result = match self.cache {
+                Some(cache) => self.fetch_cached(feed, cache).await,
+                None => {
+                    let headers = feed.request_headers();
+                    feed::fetch_feed_with(self.transport, &feed.url, &headers, self.options, None)
+                        .await
+                }
+            };
+            if let Ok(result) = &result {
+                self.report(FetchEvent::Fetched {
+                    url: &feed.url,
+                    articles: result.articles.len(),
+                    elapsed: started.elapsed(),
+                });
+            }
+            let result = result.map(|mut result| {
+                if let Some(limit) = limit {
+                    result.articles.truncate(limit);
+                }
+                result
+            });
+            (feed, result)
+        });
+        let concurrency = self.concurrency.max(1);
+        if ordered {
+            fetches.buffered(concurrency).boxed()
+        } else {
+            fetches.buffer_unordered(concurrency).boxed()
+        }
+    }
+
+    /// Fetches a feed, revalidating the cached copy if there is one. The
+    /// cached articles are also used when the feed can't be reached.
+    async fn fetch_cached(
+        self,
+        feed: &Feed,
+        cache: &Mutex<FeedCache>,
+    ) -> Result<FeedResult, FeedError> {
+        let url = feed.url.as_str();
+        let cached = cache.lock().unwrap().get(url).cloned();
+        let headers = feed.request_headers();
+        match feed::fetch_feed_conditional(
+            self.transport,
+            url,
+            &headers,
+            self.options,
+            cached.as_ref(),
+        )
+        .await
+        {
+            Ok(fetched) => {
+                if fetched.not_modified {
+                    self.report(FetchEvent::NotModified { url });
+                }
+                let result = fetched.result.clone();
+                cache.lock().unwrap().insert(url, fetched);
+                Ok(result)
+            }
+            Err(e @ (FeedError::Network(_) | FeedError::Timeout(_))) => match cached {
+                Some(cached) => {
+                    self.report(FetchEvent::Stale { url, error: &e });
+                    Ok(cached.result)
+                }
+                None => Err(e),
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Replaces the content of `feed`'s articles with the text of their
+    /// linked pages, as by [`feed::fill_full_text`].
+    pub async fn fill_full_text(self, feed: &Feed, articles: &mut [Article]) {
+        let filled =
+            feed::fill_full_text(self.transport, articles, self.options.max_content_len).await;
+        self.report(FetchEvent::FullText {
+            url: &feed.url,
+            filled,
+            total: articles.len(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::CachedFeed;
+    use crate::http::StubTransport;
+
+    /// An RSS document with one item per title.
+    fn rss(titles: &[&str]) -> String {
+        let items: String = titles
+            .iter()
+            .map(|t| format!("<item><title>{}</title></item>", t))
+            .collect();
+        format!(
+            r#"<rss version="2.0"><channel><title>Test</title>{}</channel></rss>"#,
+            items
+        )
+    }
+
+    #[tokio::test]
+    async fn fetch_feeds_yields_every_feed_in_either_mode() {
+        let transport = StubTransport::default()
+            .respond("https://a.example.com/rss", 200, rss(&["a1", "a2", "a3"]))
+            .respond("https://b.example.com/rss", 200, rss(&["b1"]))
+            .respond("https://c.example.com/rss", 200, rss(&["c1", "c2"]));
+        let options = FetchOptions::default();
+        let fetcher = Fetcher {
+            concurrency: 2,
+            ..Fetcher::new(&transport, &options)
+        };
+        let feeds: Vec<Feed> = ["a", "b", "c", "unmapped"]
+            .iter()
+            .map(|host| Feed::new(&format!("https://{}.example.com/rss", host)))
+            .collect();
+
+        for ordered in [true, false] {
+            let mut results: Vec<(String, Option<usize>)> = fetcher
+                .fetch_feeds(&feeds, Some(2), ordered)
+                .map(|(feed, result)| (feed.url.clone(), result.ok().map(|r| r.articles.len())))
+                .collect()
+                .await;
+            if !ordered {
+                results.sort();
+            }
+            assert_eq!(
+                results,
+                [
+                    ("https://a.example.com/rss".to_string(), Some(2)),
+                    ("https://b.example.com/rss".to_string(), Some(1)),
+                    ("https://c.example.com/rss".to_string(), Some(2)),
+                    ("https://unmapped.example.com/rss".to_string(), None),
+                ]
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn an_unreachable_feed_falls_back_on_its_cached_copy() {
+        let url = "https://a.example.com/rss";
+        let cached = crate::feed::parse_feed(
+            rss(&["cached"]).as_bytes(),
+            url,
+            crate::feed::DEFAULT_MAX_CONTENT_LEN,
+        )
+        .unwrap();
+        let mut cache = FeedCache::default();
+        cache.insert(
+            url,
+            CachedFeed {
+                etag: None,
+                last_modified: None,
+                result: cached,
+                last_fetched: None,
+                not_modified: false,
+            },
+        );
+        let cache = Mutex::new(cache);
+        let events = Mutex::new(Vec::new());
+        let report = |event: FetchEvent<'_>| events.lock().unwrap().push(format!("{:?}", event));
+        let transport = StubTransport::default();
+        let options = FetchOptions::default();
+        let fetcher = Fetcher {
+            cache: Some(&cache),
+            report: Some(&report),
+            ..Fetcher::new(&transport, &options)
+        };
+
+        let feeds = [Feed::new(url)];
+        let results: Vec<_> = fetcher.fetch_feeds(&feeds, None, true).collect().await;
+        let articles = &results[0].1.as_ref().unwrap().articles;
+        assert_eq!(articles[0].title, "cached");
+        let events = events.into_inner().unwrap();
+        assert!(events[0].starts_with("Stale"), "{:?}", events);
+    }
+}
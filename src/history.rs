@@ -0,0 +1,307 @@
+use crate::analysis::{ArticleMention, Sentiment};
+use crate::storage;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A mention found by a past scan.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MentionRecord {
+    pub ticker: String,
+    pub sentiment: Sentiment,
+    pub title: String,
+    pub link: Option<String>,
+    pub published: Option<String>,
+    /// Date (YYYY-MM-DD) the mention was first seen by a scan
+    pub recorded: String,
+}
+
+impl MentionRecord {
+    /// Date used for bucketing: the article's publish date, falling back to
+    /// when the mention was recorded. None if neither can be parsed.
+    pub fn date(&self) -> Option<NaiveDate> {
+        self.published
+            .as_deref()
+            .and_then(parse_date)
+            .or_else(|| parse_date(&self.recorded))
+    }
+
+    fn same_mention(&self, mention: &ArticleMention) -> bool {
+        self.ticker == mention.ticker
+            && match (&self.link, &mention.article.link) {
+                (Some(a), Some(b)) => a == b,
+                _ => self.title == mention.article.title,
+            }
+    }
+}
+
+/// When to compact the history automatically on load.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistoryLimits {
+    /// File size in bytes above which old mentions are dropped on load
+    pub max_bytes: u64,
+    /// Age in days of the mentions kept by an automatic compaction
+    pub keep_days: u32,
+}
+
+impl Default for HistoryLimits {
+    fn default() -> Self {
+        HistoryLimits {
+            max_bytes: 1024 * 1024,
+            keep_days: 365,
+        }
+    }
+}
+
+/// Mentions persisted across scans in `history.json` next to the config.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MentionHistory {
+    pub mentions: Vec<MentionRecord>,
+}
+
+impl MentionHistory {
+    /// Loads the history, compacting it first if the file has grown past
+    /// `limits.max_bytes`.
+    pub fn load(dir: &Path, limits: &HistoryLimits) -> io::Result<Self> {
+        let path = history_path(dir);
+        if !path.exists() {
+            return Ok(MentionHistory::default());
+        }
+        let content = fs::read_to_string(&path)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+        let mut history: MentionHistory = serde_json::from_str(&content).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), e),
+            )
+        })?;
+
+        if content.len() as u64 > limits.max_bytes {
+            let today = chrono::Utc::now().date_naive();
+            if history.compact(limits.keep_days, today) > 0 {
+                history.save(dir)?;
+            }
+        }
+        Ok(history)
+    }
+
+    /// Writes the history to a temporary file and renames it into place, so
+    /// an interrupted save never leaves a truncated file behind.
+    pub fn save(&self, dir: &Path) -> io::Result<()> {
+        let path = history_path(dir);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string_pretty(self)?;
+        storage::write_atomic(&path, content.as_bytes())
+    }
+
+    /// Drops mentions recorded more than `keep_days` days before `today`.
+    /// Returns how many were removed.
+    pub fn compact(&mut self, keep_days: u32, today: NaiveDate) -> usize {
+        let cutoff = today - Duration::days(keep_days as i64);
+        let before = self.mentions.len();
+        self.mentions.retain(|r| {
+            NaiveDate::parse_from_str(&r.recorded, "%Y-%m-%d").map_or(true, |d| d >= cutoff)
+        });
+        before - self.mentions.len()
+    }
+
+    /// Adds mentions not already in the history and returns the new records.
+    pub fn record(&mut self, mentions: &[ArticleMention]) -> Vec<MentionRecord> {
+        let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+        let mut added = Vec::new();
+
+        for mention in mentions {
+            if self.mentions.iter().any(|r| r.same_mention(mention)) {
+                continue;
+            }
+            let record = MentionRecord {
+                ticker: mention.ticker.clone(),
+                sentiment: mention.sentiment,
+                title: mention.article.title.clone(),
+                link: mention.article.link.clone(),
+                published: mention.article.published_display(),
+                recorded: today.clone(),
+            };
+            self.mentions.push(record.clone());
+            added.push(record);
+        }
+
+        added
+    }
+
+    pub fn for_ticker(&self, ticker: &str) -> Vec<&MentionRecord> {
+        self.mentions
+            .iter()
+            .filter(|r| r.ticker == ticker)
+            .collect()
+    }
+}
+
+/// Counts mentions per day for the `days` days ending on `today`, oldest first.
+/// Mentions outside the window are ignored.
+pub fn daily_counts(records: &[&MentionRecord], days: usize, today: NaiveDate) -> Vec<usize> {
+    let mut counts = vec![0; days];
+    for record in records {
+        let Some(date) = record.date() else {
+            continue;
+        };
+        let age = (today - date).num_days();
+        if age >= 0 && (age as usize) < days {
+            counts[days - 1 - age as usize] += 1;
+        }
+    }
+    counts
+}
+
+/// Renders values as a row of block characters scaled to the largest value.
+/// Zeros render as the lowest block.
+pub fn sparkline(values: &[usize]) -> String {
+    const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let max = values.iter().copied().max().unwrap_or(0);
+    values
+        .iter()
+        .map(|&v| {
+            let level = (v * (LEVELS.len() - 1)).checked_div(max).unwrap_or(0);
+            LEVELS[level]
+        })
+        .collect()
+}
+
+/// First day of a `days`-long window ending on `today`.
+pub fn window_start(days: usize, today: NaiveDate) -> NaiveDate {
+    today - Duration::days(days.saturating_sub(1) as i64)
+}
+
+/// Parses the date of a timestamp as written by this or an older version:
+/// `YYYY-MM-DD[ HH:MM]`, RFC 3339 or RFC 2822.
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    let value = value.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Some(dt.with_timezone(&Utc).date_naive());
+    }
+    if let Ok(dt) = DateTime::parse_from_rfc2822(value) {
+        return Some(dt.with_timezone(&Utc).date_naive());
+    }
+    let day = value.split_whitespace().next()?;
+    NaiveDate::parse_from_str(day, "%Y-%m-%d").ok()
+}
+
+fn history_path(dir: &Path) -> PathBuf {
+    dir.join("history.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(ticker: &str, published: Option<&str>, recorded: &str) -> MentionRecord {
+        MentionRecord {
+            ticker: ticker.to_string(),
+            sentiment: Sentiment::Neutral,
+            title: "Title".to_string(),
+            link: None,
+            published: published.map(str::to_string),
+            recorded: recorded.to_string(),
+        }
+    }
+
+    fn day(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn sparkline_scales_to_the_busiest_day() {
+        assert_eq!(sparkline(&[0, 1, 2, 4]), "▁▂▄█");
+        assert_eq!(sparkline(&[0, 0]), "▁▁");
+        assert_eq!(sparkline(&[]), "");
+    }
+
+    #[test]
+    fn daily_counts_buckets_by_publish_date_oldest_first() {
+        let records = [
+            record("AAPL", Some("2026-10-16 09:30"), "2026-10-16"),
+            record("AAPL", Some("2026-10-16T22:00:00Z"), "2026-10-16"),
+            record(
+                "AAPL",
+                Some("Wed, 14 Oct 2026 08:00:00 +0000"),
+                "2026-10-16",
+            ),
+            record("AAPL", None, "2026-10-15"),
+            record("AAPL", Some("2026-10-01"), "2026-10-16"),
+            record("AAPL", Some("2026-10-17"), "2026-10-16"),
+            record("AAPL", Some("someday"), "unknown"),
+        ];
+        let refs: Vec<&MentionRecord> = records.iter().collect();
+        assert_eq!(daily_counts(&refs, 4, day("2026-10-16")), [0, 1, 1, 2]);
+        assert_eq!(window_start(4, day("2026-10-16")), day("2026-10-13"));
+    }
+
+    #[test]
+    fn compact_drops_mentions_recorded_before_the_cutoff() {
+        let mut history = MentionHistory {
+            mentions: vec![
+                record("AAPL", None, "2026-09-15"),
+                record("AAPL", None, "2026-09-16"),
+                record("AAPL", None, "2026-10-16"),
+                record("AAPL", None, "not a date"),
+            ],
+        };
+        assert_eq!(history.compact(30, day("2026-10-16")), 1);
+        let recorded: Vec<_> = history
+            .mentions
+            .iter()
+            .map(|r| r.recorded.as_str())
+            .collect();
+        assert_eq!(recorded, ["2026-09-16", "2026-10-16", "not a date"]);
+        assert_eq!(history.compact(30, day("2026-10-16")), 0);
+    }
+
+    #[test]
+    fn load_compacts_files_over_the_size_limit() {
+        let dir = crate::test_dir("history-auto-compact");
+        let today = Utc::now().format("%Y-%m-%d").to_string();
+        let history = MentionHistory {
+            mentions: vec![
+                record("AAPL", None, "2000-01-01"),
+                record("AAPL", None, &today),
+            ],
+        };
+        history.save(&dir).unwrap();
+
+        let roomy = HistoryLimits::default();
+        assert_eq!(
+            MentionHistory::load(&dir, &roomy).unwrap().mentions.len(),
+            2
+        );
+
+        let tight = HistoryLimits {
+            max_bytes: 10,
+            keep_days: 30,
+        };
+        assert_eq!(
+            MentionHistory::load(&dir, &tight).unwrap().mentions.len(),
+            1
+        );
+        // The compacted history was written back.
+        assert_eq!(
+            MentionHistory::load(&dir, &roomy).unwrap().mentions.len(),
+            1
+        );
+    }
+
+    #[test]
+    fn parse_date_accepts_stored_formats() {
+        let expected = Some(day("2026-10-16"));
+        assert_eq!(parse_date("2026-10-16 09:30"), expected);
+        assert_eq!(parse_date("2026-10-16"), expected);
+        assert_eq!(parse_date("2026-10-16T09:30:00Z"), expected);
+        assert_eq!(parse_date("2026-10-15T22:30:00-04:00"), expected);
+        assert_eq!(parse_date("Fri, 16 Oct 2026 09:30:00 GMT"), expected);
+        assert_eq!(parse_date("yesterday"), None);
+    }
+}
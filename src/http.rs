@@ -0,0 +1,729 @@
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// A completed HTTP GET response.
+#[derive(Debug, Clone)]
+pub struct Response {
+    /// URL the response came from, after following any redirects
+    pub url: String,
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Value of the first header named `name`, compared case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Failure to get any response at all.
+#[derive(Debug)]
+pub enum TransportError {
+    /// The request did not complete within the configured timeout
+    Timeout(Duration),
+    /// Connection, TLS, DNS or fixture lookup failure
+    Network(String),
+}
+
+impl fmt::Display for TransportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransportError::Timeout(after) => write!(f, "timed out after {}s", after.as_secs()),
+            TransportError::Network(msg) => write!(f, "network error: {}", msg),
+        }
+    }
+}
+
+impl Error for TransportError {}
+
+/// Failure to set up a transport, before any request is made.
+#[derive(Debug)]
+pub enum SetupError {
+    /// The HTTP client could not be built, e.g. because of an invalid proxy URL
+    Client(String),
+    /// The fixture manifest is missing or unreadable
+    ManifestRead(PathBuf, io::Error),
+    /// The fixture manifest is not a JSON object mapping URLs to file names
+    ManifestParse(PathBuf, serde_json::Error),
+}
+
+impl fmt::Display for SetupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SetupError::Client(msg) => write!(f, "{}", msg),
+            SetupError::ManifestRead(path, e) => write!(f, "Cannot read {}: {}", path.display(), e),
+            SetupError::ManifestParse(path, e) => {
+                write!(f, "Invalid manifest {}: {}", path.display(), e)
+            }
+        }
+    }
+}
+
+impl Error for SetupError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SetupError::Client(_) => None,
+            SetupError::ManifestRead(_, e) => Some(e),
+            SetupError::ManifestParse(_, e) => Some(e),
+        }
+    }
+}
+
+/// Performs HTTP GET requests. Implemented over reqwest for real use and
+/// over fixture files for offline runs and tests.
+#[async_trait]
+pub trait Transport: Send + Sync {
+    async fn get(&self, url: &str, headers: &[(&str, &str)]) -> Result<Response, TransportError>;
+}
+
+/// User-Agent sent when none is configured.
+pub const DEFAULT_USER_AGENT: &str = concat!("aaron_rss/", env!("CARGO_PKG_VERSION"));
+
+/// Settings for the shared HTTP client.
+#[derive(Debug, Clone)]
+pub struct HttpOptions {
+    /// Idle connections kept open per host for reuse
+    pub pool_max_idle_per_host: usize,
+    /// How long an idle pooled connection is kept before closing
+    pub pool_idle_timeout: Duration,
+    /// Limit on each whole request, from connecting to reading the body
+    pub timeout: Duration,
+    pub user_agent: String,
+    /// Proxy URL for every request. Without one, reqwest uses HTTP_PROXY,
+    /// HTTPS_PROXY and ALL_PROXY from the environment; NO_PROXY is honored
+    /// either way.
+    pub proxy: Option<String>,
+}
+
+impl Default for HttpOptions {
+    /// The command line defaults: 8 idle connections per host kept for 90s,
+    /// a 15s request timeout and no explicit proxy.
+    fn default() -> Self {
+        HttpOptions {
+            pool_max_idle_per_host: 8,
+            pool_idle_timeout: Duration::from_secs(90),
+            timeout: Duration::from_secs(15),
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            proxy: None,
+        }
+    }
+}
+
+/// Transport over a single pooled `reqwest::Client`, shared by every feed and
+/// stock request in a run so connections (and HTTP/2 streams, negotiated via
+/// ALPN) are reused between requests to the same host.
+pub struct HttpTransport {
+    client: reqwest::Client,
+    timeout: Duration,
+}
+
+impl HttpTransport {
+    pub fn new(options: &HttpOptions) -> Result<Self, SetupError> {
+        let client_error = |e: reqwest::Error| SetupError::Client(e.to_string());
+        let mut builder = reqwest::Client::builder();
+        if let Some(proxy) = &options.proxy {
+            let proxy = reqwest::Proxy::all(proxy).map_err(client_error)?;
+            builder = builder.proxy(proxy.no_proxy(reqwest::NoProxy::from_env()));
+        }
+        let client = builder
+            .pool_max_idle_per_host(options.pool_max_idle_per_host)
+            .pool_idle_timeout(options.pool_idle_timeout)
+            .http2_adaptive_window(true)
+            // Advertise compressed encodings via Accept-Encoding; bodies are
+            // decoded before they reach the feed parser.
+            .gzip(true)
+            .deflate(true)
+            .brotli(true)
+            .timeout(options.timeout)
+            .user_agent(&options.user_agent)
+            .build()
+            .map_err(client_error)?;
+        Ok(HttpTransport {
+            client,
+            timeout: options.timeout,
+        })
+    }
+
+    fn transport_error(&self, e: reqwest::Error) -> TransportError {
+        if e.is_timeout() {
+            TransportError::Timeout(self.timeout)
+        } else {
+            TransportError::Network(e.to_string())
+        }
+    }
+}
+
+#[async_trait]
+impl Transport for HttpTransport {
+    async fn get(&self, url: &str, headers: &[(&str, &str)]) -> Result<Response, TransportError> {
+        let mut request = self.client.get(url);
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        let response = request.send().await.map_err(|e| self.transport_error(e))?;
+        let final_url = response.url().to_string();
+        let status = response.status().as_u16();
+        let headers = response
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| self.transport_error(e))?
+            .to_vec();
+        Ok(Response {
+            url: final_url,
+            status,
+            headers,
+            body,
+        })
+    }
+}
+
+/// Offline transport: URLs are mapped to files via `manifest.json` in the fixture directory.
+pub struct FixtureTransport {
+    dir: PathBuf,
+    manifest: HashMap<String, String>,
+}
+
+impl FixtureTransport {
+    pub fn load(dir: &Path) -> Result<Self, SetupError> {
+        let manifest_path = dir.join("manifest.json");
+        let content = match fs::read_to_string(&manifest_path) {
+            Ok(content) => content,
+            Err(e) => return Err(SetupError::ManifestRead(manifest_path, e)),
+        };
+        let manifest = match serde_json::from_str(&content) {
+            Ok(manifest) => manifest,
+            Err(e) => return Err(SetupError::ManifestParse(manifest_path, e)),
+        };
+        Ok(FixtureTransport {
+            dir: dir.to_path_buf(),
+            manifest,
+        })
+    }
+}
+
+#[async_trait]
+impl Transport for FixtureTransport {
+    async fn get(&self, url: &str, _headers: &[(&str, &str)]) -> Result<Response, TransportError> {
+        let file = self
+            .manifest
+            .get(url)
+            .ok_or_else(|| TransportError::Network(format!("no fixture mapped for {}", url)))?;
+        let path = self.dir.join(file);
+        let body = fs::read(&path).map_err(|e| {
+            TransportError::Network(format!("cannot read fixture {}: {}", path.display(), e))
+        })?;
+        Ok(Response {
+            url: url.to_string(),
+            status: 200,
+            headers: Vec::new(),
+            body,
+        })
+    }
+}
+
+/// First delay before retrying; doubled after every further failure.
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// Retries transient failures of another transport with exponential backoff:
+/// network errors, timeouts, HTTP 429 and 5xx responses. Other responses,
+/// including 404, are returned straight away.
+pub struct RetryTransport<T> {
+    inner: T,
+    retries: u32,
+}
+
+impl<T: Transport> RetryTransport<T> {
+    pub fn new(inner: T, retries: u32) -> Self {
+        RetryTransport { inner, retries }
+    }
+}
+
+fn is_transient(status: u16) -> bool {
+    status == 429 || (500..600).contains(&status)
+}
+
+/// Delay before retry number `attempt` (0-based), with up to 50% random
+/// jitter so concurrent requests don't retry in lockstep.
+fn backoff(attempt: u32) -> Duration {
+    let delay = RETRY_BASE_DELAY * 2u32.saturating_pow(attempt);
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+    delay + delay.mul_f64((nanos % 1000) as f64 / 2000.0)
+}
+
+#[async_trait]
+impl<T: Transport> Transport for RetryTransport<T> {
+    async fn get(&self, url: &str, headers: &[(&str, &str)]) -> Result<Response, TransportError> {
+        let mut attempt = 0;
+        loop {
+            let result = self.inner.get(url, headers).await;
+            let transient = match &result {
+                Ok(response) => is_transient(response.status),
+                Err(_) => true,
+            };
+            if !transient || attempt >= self.retries {
+                return result;
+            }
+            tokio::time::sleep(backoff(attempt)).await;
+            attempt += 1;
+        }
+    }
+}
+
+/// Canned responses for unit tests, looked up by URL. A URL given several
+/// responses gets them in turn, the last one repeating; any other URL is a
+/// network error.
+#[cfg(test)]
+#[derive(Default)]
+pub(crate) struct StubTransport {
+    responses: std::sync::Mutex<HashMap<String, Vec<Response>>>,
+}
+
+#[cfg(test)]
+impl StubTransport {
+    pub(crate) fn respond(self, url: &str, status: u16, body: impl Into<Vec<u8>>) -> Self {
+        self.respond_with(
+            url,
+            Response {
+                url: url.to_string(),
+                status,
+                headers: Vec::new(),
+                body: body.into(),
+            },
+        )
+    }
+
+    pub(crate) fn respond_with(self, url: &str, response: Response) -> Self {
+        self.responses
+            .lock()
+            .unwrap()
+            .entry(url.to_string())
+            .or_default()
+            .push(response);
+        self
+    }
+}
+
+#[cfg(test)]
+#[async_trait]
+impl Transport for StubTransport {
+    async fn get(&self, url: &str, _headers: &[(&str, &str)]) -> Result<Response, TransportError> {
+        let mut responses = self.responses.lock().unwrap();
+        let queue = responses
+            .get_mut(url)
+            .filter(|queue| !queue.is_empty())
+            .ok_or_else(|| TransportError::Network(format!("no stub for {}", url)))?;
+        if queue.len() > 1 {
+            Ok(queue.remove(0))
+        } else {
+            Ok(queue[0].clone())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixtures(name: &str, manifest: &str) -> PathBuf {
+        let dir = crate::test_dir(name);
+        fs::write(dir.join("manifest.json"), manifest).unwrap();
+        dir
+    }
+
+    /// Serves `ok` to every request on a local port, keeping connections
+    /// alive, and counts the connections it accepted.
+    fn keep_alive_server() -> (String, std::sync::Arc<std::sync::atomic::AtomicUsize>) {
+        use std::io::{Read, Write};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let connections = std::sync::Arc::new(AtomicUsize::new(0));
+        let counter = connections.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { break };
+                counter.fetch_add(1, Ordering::SeqCst);
+                std::thread::spawn(move || {
+                    let mut buf = [0; 4096];
+                    while let Ok(n @ 1..) = stream.read(&mut buf) {
+                        let requests = buf[..n].windows(4).filter(|w| w == b"\r\n\r\n").count();
+                        for _ in 0..requests {
+                            let reply = b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok";
+                            if stream.write_all(reply).is_err() {
+                                return;
+                            }
+                        }
+                    }
+                });
+            }
+        });
+        (url, connections)
+    }
+
+    #[tokio::test]
+    async fn http_transport_reuses_one_connection() {
+        let (url, connections) = keep_alive_server();
+        let transport = HttpTransport::new(&HttpOptions::default()).unwrap();
+        for _ in 0..3 {
+            let response = transport.get(&url, &[]).await.unwrap();
+            assert_eq!(response.body, b"ok");
+        }
+        assert_eq!(connections.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    /// Answers every request on a local port with `headers` and `body`,
+    /// passing the head of each request it receives back through the
+    /// returned channel.
+    fn recording_server(
+        headers: &str,
+        body: Vec<u8>,
+    ) -> (String, std::sync::mpsc::Receiver<String>) {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/feed.xml", listener.local_addr().unwrap());
+        let reply = [
+            format!(
+                "HTTP/1.1 200 OK\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n",
+                headers,
+                body.len()
+            )
+            .into_bytes(),
+            body,
+        ]
+        .concat();
+        let (requests, received) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { break };
+                let mut head = Vec::new();
+                let mut buf = [0; 4096];
+                while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match stream.read(&mut buf) {
+                        Ok(n @ 1..) => head.extend_from_slice(&buf[..n]),
+                        _ => break,
+                    }
+                }
+                let _ = requests.send(String::from_utf8_lossy(&head).to_lowercase());
+                let _ = stream.write_all(&reply);
+            }
+        });
+        (url, received)
+    }
+
+    #[tokio::test]
+    async fn gzip_bodies_are_decoded_before_parsing() {
+        use std::io::Write;
+
+        let rss = r#"<?xml version="1.0"?><rss version="2.0"><channel><title>Zipped</title>
+<item><title>Compressed story</title><link>https://example.com/a</link></item>
+</channel></rss>"#;
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(rss.as_bytes()).unwrap();
+        let (url, requests) = recording_server(
+            "Content-Type: application/rss+xml\r\nContent-Encoding: gzip\r\n",
+            encoder.finish().unwrap(),
+        );
+
+        let transport = HttpTransport::new(&HttpOptions::default()).unwrap();
+        let options = crate::feed::FetchOptions::default();
+        let result = crate::feed::fetch_feed_with(&transport, &url, &[], &options, None)
+            .await
+            .unwrap();
+        assert_eq!(result.title, "Zipped");
+        assert_eq!(result.articles[0].title, "Compressed story");
+        let request = requests.recv().unwrap();
+        assert!(
+            request
+                .lines()
+                .any(|l| l.starts_with("accept-encoding:") && l.contains("gzip")),
+            "{}",
+            request
+        );
+    }
+
+    #[tokio::test]
+    async fn requests_carry_the_configured_user_agent() {
+        let (url, requests) = recording_server("", b"ok".to_vec());
+        let default = HttpTransport::new(&HttpOptions::default()).unwrap();
+        default.get(&url, &[]).await.unwrap();
+        let request = requests.recv().unwrap();
+        let expected = format!("user-agent: {}", DEFAULT_USER_AGENT.to_lowercase());
+        assert!(request.lines().any(|l| l == expected), "{}", request);
+        assert!(DEFAULT_USER_AGENT.starts_with("aaron_rss/"));
+
+        let custom = HttpTransport::new(&HttpOptions {
+            user_agent: "MyReader/2.0".to_string(),
+            ..HttpOptions::default()
+        })
+        .unwrap();
+        custom.get(&url, &[]).await.unwrap();
+        let request = requests.recv().unwrap();
+        assert!(
+            request.lines().any(|l| l == "user-agent: myreader/2.0"),
+            "{}",
+            request
+        );
+    }
+
+    #[tokio::test]
+    async fn requests_go_through_the_configured_proxy() {
+        let (proxy_url, requests) = recording_server("", b"via proxy".to_vec());
+        let transport = HttpTransport::new(&HttpOptions {
+            proxy: Some(proxy_url.trim_end_matches("/feed.xml").to_string()),
+            ..HttpOptions::default()
+        })
+        .unwrap();
+        let response = transport
+            .get("http://feeds.example.invalid/rss", &[])
+            .await
+            .unwrap();
+        assert_eq!(response.body, b"via proxy");
+        let request = requests.recv().unwrap();
+        assert!(
+            request.starts_with("get http://feeds.example.invalid/rss http/1.1"),
+            "{}",
+            request
+        );
+
+        let bad = HttpOptions {
+            proxy: Some("not a proxy".to_string()),
+            ..HttpOptions::default()
+        };
+        assert!(matches!(
+            HttpTransport::new(&bad),
+            Err(SetupError::Client(_))
+        ));
+    }
+
+    /// Serves an RSS feed at /new on a local port and permanently
+    /// redirects /old there.
+    fn moved_feed_server() -> String {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let rss = r#"<rss version="2.0"><channel><title>Moved</title>
+<item><title>Still here</title></item></channel></rss>"#;
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { break };
+                let mut buf = [0; 4096];
+                let n = stream.read(&mut buf).unwrap_or(0);
+                let reply = if buf[..n].starts_with(b"GET /old ") {
+                    "HTTP/1.1 301 Moved Permanently\r\nLocation: /new\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+                } else {
+                    format!(
+                        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        rss.len(),
+                        rss
+                    )
+                };
+                let _ = stream.write_all(reply.as_bytes());
+            }
+        });
+        base
+    }
+
+    #[tokio::test]
+    async fn redirected_feeds_report_their_new_url() {
+        let base = moved_feed_server();
+        let old = format!("{}/old", base);
+        let transport = HttpTransport::new(&HttpOptions::default()).unwrap();
+        let options = crate::feed::FetchOptions::default();
+        let result = crate::feed::fetch_feed_with(&transport, &old, &[], &options, None)
+            .await
+            .unwrap();
+        let new = format!("{}/new", base);
+        assert_eq!(result.articles[0].title, "Still here");
+        assert_eq!(result.final_url, new);
+        assert_eq!(result.redirected_to(&old), Some(new.as_str()));
+        assert_eq!(result.redirected_to(&new), None);
+    }
+
+    #[tokio::test]
+    async fn feed_logins_are_sent_as_basic_auth() {
+        use crate::storage::{Feed, FeedAuth};
+
+        let rss = r#"<rss version="2.0"><channel><title>Private</title></channel></rss>"#;
+        let (url, requests) = recording_server("", rss.as_bytes().to_vec());
+        let transport = HttpTransport::new(&HttpOptions::default()).unwrap();
+        let options = crate::feed::FetchOptions::default();
+        let private = Feed {
+            auth: Some(FeedAuth {
+                username: "reader".to_string(),
+                password: "s3cret".to_string(),
+            }),
+            ..Feed::new(&url)
+        };
+        crate::feed::fetch_feed_with(&transport, &url, &private.request_headers(), &options, None)
+            .await
+            .unwrap();
+        let request = requests.recv().unwrap();
+        let expected = format!(
+            "authorization: {}",
+            private.auth.as_ref().unwrap().header().to_lowercase()
+        );
+        assert!(request.lines().any(|l| l == expected), "{}", request);
+
+        let public = Feed::new(&url);
+        crate::feed::fetch_feed_with(&transport, &url, &public.request_headers(), &options, None)
+            .await
+            .unwrap();
+        let request = requests.recv().unwrap();
+        assert!(!request.contains("authorization:"), "{}", request);
+    }
+
+    #[tokio::test]
+    async fn custom_headers_and_the_referer_are_sent() {
+        use crate::storage::Feed;
+
+        let rss = r#"<rss version="2.0"><channel><title>Custom</title></channel></rss>"#;
+        let (url, requests) = recording_server("", rss.as_bytes().to_vec());
+        let transport = HttpTransport::new(&HttpOptions::default()).unwrap();
+        let options = crate::feed::FetchOptions {
+            headers: vec![
+                ("X-Global".to_string(), "everywhere".to_string()),
+                ("X-Token".to_string(), "global".to_string()),
+            ],
+            ..Default::default()
+        };
+        let mut feed = Feed::new(&url);
+        feed.headers
+            .insert("X-Token".to_string(), "per-feed".to_string());
+        feed.headers
+            .insert("Cookie".to_string(), "session=1".to_string());
+        crate::feed::fetch_feed_with(&transport, &url, &feed.request_headers(), &options, None)
+            .await
+            .unwrap();
+        let request = requests.recv().unwrap();
+        let origin = url.trim_end_matches("feed.xml");
+        for expected in [
+            "x-global: everywhere".to_string(),
+            "x-token: per-feed".to_string(),
+            "cookie: session=1".to_string(),
+            format!("referer: {}", origin),
+        ] {
+            assert!(request.lines().any(|l| l == expected), "{}", request);
+        }
+        assert!(!request.contains("x-token: global"), "{}", request);
+    }
+
+    #[tokio::test]
+    async fn fixture_transport_serves_mapped_files() {
+        let dir = fixtures(
+            "fixtures-mapped",
+            r#"{"https://example.com/feed.xml": "feed.xml"}"#,
+        );
+        fs::write(dir.join("feed.xml"), "<rss/>").unwrap();
+        let transport = FixtureTransport::load(&dir).unwrap();
+
+        let response = transport
+            .get("https://example.com/feed.xml", &[])
+            .await
+            .unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, b"<rss/>");
+        assert!(matches!(
+            transport.get("https://example.com/other.xml", &[]).await,
+            Err(TransportError::Network(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn fixture_transport_reports_missing_files() {
+        let dir = fixtures(
+            "fixtures-missing",
+            r#"{"https://example.com/": "gone.xml"}"#,
+        );
+        let transport = FixtureTransport::load(&dir).unwrap();
+        match transport.get("https://example.com/", &[]).await {
+            Err(TransportError::Network(msg)) => assert!(msg.contains("gone.xml")),
+            other => panic!("expected a network error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn fixture_manifest_must_exist_and_parse() {
+        let dir = crate::test_dir("fixtures-no-manifest");
+        assert!(matches!(
+            FixtureTransport::load(&dir),
+            Err(SetupError::ManifestRead(..))
+        ));
+        let dir = fixtures("fixtures-bad-manifest", "[1, 2]");
+        assert!(matches!(
+            FixtureTransport::load(&dir),
+            Err(SetupError::ManifestParse(..))
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retries_transient_failures_until_success() {
+        let url = "https://example.com/feed.xml";
+        let stub = StubTransport::default()
+            .respond(url, 503, "busy")
+            .respond(url, 503, "busy")
+            .respond(url, 200, "ok");
+        let response = RetryTransport::new(stub, 3).get(url, &[]).await.unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, b"ok");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn gives_up_after_the_last_retry() {
+        let url = "https://example.com/feed.xml";
+        let stub = StubTransport::default()
+            .respond(url, 503, "busy")
+            .respond(url, 429, "slow down")
+            .respond(url, 200, "ok");
+        let response = RetryTransport::new(stub, 1).get(url, &[]).await.unwrap();
+        assert_eq!(response.status, 429);
+        let missing = RetryTransport::new(StubTransport::default(), 2)
+            .get(url, &[])
+            .await;
+        assert!(matches!(missing, Err(TransportError::Network(_))));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn does_not_retry_other_statuses() {
+        let url = "https://example.com/feed.xml";
+        let stub = StubTransport::default()
+            .respond(url, 404, "gone")
+            .respond(url, 200, "ok");
+        let response = RetryTransport::new(stub, 3).get(url, &[]).await.unwrap();
+        assert_eq!(response.status, 404);
+    }
+
+    #[test]
+    fn backoff_doubles_with_jitter() {
+        for attempt in 0..3 {
+            let base = RETRY_BASE_DELAY * 2u32.pow(attempt);
+            let delay = backoff(attempt);
+            assert!(delay >= base && delay <= base.mul_f64(1.5), "{:?}", delay);
+        }
+    }
+}
@@ -0,0 +1,97 @@
+//! Feed fetching, stock prices and news sentiment analysis behind the
+//! `aaron_rss` command line tool, usable from other programs.
+//!
+//! - [`feed`] fetches and parses RSS, Atom and JSON feeds into [`Article`]s.
+//! - [`stock`] gets quotes and daily price history from several providers.
+//! - [`analysis`] finds articles mentioning tracked investments, scores their
+//!   sentiment and pairs them with price moves.
+//! - [`storage`] loads and saves the feed and investment lists.
+//! - [`fetcher`] fetches many feeds at once through the feed cache, which
+//!   [`scan`] and [`watch`] build on to find mentions and new articles.
+//!
+//! Every network request goes through an [`http::Transport`], so callers can
+//! use the pooled [`http::HttpTransport`], replay fixture files with
+//! [`http::FixtureTransport`], or supply their own.
+//!
+//! # Example
+//!
+//! Fetch a feed from a fixture directory and look for mentions of Apple:
+//!
+//! ```
+//! use rss_reader::analysis::{self, Lexicon, TickerBoundaries};
+//! use rss_reader::http::FixtureTransport;
+//! use rss_reader::stock::DailyPrice;
+//! use rss_reader::{feed, Investment, Sentiment};
+//! use std::fs;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let dir = std::env::temp_dir().join(format!("rss_reader_doctest_{}", std::process::id()));
+//! fs::create_dir_all(&dir)?;
+//! fs::write(
+//!     dir.join("manifest.json"),
+//!     r#"{"https://example.com/feed.xml": "feed.xml"}"#,
+//! )?;
+//! fs::write(
+//!     dir.join("feed.xml"),
+//!     r#"<rss version="2.0"><channel><title>Markets</title>
+//!         <item>
+//!           <title>AAPL surges after record quarter</title>
+//!           <pubDate>Mon, 06 Jan 2025 14:00:00 GMT</pubDate>
+//!         </item>
+//!       </channel></rss>"#,
+//! )?;
+//!
+//! let transport = FixtureTransport::load(&dir)?;
+//! let options = feed::FetchOptions::default();
+//! let url = "https://example.com/feed.xml";
+//! let result = feed::fetch_feed_with(&transport, url, &[], &options, None).await?;
+//! assert_eq!(result.title, "Markets");
+//!
+//! let mentions = analysis::find_mentions(
+//!     &result.articles,
+//!     &[Investment::new("AAPL")],
+//!     &TickerBoundaries::default(),
+//!     &Lexicon::default(),
+//!     None,
+//! );
+//! assert_eq!(mentions.len(), 1);
+//! assert_eq!(mentions[0].sentiment, Sentiment::Positive);
+//!
+//! let prices = vec![
+//!     DailyPrice { date: "2025-01-03".into(), open: None, high: None, low: None, close: 243.36, volume: None },
+//!     DailyPrice { date: "2025-01-06".into(), open: None, high: None, low: None, close: 245.00, volume: None },
+//! ];
+//! let correlations = analysis::correlate(&mentions, &prices, 0);
+//! assert_eq!(correlations[0].price, Some(245.00));
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod analysis;
+pub mod cache;
+pub mod feed;
+pub mod fetcher;
+pub mod history;
+pub mod http;
+pub mod opml;
+pub mod scan;
+pub mod seen;
+pub mod stock;
+pub mod storage;
+pub mod watch;
+
+pub use analysis::{ArticleMention, Correlation, Sentiment, SentimentScore};
+pub use feed::{Article, FeedError, FeedResult};
+pub use stock::{DailyPrice, PriceHistory, StockError, StockProvider, StockQuote};
+pub use storage::{Config, Feed, Investment};
+
+/// A fresh, empty directory under the system temp dir for a test's files.
+/// Public for the binary's tests only.
+#[doc(hidden)]
+pub fn test_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("rss-reader-{}-{}", std::process::id(), name));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
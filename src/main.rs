@@ -1,370 +1,3268 @@
-mod analysis;
-mod feed;
-mod stock;
-mod storage;
+mod exec;
+mod notify;
+mod recent;
+mod style;
+mod tui;
 
-use clap::{Parser, Subcommand};
-use storage::Config;
+use rss_reader::{
+    analysis, cache, feed, fetcher, history, http, opml, scan, seen, stock, storage, watch,
+};
+
+/// Prints an error line to stderr, in red when stderr is a colored terminal.
+macro_rules! error {
+    ($($arg:tt)*) => {
+        eprintln!("{}", style::error(&format!($($arg)*)))
+    };
+}
+
+use analysis::GroupBy;
+use cache::FeedCache;
+use chrono::{DateTime, TimeDelta, Utc, Weekday};
+use clap::{Args, Parser, Subcommand};
+use feed::{Article, FeedError, FeedHealth, FeedResult, FeedStats, FetchOptions};
+use fetcher::{FetchEvent, Fetcher};
+use futures::future;
+use futures::stream::{self, StreamExt};
+use history::MentionHistory;
+use http::{FixtureTransport, HttpOptions, HttpTransport, RetryTransport, Transport};
+use recent::RecentArticles;
+use seen::SeenStore;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Mutex;
+use std::time::Duration;
+use stock::{
+    CachingProvider, DailyPrice, PriceHistory, ProviderKind, StockCache, StockError, StockProvider,
+};
+use storage::{
+    AddOutcome, AssetKind, Config, ConfigFormat, Feed, FeedAuth, Investment, Storage, StorageKind,
+};
+
+/// Daily move in percent that `stock alerts` flags when neither the
+/// investment nor `--threshold` sets one.
+const DEFAULT_ALERT_PCT: f64 = 5.0;
+/// Longest article summary `fetch` prints before cutting it off.
+const SUMMARY_CHARS: usize = 200;
+/// Exit status of `fetch` and `scan` when some feeds couldn't be fetched.
+const EXIT_FEED_ERRORS: u8 = 2;
+/// Exit status of `stock alerts` when no quote could be fetched at all.
+const EXIT_NO_QUOTES: u8 = 3;
+/// `watch` doubles its wait after each poll in which every feed failed, up
+/// to this many times.
+const WATCH_MAX_BACKOFF_DOUBLINGS: u32 = 3;
+/// Longest `--interval` accepted, a year in minutes.
+const MAX_INTERVAL_MINS: u64 = 366 * 24 * 60;
+/// Longest `--lag` accepted, a year in days.
+const MAX_LAG_DAYS: i64 = 366;
 
 #[derive(Parser)]
 #[command(name = "aaron_rss")]
 #[command(about = "A simple command-line RSS reader")]
 struct Cli {
+    /// Emit machine-readable JSON where supported
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Never color output (also disabled by NO_COLOR or when not a terminal)
+    #[arg(long, global = true)]
+    no_color: bool,
+
+    /// Only print results and errors: no progress lines or empty-result notes
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Also report per-feed timings, article counts, cache hits and
+    /// malformed entries
+    #[arg(short, long, global = true)]
+    verbose: bool,
+
+    /// Serve feed and stock requests from fixture files listed in <dir>/manifest.json
+    #[arg(long, global = true, value_name = "DIR")]
+    mock: Option<PathBuf>,
+
+    /// Number of feeds fetched at once
+    #[arg(long, global = true, default_value_t = fetcher::DEFAULT_CONCURRENCY)]
+    concurrency: usize,
+
+    /// Always download feeds in full instead of revalidating the cached copy
+    #[arg(long, global = true)]
+    no_cache: bool,
+
+    /// Stock data provider (defaults to `stock_provider` in the config)
+    #[arg(long, global = true, value_enum)]
+    provider: Option<ProviderKind>,
+
+    /// Fetch stock data even if a fresh cached copy exists
+    #[arg(long, global = true)]
+    refresh: bool,
+
+    /// Config file to use instead of config.json in the data directory
+    /// (also settable with AARON_RSS_CONFIG); the feed cache, read state
+    /// and mention history are kept in the same directory
+    #[arg(long, global = true, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Where feeds, the feed cache and read state are stored
+    #[arg(long, global = true, value_enum, default_value_t = StorageKind::Json)]
+    storage: StorageKind,
+
+    /// Read and write the config in this format (defaults to TOML for a
+    /// .toml path, JSON otherwise)
+    #[arg(long, global = true, value_enum)]
+    config_format: Option<ConfigFormat>,
+
+    /// Characters of article content kept, cutting longer bodies with an
+    /// ellipsis; 0 keeps everything (defaults to `max_content_len` in the
+    /// config, then 10000)
+    #[arg(long, global = true, value_name = "CHARS")]
+    max_content_len: Option<usize>,
+
+    /// Extra header for every feed request, e.g. "Cookie: consent=yes"
+    /// (repeatable; a feed's own `headers` in the config take precedence)
+    #[arg(
+        long = "header",
+        global = true,
+        value_name = "NAME: VALUE",
+        value_parser = parse_header
+    )]
+    headers: Vec<(String, String)>,
+
+    #[command(flatten)]
+    http: HttpArgs,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+/// How stock data is fetched.
+#[derive(Clone, Copy)]
+struct StockOptions {
+    /// Overrides the configured provider
+    provider: Option<ProviderKind>,
+    /// Reuse quotes and histories fetched within the configured TTLs
+    cache: bool,
+    refresh: bool,
+}
+
+/// How much besides the results themselves a command prints.
+#[derive(Clone, Copy, PartialEq)]
+enum Verbosity {
+    Quiet,
+    Normal,
+    Verbose,
+}
+
+/// How command results are written.
+#[derive(Clone, Copy)]
+struct Output {
+    json: bool,
+    verbosity: Verbosity,
+}
+
+impl Output {
+    fn quiet(&self) -> bool {
+        self.verbosity == Verbosity::Quiet
+    }
+
+    fn verbose(&self) -> bool {
+        self.verbosity == Verbosity::Verbose
+    }
+
+    /// Reports an empty result set: `[]` on stdout in JSON mode, nothing in
+    /// quiet mode, otherwise `message` on stderr so a piped stdout stays empty.
+    fn empty(&self, message: &str) {
+        let (stdout, stderr) = self.empty_lines(message);
+        if let Some(line) = stdout {
+            println!("{}", line);
+        }
+        if let Some(line) = stderr {
+            eprintln!("{}", line);
+        }
+    }
+
+    /// The lines `empty` prints on stdout and stderr.
+    fn empty_lines<'a>(&self, message: &'a str) -> (Option<&'a str>, Option<&'a str>) {
+        if self.json {
+            (Some("[]"), None)
+        } else if self.quiet() {
+            (None, None)
+        } else {
+            (None, Some(message))
+        }
+    }
+
+    /// Prints a progress line, which JSON and quiet mode leave out to keep
+    /// stdout to the results.
+    fn status(&self, message: &str) {
+        if let Some(line) = self.status_line(message) {
+            println!("{}", line);
+        }
+    }
+
+    /// The line `status` prints, if any.
+    fn status_line<'a>(&self, message: &'a str) -> Option<&'a str> {
+        (!self.json && !self.quiet()).then_some(message)
+    }
+
+    /// Prints a diagnostic line on stderr in verbose mode.
+    fn detail(&self, message: &str) {
+        if self.verbose() {
+            eprintln!("{}", message);
+        }
+    }
+
+    fn print_json<T: Serialize + ?Sized>(&self, value: &T) {
+        match serde_json::to_string_pretty(value) {
+            Ok(json) => println!("{}", json),
+            Err(e) => error!("Error serializing results: {}", e),
+        }
+    }
+}
+
+/// What commands share: where requests go, where state is kept and how
+/// results are written.
+#[derive(Clone, Copy)]
+struct Context<'a> {
+    transport: &'a dyn Transport,
+    storage: &'a dyn Storage,
+    fetch: &'a FetchOptions,
+    /// Number of feeds fetched at once
+    concurrency: usize,
+    /// Feed cache, unless disabled with --no-cache or --mock
+    cache: Option<&'a Mutex<FeedCache>>,
+    out: Output,
+    /// Shows fetch progress, as by `report_fetch`
+    report: Option<&'a (dyn Fn(FetchEvent<'_>) + Sync)>,
+}
+
+impl<'a> Context<'a> {
+    fn fetcher(&self) -> Fetcher<'a> {
+        Fetcher {
+            transport: self.transport,
+            options: self.fetch,
+            concurrency: self.concurrency,
+            cache: self.cache,
+            report: self.report,
+        }
+    }
+}
+
+/// Prints a fetcher's progress: falling back on a cached copy as a warning,
+/// everything else in verbose mode only.
+fn report_fetch(out: Output, event: FetchEvent<'_>) {
+    match event {
+        FetchEvent::Fetched {
+            url,
+            articles,
+            elapsed,
+        } => out.detail(&format!(
+            "{}: {} articles in {} ms",
+            url,
+            articles,
+            elapsed.as_millis()
+        )),
+        FetchEvent::NotModified { url } => {
+            out.detail(&format!("{}: not modified, using the cached copy", url))
+        }
+        FetchEvent::Stale { url, error } => {
+            eprintln!(
+                "Warning: {} unreachable ({}), showing cached copy",
+                url, error
+            )
+        }
+        FetchEvent::FullText { url, filled, total } => out.detail(&format!(
+            "{}: fetched the full text of {} of {} articles",
+            url, filled, total
+        )),
+    }
+}
+
+#[derive(Args)]
+struct HttpArgs {
+    /// Idle connections kept open per host for reuse
+    #[arg(long, global = true, default_value_t = 8)]
+    pool_max_idle: usize,
+    /// Seconds an idle pooled connection is kept open
+    #[arg(long, global = true, default_value_t = 90)]
+    pool_idle_timeout: u64,
+    /// Seconds to wait for a request before giving up
+    #[arg(long, global = true, default_value_t = 15)]
+    timeout: u64,
+    /// Times to retry a request after a network error, HTTP 429 or 5xx
+    /// (`add` tries the new feed once)
+    #[arg(long, global = true, default_value_t = 3)]
+    retries: u32,
+    /// User-Agent header for feed and stock requests (defaults to
+    /// `user_agent` in the config, then aaron_rss/<version>)
+    #[arg(long, global = true, value_name = "STRING")]
+    user_agent: Option<String>,
+    /// Proxy URL for all requests, e.g. http://proxy:3128 (defaults to
+    /// `proxy` in the config, then HTTP_PROXY/HTTPS_PROXY)
+    #[arg(long, global = true, value_name = "URL")]
+    proxy: Option<String>,
+}
+
+impl HttpArgs {
+    fn options(&self, config: &Config) -> HttpOptions {
+        let user_agent = self
+            .user_agent
+            .clone()
+            .or_else(|| config.user_agent.clone())
+            .unwrap_or_else(|| http::DEFAULT_USER_AGENT.to_string());
+        HttpOptions {
+            pool_max_idle_per_host: self.pool_max_idle,
+            pool_idle_timeout: Duration::from_secs(self.pool_idle_timeout),
+            timeout: Duration::from_secs(self.timeout),
+            user_agent,
+            proxy: self.proxy.clone().or_else(|| config.proxy.clone()),
+        }
+    }
+}
+
 #[derive(Subcommand)]
 enum Commands {
-    /// Add a new feed URL
-    Add { url: String },
-    /// Remove a feed URL
+    /// Add a new feed URL, or update the metadata of an existing one
+    Add(AddArgs),
+    /// Remove a feed by URL or name
     Remove { url: String },
     /// List all subscribed feeds
-    List,
-    /// Fetch and display recent articles
-    Fetch {
-        /// Optional: fetch from a specific feed URL only
+    List {
+        /// Only list feeds in this group
+        #[arg(long)]
+        group: Option<String>,
+        /// Fetch every feed and report whether it is reachable and parses
+        #[arg(long)]
+        validate: bool,
+        /// Unsubscribe from feeds that returned 404 or 410
+        #[arg(long, requires = "validate")]
+        prune: bool,
+        /// Fetch every feed and show its title, article count and newest
+        /// article, to spot feeds that have gone quiet
+        #[arg(long, conflicts_with = "validate")]
+        stats: bool,
+        /// With --stats, list the feeds that published least recently first
+        #[arg(long, requires = "stats")]
+        sort_stale: bool,
+    },
+    /// List feed groups and how many feeds each holds
+    Groups,
+    /// Browse feeds and their articles in an interactive terminal reader
+    Tui {
+        /// Only show feeds in this group
+        #[arg(long)]
+        group: Option<String>,
+    },
+    /// Find articles in all subscribed feeds that mention a keyword
+    Search(SearchArgs),
+    /// Mark the current articles of a feed, or of every feed, as read
+    MarkRead {
+        /// Optional: mark a specific feed (URL or name) only
         url: Option<String>,
     },
+    /// Open an article from the last fetch in the browser, by its number
+    Open { number: usize },
+    /// Subscribe to every feed in an OPML file
+    Import { path: PathBuf },
+    /// Write all subscribed feeds to an OPML file
+    Export { path: PathBuf },
+    /// Fetch and display recent articles
+    Fetch(FetchArgs),
+    /// Poll feeds on an interval and print new articles as they appear
+    Watch(WatchArgs),
+    /// Organize subscribed feeds
+    Feeds {
+        #[command(subcommand)]
+        action: FeedsAction,
+    },
     /// Manage tracked stock investments
     Stock {
         #[command(subcommand)]
         action: StockAction,
     },
     /// Scan feeds for mentions of tracked investments
-    Scan,
-    /// Analyze news and price correlation for a ticker
-    Analyze {
+    Scan(ScanArgs),
+    /// Show recorded mention volume for a ticker
+    Stats {
         /// Stock ticker symbol
         ticker: String,
+        /// Number of days to cover
+        #[arg(long, default_value_t = 30)]
+        days: usize,
+    },
+    /// Analyze news and price correlation for one or more tickers
+    Analyze(AnalyzeArgs),
+    /// Maintain the persisted mention history
+    History {
+        #[command(subcommand)]
+        action: HistoryAction,
+    },
+}
+
+#[derive(Args)]
+struct AddArgs {
+    #[arg(value_parser = feed::normalize_feed_url)]
+    url: String,
+    /// Display name for the feed
+    #[arg(short, long)]
+    name: Option<String>,
+    /// Group to file the feed under, e.g. finance
+    #[arg(short, long)]
+    group: Option<String>,
+    /// Fetch the feed and show recent headlines before saving
+    #[arg(long)]
+    preview: bool,
+    /// Skip the confirmation prompt when previewing
+    #[arg(short, long)]
+    yes: bool,
+    /// Only show articles published on these days, e.g. mon,tue,wed
+    #[arg(long, value_delimiter = ',')]
+    weekdays: Option<Vec<Weekday>>,
+    /// Leave an existing feed's metadata untouched
+    #[arg(long)]
+    no_update: bool,
+    /// Username for a feed behind HTTP basic authentication
+    #[arg(long, requires = "password")]
+    username: Option<String>,
+    /// Password for --username (stored in the config file)
+    #[arg(long, requires = "username")]
+    password: Option<String>,
+    /// Minutes `watch` waits between fetches of this feed, overriding its
+    /// --interval
+    #[arg(long, value_name = "MINS",
+          value_parser = clap::value_parser!(u64).range(1..=MAX_INTERVAL_MINS))]
+    interval: Option<u64>,
+}
+
+#[derive(Subcommand)]
+enum FeedsAction {
+    /// Change a feed's position in the list
+    #[command(group(clap::ArgGroup::new("target").required(true)))]
+    Move {
+        url: String,
+        /// New 1-based position (clamped to the ends of the list)
+        #[arg(long, group = "target")]
+        to: Option<usize>,
+        /// Move one place towards the top
+        #[arg(long, group = "target")]
+        up: bool,
+        /// Move one place towards the bottom
+        #[arg(long, group = "target")]
+        down: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum HistoryAction {
+    /// Drop mentions and read markers recorded more than the given number of days ago
+    Compact {
+        #[arg(long)]
+        keep_days: u32,
     },
 }
 
+/// Article filters shared by `fetch` and `scan`.
+#[derive(Args)]
+struct FilterArgs {
+    /// Drop articles published on Saturday or Sunday
+    #[arg(long)]
+    weekdays_only: bool,
+    /// Drop articles older than a date (2024-01-01) or duration (7d, 24h)
+    #[arg(long, value_name = "DATE|DURATION")]
+    since: Option<feed::Since>,
+    /// Drop articles without a publish date
+    #[arg(long)]
+    require_date: bool,
+}
+
+impl FilterArgs {
+    fn apply(&self, feed: &Feed, articles: &mut Vec<Article>) {
+        if let Some(days) = feed.allowed_weekdays(self.weekdays_only) {
+            feed::retain_weekdays(articles, &days);
+        }
+        if let Some(since) = self.since {
+            let now = chrono::Utc::now();
+            feed::retain_since(articles, since.cutoff(now), self.require_date);
+        } else if self.require_date {
+            articles.retain(|a| a.published.is_some());
+        }
+    }
+}
+
+#[derive(Args)]
+struct FetchArgs {
+    /// Optional: fetch from a specific feed (URL or name) only, or parse a
+    /// local feed file (a path, file:// URL, or - for standard input)
+    url: Option<String>,
+    /// Only fetch feeds in this group
+    #[arg(long, conflicts_with = "url")]
+    group: Option<String>,
+    #[command(flatten)]
+    filter: FilterArgs,
+    /// Print each feed as soon as it finishes instead of in config order
+    #[arg(long)]
+    stream: bool,
+    /// Order articles by publish time
+    #[arg(long, value_enum, default_value = "newest")]
+    sort: feed::SortOrder,
+    /// Maximum number of articles shown per feed (0 for no limit)
+    #[arg(long, default_value_t = feed::DEFAULT_ARTICLE_LIMIT)]
+    limit: usize,
+    /// Also show articles already marked as read
+    #[arg(long)]
+    all: bool,
+    /// Show article content as published, HTML markup included
+    #[arg(long)]
+    raw: bool,
+    /// Only show articles in this category; repeat to allow several
+    #[arg(long = "tag", value_name = "NAME")]
+    tags: Vec<String>,
+    /// Only show articles with audio or video attached, like podcast episodes
+    #[arg(long)]
+    media_only: bool,
+    /// Replace each article's content with the text of its web page, for
+    /// feeds that only carry summaries (slow: one request per article)
+    #[arg(long)]
+    full_text: bool,
+    #[command(flatten)]
+    failures: FailureArgs,
+}
+
+impl FetchArgs {
+    /// Narrows a feed's articles down to those to show: every filter is
+    /// applied first, then the newest (or oldest) matches are kept up to the
+    /// limit. Feeds don't always list entries in order, so they are sorted
+    /// before cutting.
+    fn select(&self, feed: &Feed, seen: &SeenStore, articles: &mut Vec<Article>) {
+        self.filter.apply(feed, articles);
+        if !self.all {
+            articles.retain(|a| !seen.is_read(a));
+        }
+        if !self.tags.is_empty() {
+            feed::retain_tagged(articles, &self.tags);
+        }
+        if self.media_only {
+            feed::retain_with_media(articles);
+        }
+        feed::sort_articles(articles, self.sort);
+        if self.limit > 0 {
+            articles.truncate(self.limit);
+        }
+    }
+}
+
+#[derive(Args)]
+struct WatchArgs {
+    /// Minutes between polls of feeds without their own interval
+    #[arg(long, value_name = "MINS", default_value_t = 15,
+          value_parser = clap::value_parser!(u64).range(1..=MAX_INTERVAL_MINS))]
+    interval: u64,
+    /// Only watch feeds in this group
+    #[arg(long)]
+    group: Option<String>,
+    /// Show a desktop notification for each new article that mentions a
+    /// tracked investment
+    #[arg(long)]
+    notify: bool,
+}
+
+/// What `fetch` and `scan` do when a feed can't be fetched.
+#[derive(Args)]
+struct FailureArgs {
+    /// Stop at the first feed that fails to fetch
+    #[arg(long, conflicts_with = "ignore_errors")]
+    fail_fast: bool,
+    /// Exit with status 0 even if some feeds failed to fetch
+    #[arg(long)]
+    ignore_errors: bool,
+}
+
+impl FailureArgs {
+    /// Exit code for a run in which `failed` feeds couldn't be fetched.
+    fn exit_code(&self, failed: usize) -> ExitCode {
+        if failed == 0 || self.ignore_errors {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::from(EXIT_FEED_ERRORS)
+        }
+    }
+}
+
+#[derive(Args)]
+struct SearchArgs {
+    /// Text to look for in article titles and content (case-insensitive)
+    query: String,
+    /// Treat the query as a regular expression
+    #[arg(long)]
+    regex: bool,
+    /// Maximum number of results
+    #[arg(long)]
+    limit: Option<usize>,
+}
+
+#[derive(Args)]
+struct ScanArgs {
+    /// Aggregate mention sentiment per source feed
+    #[arg(long)]
+    by_source: bool,
+    #[command(flatten)]
+    failures: FailureArgs,
+    /// Section the output by feed, ticker, date or sentiment
+    #[arg(long, value_enum)]
+    group_by: Option<GroupBy>,
+    /// Leave out mentions with neutral sentiment
+    #[arg(long)]
+    exclude_neutral: bool,
+    #[command(flatten)]
+    filter: FilterArgs,
+    /// Collapse near-duplicate headlines of the same ticker whose word
+    /// overlap (0.0-1.0) is at least THRESHOLD
+    #[arg(
+        long,
+        value_name = "THRESHOLD",
+        num_args = 0..=1,
+        default_missing_value = "0.6"
+    )]
+    collapse_similar: Option<f64>,
+    /// Show at most this many (most recent) mentions per ticker, with a
+    /// count of the rest (under `more` in JSON output)
+    #[arg(long, value_name = "N")]
+    max_per_ticker: Option<usize>,
+    /// Shell command to run for each mention not seen by a previous scan;
+    /// details are passed in AARON_RSS_* environment variables
+    #[arg(long, value_name = "COMMAND")]
+    exec: Option<String>,
+    /// Replace each article's content with the text of its web page, for
+    /// feeds that only carry summaries (slow: one request per article)
+    #[arg(long)]
+    full_text: bool,
+    /// Only match short or ambiguous tickers (see `ambiguous_tickers` in the
+    /// config) as cashtags like $ON or by company name
+    #[arg(long)]
+    strict: bool,
+}
+
+#[derive(Args)]
+struct AnalyzeArgs {
+    /// Stock ticker symbols; their price histories are fetched concurrently
+    #[arg(required = true, value_name = "TICKER", value_parser = stock::validate_ticker)]
+    tickers: Vec<String>,
+    /// Index or ETF to measure relative moves against
+    #[arg(long, default_value = "^GSPC", value_parser = stock::validate_ticker)]
+    benchmark: String,
+    /// Skip fetching the benchmark
+    #[arg(long)]
+    no_benchmark: bool,
+    /// Compare each article with the price move this many days after it
+    /// was published
+    #[arg(long, value_name = "DAYS", default_value_t = 0,
+          value_parser = clap::value_parser!(i64).range(0..=MAX_LAG_DAYS))]
+    lag: i64,
+    /// Age in days at which a mention counts half as much in the overall
+    /// news sentiment
+    #[arg(
+        long,
+        value_name = "DAYS",
+        default_value_t = analysis::DEFAULT_HALF_LIFE_DAYS,
+        value_parser = parse_half_life
+    )]
+    half_life: f64,
+    /// Replace each article's content with the text of its web page, for
+    /// feeds that only carry summaries (slow: one request per article)
+    #[arg(long)]
+    full_text: bool,
+    /// Also write the news/price rows to this file as CSV
+    #[arg(long, value_name = "PATH")]
+    csv: Option<PathBuf>,
+}
+
+fn parse_header(s: &str) -> Result<(String, String), String> {
+    let (name, value) = s
+        .split_once(':')
+        .ok_or_else(|| "expected \"Name: Value\"".to_string())?;
+    let (name, value) = (name.trim(), value.trim());
+    if reqwest::header::HeaderName::from_bytes(name.as_bytes()).is_err() {
+        return Err(format!("invalid header name '{}'", name));
+    }
+    if reqwest::header::HeaderValue::from_str(value).is_err() {
+        return Err(format!("invalid value for header '{}'", name));
+    }
+    Ok((name.to_string(), value.to_string()))
+}
+
+fn parse_alert_pct(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(pct) if pct >= 0.0 && pct.is_finite() => Ok(pct),
+        _ => Err("must be a percentage of 0 or more".to_string()),
+    }
+}
+
+fn parse_half_life(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(days) if days > 0.0 && days.is_finite() => Ok(days),
+        _ => Err("must be a positive number of days".to_string()),
+    }
+}
+
 #[derive(Subcommand)]
 enum StockAction {
     /// Add a stock ticker to track
     Add {
+        #[arg(value_parser = stock::validate_ticker)]
         ticker: String,
         /// Optional company name for better matching
         #[arg(short, long)]
         name: Option<String>,
+        /// ISIN identifier, also matched in article text
+        #[arg(long)]
+        isin: Option<String>,
+        /// CUSIP identifier, also matched in article text
+        #[arg(long)]
+        cusip: Option<String>,
+        /// Daily move in percent that `stock alerts` flags for this ticker
+        #[arg(long, value_name = "PCT", value_parser = parse_alert_pct)]
+        alert_pct: Option<f64>,
+        /// The ticker is a cryptocurrency, priced in USD by CoinGecko
+        #[arg(long)]
+        crypto: bool,
+        /// Leave an already tracked ticker's details untouched
+        #[arg(long)]
+        no_update: bool,
     },
     /// Remove a tracked ticker
     Remove { ticker: String },
     /// List all tracked investments
     List,
     /// Get current quote for a ticker
-    Quote { ticker: String },
+    Quote {
+        #[arg(required_unless_present = "all", value_parser = stock::validate_ticker)]
+        ticker: Option<String>,
+        /// Quote every tracked investment
+        #[arg(long, conflicts_with = "ticker")]
+        all: bool,
+        /// The ticker is a cryptocurrency, priced in USD by CoinGecko
+        #[arg(long, conflicts_with = "all")]
+        crypto: bool,
+    },
+    /// Flag tracked tickers that moved sharply today; exits with status 1
+    /// if any did, and 3 if no quote could be fetched
+    Alerts {
+        /// Daily move in percent to flag, overriding each investment's
+        /// `alert_pct` (default 5)
+        #[arg(long, value_name = "PCT", value_parser = parse_alert_pct)]
+        threshold: Option<f64>,
+    },
 }
 
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
     let cli = Cli::parse();
+    style::init(cli.no_color);
+    let opened = storage::config_path(cli.config)
+        .and_then(|path| storage::open(cli.storage, path, cli.config_format));
+    let storage = match opened {
+        Ok((storage, migration)) => {
+            if let Some(migration) = migration {
+                if let Some(e) = &migration.cache_error {
+                    eprintln!("Warning: not importing the feed cache: {}", e);
+                }
+                eprintln!(
+                    "Imported the JSON config, feed cache and read state into {}",
+                    migration.database.display()
+                );
+            }
+            storage
+        }
+        Err(e) => {
+            error!("Error opening storage: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let storage = storage.as_ref();
+    let Some(config) = load_config(storage) else {
+        return ExitCode::FAILURE;
+    };
+
+    let fetch = FetchOptions {
+        max_content_len: cli
+            .max_content_len
+            .or(config.max_content_len)
+            .unwrap_or(feed::DEFAULT_MAX_CONTENT_LEN),
+        headers: cli.headers,
+    };
+
+    let transport: Box<dyn Transport> = match &cli.mock {
+        Some(dir) => match FixtureTransport::load(dir) {
+            Ok(t) => Box::new(t),
+            Err(e) => {
+                error!("Error loading mock fixtures: {}", e);
+                return ExitCode::FAILURE;
+            }
+        },
+        None => match HttpTransport::new(&cli.http.options(&config)) {
+            // `add` probes the new feed once: retrying a URL that can't be
+            // reached would only hold up saving it.
+            Ok(t) if matches!(cli.command, Commands::Add(_)) => Box::new(t),
+            Ok(t) => Box::new(RetryTransport::new(t, cli.http.retries)),
+            Err(e) => {
+                error!("Error creating HTTP client: {}", e);
+                return ExitCode::FAILURE;
+            }
+        },
+    };
+    let transport = transport.as_ref();
+    let out = Output {
+        json: cli.json,
+        verbosity: if cli.quiet {
+            Verbosity::Quiet
+        } else if cli.verbose {
+            Verbosity::Verbose
+        } else {
+            Verbosity::Normal
+        },
+    };
+    // Fixture runs neither read nor overwrite the real cache.
+    let cache = (!cli.no_cache && cli.mock.is_none())
+        .then(|| Mutex::new(storage.load_cache().unwrap_or_default()));
+    let report = move |event: FetchEvent<'_>| report_fetch(out, event);
+    let ctx = Context {
+        transport,
+        storage,
+        fetch: &fetch,
+        concurrency: cli.concurrency.max(1),
+        cache: cache.as_ref(),
+        out,
+        report: Some(&report),
+    };
+    let stock_opts = StockOptions {
+        provider: cli.provider,
+        cache: cli.mock.is_none(),
+        refresh: cli.refresh,
+    };
 
     match cli.command {
-        Commands::Add { url } => cmd_add(&url),
-        Commands::Remove { url } => cmd_remove(&url),
-        Commands::List => cmd_list(),
-        Commands::Fetch { url } => cmd_fetch(url).await,
-        Commands::Stock { action } => cmd_stock(action).await,
-        Commands::Scan => cmd_scan().await,
-        Commands::Analyze { ticker } => cmd_analyze(&ticker).await,
+        Commands::Add(args) => cmd_add(ctx, args).await,
+        Commands::Remove { url } => cmd_remove(storage, &url),
+        Commands::List {
+            group,
+            validate: true,
+            prune,
+            ..
+        } => cmd_validate(ctx, group.as_deref(), prune).await,
+        Commands::List {
+            group,
+            stats: true,
+            sort_stale,
+            ..
+        } => cmd_list_stats(ctx, group.as_deref(), sort_stale).await,
+        Commands::List { group, .. } => cmd_list(storage, group.as_deref()),
+        Commands::Groups => cmd_groups(storage),
+        Commands::Tui { group } => cmd_tui(ctx, group.as_deref()).await,
+        Commands::Search(args) => cmd_search(ctx, args).await,
+        Commands::MarkRead { url } => cmd_mark_read(ctx, url).await,
+        Commands::Open { number } => cmd_open(storage, number),
+        Commands::Import { path } => cmd_import(storage, &path),
+        Commands::Export { path } => cmd_export(storage, &path),
+        Commands::Fetch(args) => cmd_fetch(ctx, args).await,
+        Commands::Watch(args) => cmd_watch(ctx, args).await,
+        Commands::Feeds { action } => cmd_feeds(storage, action),
+        Commands::Stock { action } => cmd_stock(ctx, stock_opts, action).await,
+        Commands::Scan(args) => cmd_scan(ctx, args).await,
+        Commands::Stats { ticker, days } => cmd_stats(storage, &ticker, days),
+        Commands::Analyze(args) => cmd_analyze(ctx, stock_opts, args).await,
+        Commands::History { action } => cmd_history(storage, action),
     }
 }
 
-fn cmd_add(url: &str) {
-    let mut config = Config::load().unwrap_or_default();
-    if config.add_feed(url) {
-        if let Err(e) = config.save() {
-            eprintln!("Error saving config: {}", e);
-            return;
+/// Loads the config. When the file is unreadable or corrupt, reports the
+/// problem and returns None so the command stops rather than carrying on
+/// with an empty config (and later saving it over the user's feeds).
+fn load_config(storage: &dyn Storage) -> Option<Config> {
+    match storage.load_config() {
+        Ok(config) => Some(config),
+        Err(e) => {
+            error!("Error loading config: {}", e);
+            eprintln!("Fix or remove the file; it has been left untouched.");
+            None
         }
-        println!("Added feed: {}", url);
-    } else {
-        println!("Feed already exists: {}", url);
     }
 }
 
-fn cmd_remove(url: &str) {
-    let mut config = Config::load().unwrap_or_default();
-    if config.remove_feed(url) {
-        if let Err(e) = config.save() {
-            eprintln!("Error saving config: {}", e);
-            return;
+/// Loads the mention history, or reports why it can't be and returns None
+/// so the caller stops before saving over it.
+fn load_history(storage: &dyn Storage, config: &Config) -> Option<MentionHistory> {
+    match MentionHistory::load(storage.state_dir(), &config.history) {
+        Ok(history) => Some(history),
+        Err(e) => {
+            error!("Error loading mention history: {}", e);
+            eprintln!("Fix or remove the file; it has been left untouched.");
+            None
         }
-        println!("Removed feed: {}", url);
-    } else {
-        println!("Feed not found: {}", url);
     }
 }
 
-fn cmd_list() {
-    let config = Config::load().unwrap_or_default();
-    if config.feeds.is_empty() {
-        println!("No feeds subscribed. Use 'aaron_rss add <url>' to add a feed.");
-        return;
-    }
-    println!("Subscribed feeds:");
-    for (i, feed) in config.feeds.iter().enumerate() {
-        println!("  {}. {}", i + 1, feed);
+/// Loads the read state, or reports why it can't be and returns None so
+/// the caller stops before saving over the existing marks.
+fn load_seen(storage: &dyn Storage) -> Option<SeenStore> {
+    match storage.load_seen() {
+        Ok(seen) => Some(seen),
+        Err(e) => {
+            error!("Error loading read state: {}", e);
+            eprintln!("Fix or remove the file; it has been left untouched.");
+            None
+        }
     }
 }
 
-async fn cmd_fetch(url: Option<String>) {
-    let urls = match url {
-        Some(u) => vec![u],
-        None => {
-            let config = Config::load().unwrap_or_default();
-            if config.feeds.is_empty() {
-                println!("No feeds subscribed. Use 'aaron_rss add <url>' to add a feed.");
-                return;
-            }
-            config.feeds
+async fn cmd_add(ctx: Context<'_>, args: AddArgs) -> ExitCode {
+    let Some(mut config) = load_config(ctx.storage) else {
+        return ExitCode::FAILURE;
+    };
+    let auth = args
+        .username
+        .zip(args.password)
+        .map(|(username, password)| FeedAuth { username, password });
+    let headers = Feed {
+        auth: auth.clone(),
+        ..Feed::new(&args.url)
+    }
+    .request_headers();
+    let (url, fetched) = if config.has_feed(&args.url) {
+        (args.url.clone(), None)
+    } else {
+        match resolve_feed_url(ctx, &args.url, &headers).await {
+            Some((url, fetched)) => (feed::normalize_feed_url(&url).unwrap_or(url), fetched),
+            None => return ExitCode::FAILURE,
         }
     };
+    let url = url.as_str();
 
-    for feed_url in &urls {
-        println!("\nFetching: {}", feed_url);
-        match feed::fetch_feed(feed_url).await {
-            Ok(result) => {
-                println!("== {} ==", result.title);
-                if result.articles.is_empty() {
-                    println!("  No articles found.");
-                } else {
-                    for article in &result.articles {
-                        let date = article
-                            .published
-                            .as_deref()
-                            .unwrap_or("No date");
-                        println!("\n  [{}]", date);
-                        println!("  {}", article.title);
-                        if let Some(link) = &article.link {
-                            println!("  {}", link);
-                        }
-                    }
-                }
-            }
-            Err(e) => {
-                eprintln!("Error fetching {}: {}", feed_url, e);
-            }
-        }
+    if args.preview
+        && !config.has_feed(url)
+        && !preview_feed(ctx, url, &headers, fetched, args.yes).await
+    {
+        println!("Feed not added.");
+        return ExitCode::SUCCESS;
+    }
+
+    let feed = Feed {
+        name: args.name,
+        weekdays: args.weekdays,
+        group: args.group,
+        auth,
+        interval_mins: args.interval,
+        ..Feed::new(url)
+    };
+    let outcome = config.upsert_feed(feed, !args.no_update);
+    if outcome == AddOutcome::Unchanged {
+        println!("Feed already exists: {}", url);
+        return ExitCode::SUCCESS;
+    }
+    if let Err(e) = ctx.storage.save_config(&config) {
+        error!("Error saving config: {}", e);
+        return ExitCode::FAILURE;
+    }
+    match outcome {
+        AddOutcome::Added => println!("Added feed: {}", url),
+        _ => println!("Updated feed: {}", url),
     }
+    ExitCode::SUCCESS
 }
 
-async fn cmd_stock(action: StockAction) {
-    match action {
-        StockAction::Add { ticker, name } => {
-            let mut config = Config::load().unwrap_or_default();
-            if config.add_investment(&ticker, name.clone()) {
-                if let Err(e) = config.save() {
-                    eprintln!("Error saving config: {}", e);
-                    return;
-                }
-                let display = match name {
-                    Some(n) => format!("{} ({})", ticker.to_uppercase(), n),
-                    None => ticker.to_uppercase(),
-                };
-                println!("Added investment: {}", display);
-            } else {
-                println!("Investment already tracked: {}", ticker.to_uppercase());
-            }
+/// Returns the feed to subscribe to for `url`, with the result of fetching
+/// it when that already happened. A site's homepage usually advertises its
+/// feed, which is used instead when there is exactly one; otherwise the
+/// candidates are listed and None is returned. URLs that can't be fetched
+/// are passed through unchanged.
+async fn resolve_feed_url(
+    ctx: Context<'_>,
+    url: &str,
+    headers: &[(String, String)],
+) -> Option<(String, Option<Result<FeedResult, FeedError>>)> {
+    let limit = Some(feed::DEFAULT_ARTICLE_LIMIT);
+    let candidates =
+        match feed::fetch_feed_with(ctx.transport, url, headers, ctx.fetch, limit).await {
+            Err(FeedError::HtmlPage(candidates)) => candidates,
+            fetched => return Some((url.to_string(), Some(fetched))),
+        };
+    match candidates.as_slice() {
+        [] => {
+            eprintln!("{} is a web page without any feed links.", url);
+            None
         }
-        StockAction::Remove { ticker } => {
-            let mut config = Config::load().unwrap_or_default();
-            if config.remove_investment(&ticker) {
-                if let Err(e) = config.save() {
-                    eprintln!("Error saving config: {}", e);
-                    return;
-                }
-                println!("Removed investment: {}", ticker.to_uppercase());
-            } else {
-                println!("Investment not found: {}", ticker.to_uppercase());
+        [only] => {
+            println!("Discovered feed: {}", only);
+            Some((only.clone(), None))
+        }
+        _ => {
+            println!("{} links to several feeds; add one of them:", url);
+            for candidate in &candidates {
+                println!("  {}", candidate);
             }
+            None
         }
-        StockAction::List => {
-            let config = Config::load().unwrap_or_default();
-            if config.investments.is_empty() {
-                println!("No investments tracked. Use 'aaron_rss stock add <ticker>' to add one.");
-                return;
+    }
+}
+
+/// Prints the feed title and latest headlines, then asks whether to add it.
+/// The feed is only fetched if `fetched` doesn't already hold the result.
+/// Returns true if the feed should be saved.
+async fn preview_feed(
+    ctx: Context<'_>,
+    url: &str,
+    headers: &[(String, String)],
+    fetched: Option<Result<FeedResult, FeedError>>,
+    yes: bool,
+) -> bool {
+    let interactive = !yes && io::stdin().is_terminal();
+    let fetched = match fetched {
+        Some(fetched) => fetched,
+        None => {
+            let limit = Some(feed::DEFAULT_ARTICLE_LIMIT);
+            feed::fetch_feed_with(ctx.transport, url, headers, ctx.fetch, limit).await
+        }
+    };
+
+    match fetched {
+        Ok(result) => {
+            println!("== {} ==", result.title);
+            if result.articles.is_empty() {
+                println!("  No articles found.");
             }
-            println!("Tracked investments:");
-            for (i, inv) in config.investments.iter().enumerate() {
-                let display = match &inv.name {
-                    Some(n) => format!("{} ({})", inv.ticker, n),
-                    None => inv.ticker.clone(),
-                };
-                println!("  {}. {}", i + 1, display);
+            for article in result.articles.iter().take(5) {
+                println!("  - {}", article.title);
             }
+            println!();
+            !interactive || confirm("Add this feed?")
         }
-        StockAction::Quote { ticker } => {
-            println!("Fetching quote for {}...", ticker.to_uppercase());
-            match stock::fetch_quote(&ticker).await {
-                Ok(quote) => {
-                    let change_sign = if quote.change >= 0.0 { "+" } else { "" };
-                    println!(
-                        "\n{}: ${:.2} ({}{:.2}, {}{:.2}%)",
-                        quote.ticker,
-                        quote.price,
-                        change_sign,
-                        quote.change,
-                        change_sign,
-                        quote.change_percent
-                    );
-                }
-                Err(e) => {
-                    eprintln!("Error fetching quote: {}", e);
-                }
+        Err(e) => {
+            eprintln!("Warning: could not fetch {}: {}", url, e);
+            if yes {
+                return true;
             }
+            interactive && confirm("Add anyway?")
         }
     }
 }
 
-async fn cmd_scan() {
-    let config = Config::load().unwrap_or_default();
-
-    if config.investments.is_empty() {
-        println!("No investments tracked. Use 'aaron_rss stock add <ticker>' to add one.");
-        return;
+fn confirm(prompt: &str) -> bool {
+    print!("{} [y/N] ", prompt);
+    let _ = io::stdout().flush();
+    let mut answer = String::new();
+    if io::stdin().read_line(&mut answer).is_err() {
+        return false;
     }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
 
-    if config.feeds.is_empty() {
-        println!("No feeds subscribed. Use 'aaron_rss add <url>' to add a feed.");
-        return;
+fn cmd_remove(storage: &dyn Storage, url: &str) -> ExitCode {
+    let Some(mut config) = load_config(storage) else {
+        return ExitCode::FAILURE;
+    };
+    if let Some(feed) = config.remove_feed(url) {
+        if let Err(e) = storage.save_config(&config) {
+            error!("Error saving config: {}", e);
+            return ExitCode::FAILURE;
+        }
+        println!("Removed feed: {}", feed.url);
+    } else {
+        println!("Feed not found: {}", url);
     }
+    ExitCode::SUCCESS
+}
 
-    println!("Scanning feeds for investment mentions...\n");
-
-    let mut all_articles = Vec::new();
+fn cmd_import(storage: &dyn Storage, path: &Path) -> ExitCode {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => {
+            error!("Error reading {}: {}", path.display(), e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let feeds = match opml::parse(&content) {
+        Ok(feeds) => feeds,
+        Err(e) => {
+            error!("Error parsing {}: {}", path.display(), e);
+            return ExitCode::FAILURE;
+        }
+    };
 
-    for feed_url in &config.feeds {
-        match feed::fetch_feed(feed_url).await {
-            Ok(result) => {
-                all_articles.extend(result.articles);
-            }
+    let Some(mut config) = load_config(storage) else {
+        return ExitCode::FAILURE;
+    };
+    let mut added = 0;
+    let mut skipped = 0;
+    for mut feed in feeds {
+        match feed::normalize_feed_url(&feed.url) {
+            Ok(url) => feed.url = url,
             Err(e) => {
-                eprintln!("Error fetching {}: {}", feed_url, e);
+                eprintln!("Skipping feed {}: {}", feed.url, e);
+                continue;
             }
         }
+        match config.upsert_feed(feed, false) {
+            AddOutcome::Added => added += 1,
+            _ => skipped += 1,
+        }
     }
-
-    let mentions = analysis::find_mentions(&all_articles, &config.investments);
-
+    if added > 0 {
+        if let Err(e) = storage.save_config(&config) {
+            error!("Error saving config: {}", e);
+            return ExitCode::FAILURE;
+        }
+    }
+    println!("Imported {} feeds ({} already present)", added, skipped);
+    ExitCode::SUCCESS
+}
+
+fn cmd_export(storage: &dyn Storage, path: &Path) -> ExitCode {
+    let Some(config) = load_config(storage) else {
+        return ExitCode::FAILURE;
+    };
+    let document = opml::render(&config.feeds);
+
+    // A failed export never leaves a half-written file in place of an
+    // existing one.
+    if let Err(e) = storage::write_atomic(path, document.as_bytes()) {
+        error!("Error writing {}: {}", path.display(), e);
+        return ExitCode::FAILURE;
+    }
+    println!(
+        "Exported {} feeds to {}",
+        config.feeds.len(),
+        path.display()
+    );
+    ExitCode::SUCCESS
+}
+
+fn cmd_list(storage: &dyn Storage, group: Option<&str>) -> ExitCode {
+    let Some(config) = load_config(storage) else {
+        return ExitCode::FAILURE;
+    };
+    if config.feeds.is_empty() {
+        println!("No feeds subscribed. Use 'aaron_rss add <url>' to add a feed.");
+        return ExitCode::SUCCESS;
+    }
+    if let Some(group) = group {
+        if config.feeds_in_group(Some(group)).is_empty() {
+            println!("No feeds in group '{}'.", group);
+            return ExitCode::SUCCESS;
+        }
+    }
+    println!("Subscribed feeds:");
+    // Numbered by position in the full list, which `feeds move` uses.
+    for (i, feed) in config.feeds.iter().enumerate() {
+        if !group.is_none_or(|g| feed.in_group(g)) {
+            continue;
+        }
+        let mut line = match &feed.name {
+            Some(name) => format!("  {}. {} ({})", i + 1, name, feed.url),
+            None => format!("  {}. {}", i + 1, feed.url),
+        };
+        if let (None, Some(g)) = (group, &feed.group) {
+            line.push_str(&format!(" [{}]", g));
+        }
+        println!("{}", line);
+    }
+    ExitCode::SUCCESS
+}
+
+/// Fetches every feed (in `group`) concurrently and lists each with its
+/// title, article count and newest article date.
+async fn cmd_list_stats(ctx: Context<'_>, group: Option<&str>, sort_stale: bool) -> ExitCode {
+    let Some(config) = load_config(ctx.storage) else {
+        return ExitCode::FAILURE;
+    };
+    // Numbered by position in the full list, like `list`.
+    let feeds: Vec<(usize, &Feed)> = config
+        .feeds
+        .iter()
+        .enumerate()
+        .filter(|(_, feed)| group.is_none_or(|g| feed.in_group(g)))
+        .collect();
+    if feeds.is_empty() {
+        match group {
+            Some(group) => println!("No feeds in group '{}'.", group),
+            None => println!("No feeds subscribed. Use 'aaron_rss add <url>' to add a feed."),
+        }
+        return ExitCode::SUCCESS;
+    }
+
+    let mut stats: Vec<(usize, &Feed, Option<FeedStats>)> = stream::iter(feeds)
+        .map(|(i, feed)| async move {
+            let result = tokio::time::timeout(
+                VALIDATE_TIMEOUT,
+                feed::fetch_feed_with(
+                    ctx.transport,
+                    &feed.url,
+                    &feed.request_headers(),
+                    ctx.fetch,
+                    None,
+                ),
+            )
+            .await;
+            let stats = match result {
+                Ok(Ok(result)) => Some(FeedStats::from_result(&result)),
+                _ => None,
+            };
+            (i, feed, stats)
+        })
+        .buffered(ctx.concurrency)
+        .collect()
+        .await;
+    if sort_stale {
+        // Unreachable and undated feeds sort first.
+        stats.sort_by_key(|(_, _, stats)| stats.as_ref().and_then(|s| s.newest));
+    }
+
+    let now = chrono::Utc::now();
+    // When `fetch`, `scan` or `watch` last got each feed, if ever.
+    let last_fetched = |feed: &Feed| {
+        let cached = ctx.cache?.lock().unwrap().get(&feed.url)?.last_fetched?;
+        Some(format!(", last fetched {}", describe_age(now - cached)))
+    };
+    println!("Subscribed feeds:");
+    for (i, feed, stats) in &stats {
+        let fetched = last_fetched(feed).unwrap_or_default();
+        let Some(stats) = stats else {
+            println!("  {}. {} (unreachable{})", i + 1, feed.url, fetched);
+            continue;
+        };
+        let title = feed.name.as_deref().unwrap_or(&stats.title);
+        let newest = match stats.newest {
+            Some(newest) => {
+                let days = (now - newest).num_days().max(0);
+                format!(
+                    "newest {} ({} day{} ago)",
+                    newest.format("%Y-%m-%d"),
+                    days,
+                    if days == 1 { "" } else { "s" }
+                )
+            }
+            None => "no dated articles".to_string(),
+        };
+        println!(
+            "  {}. {} ({}): {} articles, {}{}",
+            i + 1,
+            title,
+            feed.url,
+            stats.articles,
+            newest,
+            fetched
+        );
+    }
+    ExitCode::SUCCESS
+}
+
+/// "5 minutes ago", "1 hour ago", "3 days ago".
+fn describe_age(age: TimeDelta) -> String {
+    let (n, unit) = if age.num_days() > 0 {
+        (age.num_days(), "day")
+    } else if age.num_hours() > 0 {
+        (age.num_hours(), "hour")
+    } else {
+        (age.num_minutes().max(0), "minute")
+    };
+    format!("{} {}{} ago", n, unit, if n == 1 { "" } else { "s" })
+}
+
+/// Longest `list --validate` or `list --stats` waits for a single feed.
+const VALIDATE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Fetches every feed (in `group`) concurrently and reports its health.
+/// Nothing is saved unless `prune` is set, which drops feeds that are gone.
+async fn cmd_validate(ctx: Context<'_>, group: Option<&str>, prune: bool) -> ExitCode {
+    let Some(mut config) = load_config(ctx.storage) else {
+        return ExitCode::FAILURE;
+    };
+    let feeds = config.feeds_in_group(group);
+    if feeds.is_empty() {
+        match group {
+            Some(group) => println!("No feeds in group '{}'.", group),
+            None => println!("No feeds subscribed. Use 'aaron_rss add <url>' to add a feed."),
+        }
+        return ExitCode::SUCCESS;
+    }
+
+    let mut checks = stream::iter(&feeds)
+        .map(|feed| async move {
+            let result = tokio::time::timeout(
+                VALIDATE_TIMEOUT,
+                feed::fetch_feed_with(
+                    ctx.transport,
+                    &feed.url,
+                    &feed.request_headers(),
+                    ctx.fetch,
+                    None,
+                ),
+            )
+            .await
+            .unwrap_or(Err(FeedError::Timeout(VALIDATE_TIMEOUT)));
+            (feed, result)
+        })
+        .buffered(ctx.concurrency);
+
+    let mut healths = Vec::new();
+    let mut gone = Vec::new();
+    while let Some((feed, result)) = checks.next().await {
+        let health = FeedHealth::classify(&result);
+        let label = feed.name.as_deref().unwrap_or(&feed.url);
+        match &result {
+            Ok(result) => {
+                println!(
+                    "  {:<11} {} ({} articles)",
+                    health.label(),
+                    label,
+                    result.articles.len()
+                );
+                if let Some(moved) = result.redirected_to(&feed.url) {
+                    println!("              redirected to {}", moved);
+                }
+            }
+            Err(e) => println!("  {:<11} {}: {}", health.label(), label, e),
+        }
+        healths.push(health);
+        if health == FeedHealth::Gone {
+            gone.push(feed.url.clone());
+        }
+    }
+
+    let summary: Vec<String> = FeedHealth::ALL
+        .iter()
+        .filter_map(|health| {
+            let count = healths.iter().filter(|h| *h == health).count();
+            (count > 0).then(|| format!("{} {}", count, health.label()))
+        })
+        .collect();
+    println!("\nChecked {} feeds: {}", feeds.len(), summary.join(", "));
+
+    if prune && !gone.is_empty() {
+        for url in &gone {
+            config.remove_feed(url);
+        }
+        if let Err(e) = ctx.storage.save_config(&config) {
+            error!("Error saving config: {}", e);
+            return ExitCode::FAILURE;
+        }
+        println!("Removed {} feeds that no longer exist.", gone.len());
+    }
+    ExitCode::SUCCESS
+}
+
+async fn cmd_tui(ctx: Context<'_>, group: Option<&str>) -> ExitCode {
+    let Some(config) = load_config(ctx.storage) else {
+        return ExitCode::FAILURE;
+    };
+    let feeds = config.feeds_in_group(group);
+    if feeds.is_empty() {
+        match group {
+            Some(group) => println!("No feeds in group '{}'.", group),
+            None => println!("No feeds subscribed. Use 'aaron_rss add <url>' to add a feed."),
+        }
+        return ExitCode::SUCCESS;
+    }
+    // Diagnostics on stderr would draw over the reader.
+    let out = Output {
+        verbosity: Verbosity::Normal,
+        ..ctx.out
+    };
+    let report = move |event: FetchEvent<'_>| report_fetch(out, event);
+    let ctx = Context {
+        out,
+        report: Some(&report),
+        ..ctx
+    };
+    let fetches = ctx.fetcher().fetch_feeds(&feeds, None, false);
+    if let Err(e) = tui::run(&feeds, fetches).await {
+        error!("Error running the reader: {}", e);
+    }
+    save_cache(ctx);
+    ExitCode::SUCCESS
+}
+
+fn cmd_groups(storage: &dyn Storage) -> ExitCode {
+    let Some(config) = load_config(storage) else {
+        return ExitCode::FAILURE;
+    };
+    let groups = config.groups();
+    if groups.is_empty() {
+        println!("No feed groups. Use 'aaron_rss add <url> --group <name>' to file a feed.");
+        return ExitCode::SUCCESS;
+    }
+    for (group, count) in groups {
+        println!(
+            "{} ({} feed{})",
+            group,
+            count,
+            if count == 1 { "" } else { "s" }
+        );
+    }
+    ExitCode::SUCCESS
+}
+
+async fn cmd_fetch(ctx: Context<'_>, args: FetchArgs) -> ExitCode {
+    let out = ctx.out;
+    let Some(config) = load_config(ctx.storage) else {
+        return ExitCode::FAILURE;
+    };
+    let mut local = None;
+    let feeds = match &args.url {
+        Some(u) => match config.find_feed(u) {
+            Some(feed) => vec![feed.clone()],
+            None => {
+                local = feed::local_source(u);
+                vec![Feed::new(u)]
+            }
+        },
+        None => {
+            if config.feeds.is_empty() {
+                println!("No feeds subscribed. Use 'aaron_rss add <url>' to add a feed.");
+                return ExitCode::SUCCESS;
+            }
+            let feeds = config.feeds_in_group(args.group.as_deref());
+            if feeds.is_empty() {
+                println!(
+                    "No feeds in group '{}'.",
+                    args.group.as_deref().unwrap_or_default()
+                );
+                return ExitCode::SUCCESS;
+            }
+            feeds
+        }
+    };
+    let seen = ctx.storage.load_seen().unwrap_or_default();
+
+    let mut json_articles = Vec::new();
+    let mut recent = RecentArticles::default();
+    let mut failed = 0;
+    let mut fetches = match &local {
+        // A document given on the command line is parsed without touching
+        // the network.
+        Some(source) => {
+            let result = match read_local_feed(source, io::stdin()) {
+                Ok(body) => feed::parse_feed(&body, &feeds[0].url, ctx.fetch.max_content_len),
+                Err(e) => {
+                    error!("Error reading {}: {}", feeds[0].url, e);
+                    return ExitCode::FAILURE;
+                }
+            };
+            stream::iter([(&feeds[0], result)]).boxed()
+        }
+        None => ctx.fetcher().fetch_feeds(&feeds, None, !args.stream),
+    };
+    while let Some((feed, result)) = fetches.next().await {
+        match result {
+            Ok(mut result) => {
+                args.select(feed, &seen, &mut result.articles);
+                if args.full_text {
+                    ctx.fetcher()
+                        .fill_full_text(feed, &mut result.articles)
+                        .await;
+                }
+                if !args.raw {
+                    for article in &mut result.articles {
+                        article.content = article.content.as_deref().map(feed::strip_html);
+                    }
+                }
+                if out.json {
+                    for article in &result.articles {
+                        recent.push(article);
+                    }
+                    json_articles.extend(result.articles);
+                    continue;
+                }
+                let title = feed.name.as_deref().unwrap_or(&result.title);
+                println!(
+                    "\n{}",
+                    style::bold(&format!("== {} ({}) ==", title, feed.url))
+                );
+                if let Some(moved) = result.redirected_to(&feed.url) {
+                    eprintln!(
+                        "  Feed redirected to {} - consider updating the subscription",
+                        moved
+                    );
+                }
+                if out.verbose() {
+                    for warning in &result.warnings {
+                        eprintln!("  Warning: {}", warning);
+                    }
+                }
+                if result.articles.is_empty() {
+                    if !out.quiet() {
+                        let message = if !args.tags.is_empty() || args.media_only {
+                            "No matching articles."
+                        } else if args.all {
+                            "No articles found."
+                        } else {
+                            "No new articles."
+                        };
+                        eprintln!("  {}", message);
+                    }
+                } else {
+                    for article in &result.articles {
+                        let new = !seen.is_read(article);
+                        let number = recent.push(article);
+                        for line in article_lines(article, number, new, args.raw) {
+                            println!("{}", line);
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                error!("Error fetching {}: {}", feed.url, e);
+                match e {
+                    FeedError::NotFound => eprintln!(
+                        "  The feed is gone; 'aaron_rss list --validate --prune' unsubscribes from it."
+                    ),
+                    FeedError::Parse(_) | FeedError::Empty => {
+                        eprintln!("  The URL doesn't serve a feed any more; check it in a browser.")
+                    }
+                    _ => {}
+                }
+                failed += 1;
+                if args.failures.fail_fast {
+                    break;
+                }
+            }
+        }
+    }
+    save_cache(ctx);
+    if let Err(e) = recent.save(ctx.storage.state_dir()) {
+        error!("Error saving fetched articles: {}", e);
+    }
+
+    if out.json {
+        out.print_json(&json_articles);
+    }
+    args.failures.exit_code(failed)
+}
+
+async fn cmd_watch(ctx: Context<'_>, args: WatchArgs) -> ExitCode {
+    let out = ctx.out;
+    let Some(config) = load_config(ctx.storage) else {
+        return ExitCode::FAILURE;
+    };
+    let feeds = config.feeds_in_group(args.group.as_deref());
+    if feeds.is_empty() {
+        match args.group {
+            Some(group) => println!("No feeds in group '{}'.", group),
+            None => println!("No feeds subscribed. Use 'aaron_rss add <url>' to add a feed."),
+        }
+        return ExitCode::SUCCESS;
+    }
+    let lexicon = match args.notify.then(|| config.lexicon()).transpose() {
+        Ok(lexicon) => lexicon,
+        Err(e) => {
+            error!("Error loading sentiment lexicon: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    out.status(&format!(
+        "Watching {} feeds every {} minute{}; press Ctrl-C to stop.",
+        feeds.len(),
+        args.interval,
+        if args.interval == 1 { "" } else { "s" }
+    ));
+    let interval = Duration::from_secs(args.interval * 60);
+    let default_interval = TimeDelta::minutes(args.interval as i64);
+    // Seeded from the cache so a restart doesn't refetch feeds that were
+    // fetched recently.
+    let mut last_fetched: HashMap<String, DateTime<Utc>> = ctx
+        .cache
+        .map(|cache| {
+            let cache = cache.lock().unwrap();
+            feeds
+                .iter()
+                .filter_map(|f| Some((f.url.clone(), cache.get(&f.url)?.last_fetched?)))
+                .collect()
+        })
+        .unwrap_or_default();
+    let mut failed_polls = 0;
+    loop {
+        let now = Utc::now();
+        let due: Vec<Feed> = feeds
+            .iter()
+            .filter(|f| f.is_due(last_fetched.get(&f.url).copied(), default_interval, now))
+            .cloned()
+            .collect();
+        if !due.is_empty() {
+            out.detail(&format!("Polling {} of {} feeds", due.len(), feeds.len()));
+            let poll = watch_poll(ctx, &config, &due, lexicon.as_ref());
+            let ok = tokio::select! {
+                ok = poll => ok,
+                _ = tokio::signal::ctrl_c() => break,
+            };
+            let Some(ok) = ok else {
+                return ExitCode::FAILURE;
+            };
+            for feed in &due {
+                last_fetched.insert(feed.url.clone(), now);
+            }
+            failed_polls = if ok { 0 } else { failed_polls + 1 };
+        }
+        // Until the next feed is due, and at least a second so a feed due
+        // right now doesn't spin.
+        let next_due = feeds
+            .iter()
+            .filter_map(|f| Some(*last_fetched.get(&f.url)? + f.refresh_interval(default_interval)))
+            .min()
+            .map_or(interval, |next| {
+                (next - Utc::now())
+                    .to_std()
+                    .unwrap_or_default()
+                    .max(Duration::from_secs(1))
+            });
+        let wait = if failed_polls > 0 {
+            interval * 2u32.pow(failed_polls.min(WATCH_MAX_BACKOFF_DOUBLINGS))
+        } else {
+            next_due
+        };
+        if failed_polls > 0 {
+            eprintln!(
+                "Every feed failed; trying again in {} minutes.",
+                wait.as_secs() / 60
+            );
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(wait) => {}
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+    out.status("Stopped watching.");
+    ExitCode::SUCCESS
+}
+
+/// Fetches `feeds` once and prints the articles not marked read yet, then
+/// marks them read so the next poll only shows what's new since. With a
+/// `lexicon`, new articles mentioning a tracked investment also raise a
+/// notification. Returns false if every feed failed, and None without
+/// fetching anything if the read state can't be loaded.
+async fn watch_poll(
+    ctx: Context<'_>,
+    config: &Config,
+    feeds: &[Feed],
+    lexicon: Option<&analysis::Lexicon>,
+) -> Option<bool> {
+    let out = ctx.out;
+    // Reloaded every poll so `mark-read` in another terminal is honored.
+    let mut seen = load_seen(ctx.storage)?;
+    let failed = watch::poll(ctx.fetcher(), feeds, &mut seen, |feed, result| {
+        let result = match result {
+            Ok(result) => result,
+            Err(e) => {
+                error!("Error fetching {}: {}", feed.url, e);
+                return;
+            }
+        };
+        let title = feed.name.as_deref().unwrap_or(&result.title);
+        for article in &result.articles {
+            if out.json {
+                // One object per line, so each can be handled as it arrives.
+                match serde_json::to_string(article) {
+                    Ok(json) => println!("{}", json),
+                    Err(e) => error!("Error serializing results: {}", e),
+                }
+                continue;
+            }
+            let date = article
+                .published_display()
+                .unwrap_or_else(|| "No date".to_string());
+            println!("[{}] {}: {}", date, style::bold(title), article.title);
+            if let Some(link) = &article.link {
+                println!("  {}", link);
+            }
+        }
+        if let Some(lexicon) = lexicon {
+            notify::mentions(&watch::notifications(config, lexicon, &result.articles));
+        }
+    })
+    .await;
+    save_cache(ctx);
+    if let Err(e) = ctx.storage.save_seen(&seen) {
+        error!("Error saving read state: {}", e);
+    }
+    Some(failed < feeds.len())
+}
+
+/// Reads the document `source` names, with `stdin` standing for `-`.
+fn read_local_feed(source: &feed::LocalSource, mut stdin: impl Read) -> io::Result<Vec<u8>> {
+    match source {
+        feed::LocalSource::Stdin => {
+            let mut body = Vec::new();
+            stdin.read_to_end(&mut body)?;
+            Ok(body)
+        }
+        feed::LocalSource::File(path) => fs::read(path),
+    }
+}
+
+fn cmd_open(storage: &dyn Storage, number: usize) -> ExitCode {
+    let recent = match RecentArticles::load(storage.state_dir()) {
+        Ok(recent) => recent,
+        Err(e) => {
+            error!("Error loading the last fetch: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let link = match recent.link(number) {
+        Ok(link) => link,
+        Err(e) => {
+            eprintln!("Cannot open article {}: {}", number, e);
+            return ExitCode::FAILURE;
+        }
+    };
+    println!("Opening {}", link);
+    if let Err(e) = open::that_detached(link) {
+        error!("Error opening {}: {}", link, e);
+    }
+    ExitCode::SUCCESS
+}
+
+/// How `fetch` shows the article numbered `number`, whatever the verbosity.
+/// `raw` keeps the full content instead of a summary.
+fn article_lines(article: &Article, number: usize, new: bool, raw: bool) -> Vec<String> {
+    let date = article
+        .published_display()
+        .unwrap_or_else(|| "No date".to_string());
+    let marker = if new { " [NEW]" } else { "" };
+    let mut lines = vec![
+        format!("\n  {}. [{}]{}", number, date, marker),
+        format!("  {}", article.title),
+    ];
+    if let Some(author) = &article.author {
+        lines.push(format!("  by {}", author));
+    }
+    if let Some(content) = article.content.as_deref().filter(|c| !c.is_empty()) {
+        if raw {
+            lines.push(format!("  {}", content));
+        } else {
+            lines.push(format!("  {}", summarize(content, SUMMARY_CHARS)));
+        }
+    }
+    if let Some(link) = &article.link {
+        lines.push(format!("  {}", link));
+    }
+    for enclosure in &article.enclosures {
+        lines.push(format!("  {}", describe_enclosure(enclosure)));
+    }
+    lines
+}
+
+/// One line for an attachment: its URL plus type and size when known.
+fn describe_enclosure(enclosure: &feed::Enclosure) -> String {
+    let mut details = Vec::new();
+    if let Some(mime) = &enclosure.mime_type {
+        details.push(mime.clone());
+    }
+    if let Some(length) = enclosure.length.filter(|&l| l > 0) {
+        details.push(format!("{:.1} MB", length as f64 / 1_000_000.0));
+    }
+    if details.is_empty() {
+        format!("Attachment: {}", enclosure.url)
+    } else {
+        format!("Attachment: {} ({})", enclosure.url, details.join(", "))
+    }
+}
+
+/// Shortens text to at most `max` characters, ending in an ellipsis if cut.
+fn summarize(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(max.saturating_sub(1)).collect();
+    format!("{}…", cut.trim_end())
+}
+
+fn save_cache(ctx: Context<'_>) {
+    if let Some(cache) = ctx.cache {
+        if let Err(e) = ctx.storage.save_cache(&cache.lock().unwrap()) {
+            error!("Error saving feed cache: {}", e);
+        }
+    }
+}
+
+async fn cmd_search(ctx: Context<'_>, args: SearchArgs) -> ExitCode {
+    let out = ctx.out;
+    let Some(config) = load_config(ctx.storage) else {
+        return ExitCode::FAILURE;
+    };
+    if config.feeds.is_empty() {
+        println!("No feeds subscribed. Use 'aaron_rss add <url>' to add a feed.");
+        return ExitCode::SUCCESS;
+    }
+
+    let limit = args.limit.unwrap_or(usize::MAX);
+    let mut hits = Vec::new();
+    let mut fetches = ctx.fetcher().fetch_feeds(&config.feeds, None, true);
+    while let Some((feed, result)) = fetches.next().await {
+        let result = match result {
+            Ok(result) => result,
+            Err(e) => {
+                error!("Error fetching {}: {}", feed.url, e);
+                continue;
+            }
+        };
+        let found = match analysis::search_articles(&result.articles, &args.query, args.regex) {
+            Ok(found) => found,
+            Err(e) => {
+                eprintln!("Invalid search pattern: {}", e);
+                return ExitCode::FAILURE;
+            }
+        };
+        hits.extend(found.into_iter().map(|hit| (result.title.clone(), hit)));
+        if hits.len() >= limit {
+            hits.truncate(limit);
+            break;
+        }
+    }
+    drop(fetches);
+    save_cache(ctx);
+
+    if hits.is_empty() {
+        out.empty(&format!("No articles match '{}'.", args.query));
+        return ExitCode::SUCCESS;
+    }
+    if out.json {
+        let hits: Vec<_> = hits.into_iter().map(|(_, hit)| hit).collect();
+        out.print_json(&hits);
+        return ExitCode::SUCCESS;
+    }
+    for (feed_title, hit) in &hits {
+        println!("[{}] {}", feed_title, hit.article.title);
+        println!("  {}", hit.snippet);
+        if let Some(link) = &hit.article.link {
+            println!("  {}", link);
+        }
+        println!();
+    }
+    ExitCode::SUCCESS
+}
+
+async fn cmd_mark_read(ctx: Context<'_>, url: Option<String>) -> ExitCode {
+    let Some(config) = load_config(ctx.storage) else {
+        return ExitCode::FAILURE;
+    };
+    let feeds = match url {
+        Some(u) => vec![config
+            .find_feed(&u)
+            .cloned()
+            .unwrap_or_else(|| Feed::new(&u))],
+        None => config.feeds,
+    };
+
+    let Some(mut seen) = load_seen(ctx.storage) else {
+        return ExitCode::FAILURE;
+    };
+    let mut marked = 0;
+    let mut fetches = ctx.fetcher().fetch_feeds(&feeds, None, false);
+    while let Some((feed, result)) = fetches.next().await {
+        match result {
+            Ok(result) => marked += seen.mark_read(&result.articles),
+            Err(e) => error!("Error fetching {}: {}", feed.url, e),
+        }
+    }
+    save_cache(ctx);
+
+    if let Err(e) = ctx.storage.save_seen(&seen) {
+        error!("Error saving read state: {}", e);
+        return ExitCode::FAILURE;
+    }
+    println!("Marked {} article(s) as read.", marked);
+    ExitCode::SUCCESS
+}
+
+fn cmd_feeds(storage: &dyn Storage, action: FeedsAction) -> ExitCode {
+    match action {
+        FeedsAction::Move { url, to, up, down } => {
+            let Some(mut config) = load_config(storage) else {
+                return ExitCode::FAILURE;
+            };
+            let Some(current) = config.feed_position(&url) else {
+                println!("Feed not found: {}", url);
+                return ExitCode::SUCCESS;
+            };
+            let target = if up {
+                current.saturating_sub(1)
+            } else if down {
+                current + 1
+            } else {
+                to.unwrap_or(current)
+            };
+            let position = config.move_feed(&url, target).unwrap_or(current);
+            if let Err(e) = storage.save_config(&config) {
+                error!("Error saving config: {}", e);
+                return ExitCode::FAILURE;
+            }
+            println!("Moved feed to position {}: {}", position, url);
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+fn cmd_history(storage: &dyn Storage, action: HistoryAction) -> ExitCode {
+    match action {
+        HistoryAction::Compact { keep_days } => {
+            let Some(config) = load_config(storage) else {
+                return ExitCode::FAILURE;
+            };
+            let Some(mut history) = load_history(storage, &config) else {
+                return ExitCode::FAILURE;
+            };
+            let Some(mut seen) = load_seen(storage) else {
+                return ExitCode::FAILURE;
+            };
+            let today = chrono::Utc::now().date_naive();
+            let removed = history.compact(keep_days, today);
+            if let Err(e) = history.save(storage.state_dir()) {
+                error!("Error saving mention history: {}", e);
+                return ExitCode::FAILURE;
+            }
+
+            let forgotten = seen.compact(keep_days, today);
+            if let Err(e) = storage.save_seen(&seen) {
+                error!("Error saving read state: {}", e);
+                return ExitCode::FAILURE;
+            }
+            println!(
+                "Removed {} mention(s) and {} read marker(s) older than {} days, {} mention(s) kept.",
+                removed,
+                forgotten,
+                keep_days,
+                history.mentions.len()
+            );
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+/// Creates the stock provider chosen with `--provider`, or in the config,
+/// serving fresh data from the stock cache when enabled. Tracked crypto
+/// investments, and `extra_crypto` if given, are priced by CoinGecko.
+/// Reports the problem and returns None if it can't be set up.
+fn stock_provider<'a>(
+    ctx: Context<'a>,
+    stock_opts: StockOptions,
+    config: &Config,
+    extra_crypto: Option<&str>,
+) -> Option<Box<dyn StockProvider + 'a>> {
+    let kind = stock_opts.provider.unwrap_or(config.stock_provider);
+    let crypto_tickers = config
+        .investments
+        .iter()
+        .filter(|i| i.kind == AssetKind::Crypto)
+        .map(|i| i.ticker.clone())
+        .chain(extra_crypto.map(str::to_uppercase))
+        .collect();
+    let api_key = config.alphavantage_api_key.as_deref();
+    let provider = match stock::provider(kind, ctx.transport, api_key, crypto_tickers) {
+        Ok(provider) => provider,
+        Err(e) => {
+            error!("Error setting up stock provider: {}", e);
+            return None;
+        }
+    };
+    if !stock_opts.cache {
+        return Some(provider);
+    }
+    let dir = ctx.storage.state_dir();
+    let cache = StockCache::load(dir).unwrap_or_default();
+    Some(Box::new(CachingProvider::new(
+        provider,
+        config.stock_cache.clone(),
+        cache,
+        dir.to_path_buf(),
+        stock_opts.refresh,
+    )))
+}
+
+async fn cmd_stock(ctx: Context<'_>, stock_opts: StockOptions, action: StockAction) -> ExitCode {
+    match action {
+        StockAction::Add {
+            ticker,
+            name,
+            isin,
+            cusip,
+            alert_pct,
+            crypto,
+            no_update,
+        } => {
+            let Some(mut config) = load_config(ctx.storage) else {
+                return ExitCode::FAILURE;
+            };
+            let investment = Investment {
+                name: name.clone(),
+                isin: isin.map(|i| i.to_uppercase()),
+                cusip: cusip.map(|c| c.to_uppercase()),
+                alert_pct,
+                kind: if crypto {
+                    AssetKind::Crypto
+                } else {
+                    AssetKind::Equity
+                },
+                ..Investment::new(&ticker)
+            };
+            let outcome = config.upsert_investment(investment, !no_update);
+            if outcome == AddOutcome::Unchanged {
+                println!("Investment already tracked: {}", ticker.to_uppercase());
+                return ExitCode::SUCCESS;
+            }
+            if let Err(e) = ctx.storage.save_config(&config) {
+                error!("Error saving config: {}", e);
+                return ExitCode::FAILURE;
+            }
+            let display = match name {
+                Some(n) => format!("{} ({})", ticker.to_uppercase(), n),
+                None => ticker.to_uppercase(),
+            };
+            match outcome {
+                AddOutcome::Added => println!("Added investment: {}", display),
+                _ => println!("Updated investment: {}", display),
+            }
+        }
+        StockAction::Remove { ticker } => {
+            let ticker = stock::normalize_ticker(&ticker);
+            let Some(mut config) = load_config(ctx.storage) else {
+                return ExitCode::FAILURE;
+            };
+            if config.remove_investment(&ticker) {
+                if let Err(e) = ctx.storage.save_config(&config) {
+                    error!("Error saving config: {}", e);
+                    return ExitCode::FAILURE;
+                }
+                println!("Removed investment: {}", ticker);
+            } else {
+                println!("Investment not found: {}", ticker);
+            }
+        }
+        StockAction::List => {
+            let Some(config) = load_config(ctx.storage) else {
+                return ExitCode::FAILURE;
+            };
+            if config.investments.is_empty() {
+                println!("No investments tracked. Use 'aaron_rss stock add <ticker>' to add one.");
+                return ExitCode::SUCCESS;
+            }
+            println!("Tracked investments:");
+            for (i, inv) in config.investments.iter().enumerate() {
+                let display = match &inv.name {
+                    Some(n) => format!("{} ({})", inv.ticker, n),
+                    None => inv.ticker.clone(),
+                };
+                println!("  {}. {}", i + 1, display);
+            }
+        }
+        StockAction::Quote { ticker: None, .. } => {
+            let Some(config) = load_config(ctx.storage) else {
+                return ExitCode::FAILURE;
+            };
+            if config.investments.is_empty() {
+                println!("No investments tracked. Use 'aaron_rss stock add <ticker>' to add one.");
+                return ExitCode::SUCCESS;
+            }
+            let Some(stocks) = stock_provider(ctx, stock_opts, &config, None) else {
+                return ExitCode::FAILURE;
+            };
+            let stocks = stocks.as_ref();
+            let quotes: Vec<_> =
+                stream::iter(&config.investments)
+                    .map(|inv| async move {
+                        (inv.ticker.clone(), stocks.fetch_quote(&inv.ticker).await)
+                    })
+                    .buffered(ctx.concurrency)
+                    .collect()
+                    .await;
+            for line in stock::quote_table(&quotes) {
+                println!("{}", line);
+            }
+        }
+        StockAction::Alerts { threshold } => {
+            let Some(config) = load_config(ctx.storage) else {
+                return ExitCode::FAILURE;
+            };
+            if config.investments.is_empty() {
+                println!("No investments tracked. Use 'aaron_rss stock add <ticker>' to add one.");
+                return ExitCode::SUCCESS;
+            }
+            let Some(stocks) = stock_provider(ctx, stock_opts, &config, None) else {
+                return ExitCode::FAILURE;
+            };
+            let stocks = stocks.as_ref();
+            let quotes: Vec<_> = stream::iter(&config.investments)
+                .map(|inv| async move { (inv, stocks.fetch_quote(&inv.ticker).await) })
+                .buffered(ctx.concurrency)
+                .collect()
+                .await;
+
+            let mut fired = 0;
+            let mut failed = 0;
+            for (inv, result) in quotes {
+                let limit = threshold.or(inv.alert_pct).unwrap_or(DEFAULT_ALERT_PCT);
+                match result {
+                    Ok(quote) if quote.moved_at_least(limit) => {
+                        fired += 1;
+                        let marker = if quote.change_percent >= 0.0 {
+                            "▲"
+                        } else {
+                            "▼"
+                        };
+                        println!(
+                            "{} {}: {:+.2}% (${:.2}, alert at ±{}%)",
+                            marker, quote.ticker, quote.change_percent, quote.price, limit
+                        );
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        error!("Error fetching quote for {}: {}", inv.ticker, e);
+                        failed += 1;
+                    }
+                }
+            }
+            if failed == config.investments.len() {
+                // Nothing was checked, which "No alerts." would hide.
+                return ExitCode::from(EXIT_NO_QUOTES);
+            }
+            if fired == 0 {
+                println!("No alerts.");
+                return ExitCode::SUCCESS;
+            }
+            return ExitCode::FAILURE;
+        }
+        StockAction::Quote {
+            ticker: Some(ticker),
+            crypto,
+            ..
+        } => {
+            let Some(config) = load_config(ctx.storage) else {
+                return ExitCode::FAILURE;
+            };
+            let extra_crypto = crypto.then_some(ticker.as_str());
+            let Some(stocks) = stock_provider(ctx, stock_opts, &config, extra_crypto) else {
+                return ExitCode::FAILURE;
+            };
+            println!("Fetching quote for {}...", ticker.to_uppercase());
+            match stocks.fetch_quote(&ticker).await {
+                Ok(quote) => {
+                    let change_sign = if quote.change >= 0.0 { "+" } else { "" };
+                    println!(
+                        "\n{}: ${:.2} ({}{:.2}, {}{:.2}%) as of {}",
+                        quote.ticker,
+                        quote.price,
+                        change_sign,
+                        quote.change,
+                        change_sign,
+                        quote.change_percent,
+                        quote.date
+                    );
+                }
+                Err(StockError::NotFound) => {
+                    error!(
+                        "No quote found for {}; check the ticker symbol.",
+                        ticker.to_uppercase()
+                    );
+                }
+                Err(e) => {
+                    error!("Error fetching quote: {}", e);
+                }
+            }
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+async fn cmd_scan(ctx: Context<'_>, args: ScanArgs) -> ExitCode {
+    let out = ctx.out;
+    let Some(config) = load_config(ctx.storage) else {
+        return ExitCode::FAILURE;
+    };
+
+    if config.investments.is_empty() {
+        println!("No investments tracked. Use 'aaron_rss stock add <ticker>' to add one.");
+        return ExitCode::SUCCESS;
+    }
+
+    if config.feeds.is_empty() {
+        println!("No feeds subscribed. Use 'aaron_rss add <url>' to add a feed.");
+        return ExitCode::SUCCESS;
+    }
+
+    let lexicon = match config.lexicon() {
+        Ok(lexicon) => lexicon,
+        Err(e) => {
+            error!("Error loading sentiment lexicon: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    // Loaded before scanning so a history that can't be read stops the scan
+    // instead of being replaced by this scan's mentions alone.
+    let Some(mut history) = load_history(ctx.storage, &config) else {
+        return ExitCode::FAILURE;
+    };
+
+    out.status("Scanning feeds for investment mentions...\n");
+
+    let options = scan::ScanOptions {
+        full_text: args.full_text,
+        strict: args.strict,
+        fail_fast: args.failures.fail_fast,
+    };
+    let scanned = scan::scan_mentions(
+        ctx.fetcher(),
+        &config,
+        &lexicon,
+        options,
+        |feed, articles| args.filter.apply(feed, articles),
+    )
+    .await;
+    save_cache(ctx);
+    for (url, e) in &scanned.failed {
+        error!("Error fetching {}: {}", url, e);
+    }
+    let failed = scanned.failed.len();
+    let mut mentions = scanned.mentions;
+    let status = args.failures.exit_code(failed);
+    if failed > 0 && args.failures.fail_fast {
+        return status;
+    }
+
+    let new_mentions = history.record(&mentions);
+    if let Err(e) = history.save(ctx.storage.state_dir()) {
+        error!("Error saving mention history: {}", e);
+    }
+    if let Some(command) = &args.exec {
+        exec::run_for_mentions(command, &new_mentions).await;
+    }
+    if args.exclude_neutral {
+        mentions.retain(|m| m.sentiment != analysis::Sentiment::Neutral);
+    }
+    if let Some(threshold) = args.collapse_similar {
+        mentions = analysis::collapse_similar(mentions, threshold);
+    }
+
+    if args.by_source {
+        print_source_sentiment(&analysis::sentiment_by_source(&mentions), out);
+        return status;
+    }
+
     if mentions.is_empty() {
-        println!("No mentions found for tracked investments.");
-        return;
+        out.empty("No mentions found for tracked investments.");
+        return status;
     }
 
-    println!("Found {} mentions:\n", mentions.len());
+    out.status(&format!("Found {} mentions:\n", mentions.len()));
 
-    for mention in &mentions {
-        let date = mention.article.published.as_deref().unwrap_or("No date");
-        let sentiment_indicator = match mention.sentiment {
-            analysis::Sentiment::Positive => "+",
-            analysis::Sentiment::Negative => "-",
-            analysis::Sentiment::Neutral => "~",
-        };
+    let overflow = match args.max_per_ticker {
+        Some(max) => {
+            let (kept, overflow) = analysis::cap_per_ticker(mentions, max);
+            mentions = kept;
+            Some(overflow)
+        }
+        None => None,
+    };
+
+    match args.group_by {
+        Some(group_by) => {
+            let mut groups = analysis::group_mentions(&mentions, group_by);
+            if args.exclude_neutral {
+                groups.retain(|(key, _)| key != "Neutral" || group_by != GroupBy::Sentiment);
+            }
+            if out.json {
+                #[derive(Serialize)]
+                struct Group<'a> {
+                    group: &'a str,
+                    mentions: &'a [&'a analysis::ArticleMention],
+                }
+                let groups: Vec<Group> = groups
+                    .iter()
+                    .map(|(key, members)| Group {
+                        group: key,
+                        mentions: members,
+                    })
+                    .collect();
+                out.print_json(&scan_json("groups", &groups, overflow.as_deref()));
+                return status;
+            }
+            for (key, members) in &groups {
+                println!("== {} ({}) ==", key, members.len());
+                for mention in members {
+                    print_mention(mention, out.verbose());
+                }
+                println!();
+            }
+        }
+        None if out.json => {
+            out.print_json(&scan_json("mentions", &mentions, overflow.as_deref()));
+            return status;
+        }
+        None => {
+            for mention in &mentions {
+                print_mention(mention, out.verbose());
+            }
+        }
+    }
+
+    for (ticker, hidden) in overflow.iter().flatten() {
+        println!("[{}] +{} more", ticker, hidden);
+    }
+    status
+}
+
+/// The JSON `scan` prints: `results` as they are, or with
+/// `--max-per-ticker` (`overflow` given) an object holding them under `key`
+/// and the number of mentions left out per ticker under `more`.
+fn scan_json<T: Serialize + ?Sized>(
+    key: &str,
+    results: &T,
+    overflow: Option<&[(String, usize)]>,
+) -> serde_json::Value {
+    let results = serde_json::to_value(results).unwrap_or_default();
+    match overflow {
+        Some(overflow) => {
+            let more: BTreeMap<&str, usize> = overflow
+                .iter()
+                .map(|(ticker, hidden)| (ticker.as_str(), *hidden))
+                .collect();
+            serde_json::json!({ key: results, "more": more })
+        }
+        None => results,
+    }
+}
+
+fn print_mention(mention: &analysis::ArticleMention, verbose: bool) {
+    let date = mention
+        .article
+        .published_display()
+        .unwrap_or_else(|| "No date".to_string());
+    let sentiment_indicator = match mention.sentiment {
+        analysis::Sentiment::Positive => style::green("+"),
+        analysis::Sentiment::Negative => style::red("-"),
+        analysis::Sentiment::Neutral => style::dim("~"),
+    };
+    println!(
+        "[{}] {} [{}] {}",
+        mention.ticker, sentiment_indicator, date, mention.article.title
+    );
+    if let Some(link) = &mention.article.link {
+        println!("    {}", link);
+    }
+    println!("    {}", mention.snippet);
+    if !mention.also_in.is_empty() {
+        println!("    also in {} other feeds", mention.also_in.len());
+    }
+    if verbose {
         println!(
-            "[{}] {} [{}] {}",
-            mention.ticker, sentiment_indicator, date, mention.article.title
+            "    matched by {} \"{}\"",
+            mention.matched_by, mention.matched_term
+        );
+        println!(
+            "    sentiment score {:+.2} ({} positive, {} negative words)",
+            mention.score.score, mention.score.positive, mention.score.negative
         );
-        if let Some(link) = &mention.article.link {
-            println!("    {}", link);
-        }
     }
 }
 
-async fn cmd_analyze(ticker: &str) {
-    let config = Config::load().unwrap_or_default();
-    let ticker_upper = ticker.to_uppercase();
+fn print_source_sentiment(sources: &[analysis::SourceSentiment], out: Output) {
+    if out.json {
+        out.print_json(sources);
+        return;
+    }
 
-    let investment = config
-        .investments
-        .iter()
-        .find(|i| i.ticker == ticker_upper);
+    if sources.is_empty() {
+        out.empty("No mentions found for tracked investments.");
+        return;
+    }
 
-    if investment.is_none() {
+    println!("Sentiment by source:");
+    println!("{:-<80}", "");
+    for source in sources {
         println!(
-            "Ticker {} is not being tracked. Use 'aaron_rss stock add {}' first.",
-            ticker_upper, ticker_upper
+            "{:+4}  (+{} / -{} / ~{})  {}",
+            source.net, source.positive, source.negative, source.neutral, source.source
         );
-        return;
     }
+}
+
+fn cmd_stats(storage: &dyn Storage, ticker: &str, days: usize) -> ExitCode {
+    let ticker_upper = stock::normalize_ticker(ticker);
+    let Some(config) = load_config(storage) else {
+        return ExitCode::FAILURE;
+    };
+    let Some(history) = load_history(storage, &config) else {
+        return ExitCode::FAILURE;
+    };
+
+    let today = chrono::Utc::now().date_naive();
+    let start = history::window_start(days, today);
+    let recent: Vec<_> = history
+        .for_ticker(&ticker_upper)
+        .into_iter()
+        .filter(|r| r.date().is_some_and(|d| d >= start && d <= today))
+        .collect();
+
+    if recent.is_empty() {
+        println!("{}: no history", ticker_upper);
+        return ExitCode::SUCCESS;
+    }
+
+    let count =
+        |sentiment: analysis::Sentiment| recent.iter().filter(|r| r.sentiment == sentiment).count();
+    println!(
+        "{}: {} mentions in the last {} days (+{} / -{} / ~{})",
+        ticker_upper,
+        recent.len(),
+        days,
+        count(analysis::Sentiment::Positive),
+        count(analysis::Sentiment::Negative),
+        count(analysis::Sentiment::Neutral)
+    );
 
-    println!("Analyzing {} ...\n", ticker_upper);
+    let counts = history::daily_counts(&recent, days, today);
+    println!("  {} {} {}", start, history::sparkline(&counts), today);
+    ExitCode::SUCCESS
+}
 
-    // Fetch price history
-    println!("Fetching price history...");
-    let prices = match stock::fetch_history(ticker, 30).await {
-        Ok(history) => {
-            println!("Got {} days of price data.\n", history.prices.len());
-            history.prices
+async fn cmd_analyze(ctx: Context<'_>, stock_opts: StockOptions, args: AnalyzeArgs) -> ExitCode {
+    let out = ctx.out;
+    let Some(config) = load_config(ctx.storage) else {
+        return ExitCode::FAILURE;
+    };
+    let mut investments: Vec<&Investment> = Vec::new();
+    for ticker in &args.tickers {
+        let ticker_upper = ticker.to_uppercase();
+        if investments.iter().any(|i| i.ticker == ticker_upper) {
+            continue;
+        }
+        match config.investments.iter().find(|i| i.ticker == ticker_upper) {
+            Some(investment) => investments.push(investment),
+            None => {
+                println!(
+                    "Ticker {} is not being tracked. Use 'aaron_rss stock add {}' first.",
+                    ticker_upper, ticker_upper
+                );
+                return ExitCode::SUCCESS;
+            }
         }
+    }
+    let tickers: Vec<&str> = investments.iter().map(|i| i.ticker.as_str()).collect();
+
+    let lexicon = match config.lexicon() {
+        Ok(lexicon) => lexicon,
         Err(e) => {
-            eprintln!("Error fetching price history: {}", e);
-            Vec::new()
+            error!("Error loading sentiment lexicon: {}", e);
+            return ExitCode::FAILURE;
         }
     };
 
-    // Display recent prices
-    if !prices.is_empty() {
-        println!("Recent prices:");
-        for price in prices.iter().rev().take(5).rev() {
-            println!("  {}: ${:.2}", price.date, price.close);
+    let Some(stocks) = stock_provider(ctx, stock_opts, &config, None) else {
+        return ExitCode::FAILURE;
+    };
+
+    out.status(&format!("Analyzing {} ...\n", tickers.join(", ")));
+
+    // Fetch price histories, the benchmark's alongside
+    out.status("Fetching price history...");
+    let benchmark = async {
+        if args.no_benchmark {
+            return Vec::new();
         }
-        println!();
-    }
+        match stocks.fetch_history(&args.benchmark, 30).await {
+            Ok(history) => history.prices,
+            Err(e) => {
+                error!("Error fetching benchmark {}: {}", args.benchmark, e);
+                Vec::new()
+            }
+        }
+    };
+    let (histories, benchmark) =
+        tokio::join!(fetch_histories(stocks.as_ref(), &tickers, 30), benchmark);
 
     // Fetch and scan articles
     if config.feeds.is_empty() {
         println!("No feeds to scan. Add some feeds with 'aaron_rss add <url>'.");
-        return;
+        return ExitCode::SUCCESS;
     }
 
-    println!("Scanning feeds for mentions...");
+    out.status("Scanning feeds for mentions...");
     let mut all_articles = Vec::new();
 
-    for feed_url in &config.feeds {
-        if let Ok(result) = feed::fetch_feed(feed_url).await {
-            all_articles.extend(result.articles);
+    let mut failed = 0;
+    let mut fetches =
+        ctx.fetcher()
+            .fetch_feeds(&config.feeds, Some(feed::DEFAULT_ARTICLE_LIMIT), true);
+    while let Some((feed, result)) = fetches.next().await {
+        match result {
+            Ok(mut result) => {
+                if args.full_text {
+                    ctx.fetcher()
+                        .fill_full_text(feed, &mut result.articles)
+                        .await;
+                }
+                all_articles.extend(result.articles);
+            }
+            Err(e) => {
+                error!("Error fetching {}: {}", feed.url, e);
+                failed += 1;
+            }
         }
     }
+    drop(fetches);
+    save_cache(ctx);
+    if failed > 0 {
+        out.status(&format!(
+            "Skipped {} feed{} that failed to fetch.",
+            failed,
+            if failed == 1 { "" } else { "s" }
+        ));
+    }
+    feed::dedup_articles(&mut all_articles);
 
-    let single_investment = vec![investment.unwrap().clone()];
-    let mentions = analysis::find_mentions(&all_articles, &single_investment);
+    let mut results = Vec::new();
+    for (investment, (ticker, history)) in investments.iter().zip(histories) {
+        if tickers.len() > 1 && !out.json {
+            println!("\n{}", style::bold(&format!("== {} ==", ticker)));
+        }
+        let prices = match history {
+            Ok(history) => {
+                out.status(&format!(
+                    "Got {} days of price data for {}.\n",
+                    history.prices.len(),
+                    history.ticker
+                ));
+                history.prices
+            }
+            Err(e) => {
+                error!("Error fetching price history for {}: {}", ticker, e);
+                Vec::new()
+            }
+        };
+        if !out.json {
+            print_recent_prices(&prices);
+        }
 
-    if mentions.is_empty() {
-        println!("No recent news mentions found for {}.", ticker_upper);
-        return;
+        let mentions = analysis::find_mentions(
+            &all_articles,
+            std::slice::from_ref(*investment),
+            &config.ticker_boundaries,
+            &lexicon,
+            None,
+        );
+        if mentions.is_empty() {
+            if !out.json {
+                out.empty(&format!("No recent news mentions found for {}.", ticker));
+            }
+            results.push((ticker, Vec::new()));
+            continue;
+        }
+
+        out.status(&format!("Found {} mentions.\n", mentions.len()));
+
+        // Correlate with prices
+        let mut correlations = analysis::correlate(&mentions, &prices, args.lag);
+        analysis::apply_benchmark(&mut correlations, &benchmark);
+
+        if !out.json {
+            print_correlations(&correlations);
+            println!();
+            let summary = analysis::summarize_reactions(&correlations);
+            println!("{}", reaction_summary_line(ticker, &summary, args.lag));
+            match analysis::pearson_correlation(&mentions, &prices, args.lag) {
+                Some(r) => println!("Sentiment/price correlation: r = {:+.2}", r),
+                None => println!("Sentiment/price correlation: not enough data"),
+            }
+            let trend = analysis::sentiment_trend(&mentions, chrono::Utc::now(), args.half_life);
+            match trend {
+                Some(trend) => println!(
+                    "News sentiment: {:+.2} ({})",
+                    trend,
+                    analysis::describe_trend(trend)
+                ),
+                None => println!("News sentiment: no dated articles"),
+            }
+            println!();
+            print_mention_histogram(&analysis::mention_histogram(&mentions));
+        }
+        results.push((ticker, correlations));
+    }
+
+    let mut status = ExitCode::SUCCESS;
+    if let Some(path) = &args.csv {
+        let written = fs::File::create(path)
+            .map_err(csv::Error::from)
+            .and_then(|file| analysis::write_correlations_csv(file, &results));
+        match written {
+            Ok(()) => out.status(&format!(
+                "\nWrote {} rows to {}",
+                results.iter().map(|(_, c)| c.len()).sum::<usize>(),
+                path.display()
+            )),
+            Err(e) => {
+                error!("Error writing {}: {}", path.display(), e);
+                status = ExitCode::FAILURE;
+            }
+        }
     }
 
-    println!("Found {} mentions.\n", mentions.len());
+    if out.json {
+        // A single ticker keeps the plain list of rows; several are keyed by
+        // ticker.
+        match results.as_slice() {
+            [(_, correlations)] => out.print_json(correlations),
+            _ => out.print_json(&results.into_iter().collect::<BTreeMap<_, _>>()),
+        }
+    }
+    status
+}
+
+/// Fetches the last `days` of prices for every ticker at once. Each ticker
+/// gets its own result, in the order of `tickers`, so one failing fetch
+/// doesn't lose the others.
+async fn fetch_histories<'a>(
+    stocks: &dyn StockProvider,
+    tickers: &[&'a str],
+    days: u32,
+) -> Vec<(&'a str, Result<PriceHistory, StockError>)> {
+    future::join_all(
+        tickers
+            .iter()
+            .map(|&ticker| async move { (ticker, stocks.fetch_history(ticker, days).await) }),
+    )
+    .await
+}
 
-    // Correlate with prices
-    let correlations = analysis::correlate(&mentions, &prices);
+fn print_recent_prices(prices: &[DailyPrice]) {
+    if prices.is_empty() {
+        return;
+    }
+    println!("Recent prices:");
+    for price in prices.iter().rev().take(5).rev() {
+        let mut line = format!("  {}: ${:.2}", price.date, price.close);
+        if let (Some(open), Some(high), Some(low)) = (price.open, price.high, price.low) {
+            line.push_str(&format!(" (O {:.2} H {:.2} L {:.2})", open, high, low));
+        }
+        if let Some(volume) = price.volume {
+            line.push_str(&format!(" vol {}", volume));
+        }
+        println!("{}", line);
+    }
+    if let Some(summary) = analysis::trend_summary(prices) {
+        let position = if summary.last_close >= summary.sma {
+            "above"
+        } else {
+            "below"
+        };
+        println!(
+            "  {} trading days: {:+.1}%, {} {}-day SMA (${:.2}) → {}",
+            prices.len(),
+            summary.change,
+            position,
+            analysis::TREND_SMA_DAYS.min(prices.len()),
+            summary.sma,
+            summary.trend
+        );
+    }
+    println!();
+}
 
+fn print_correlations(correlations: &[analysis::Correlation]) {
     println!("News & Price Correlation:");
     println!("{:-<80}", "");
 
-    for corr in &correlations {
+    for corr in correlations {
         let sentiment_str = match corr.sentiment {
             analysis::Sentiment::Positive => "Positive",
             analysis::Sentiment::Negative => "Negative",
             analysis::Sentiment::Neutral => "Neutral ",
         };
 
-        let price_str = match (corr.price, corr.price_change) {
-            (Some(p), Some(c)) => {
+        let price_str = match (corr.price, corr.price_change, corr.excess_change) {
+            (Some(p), Some(c), Some(x)) => {
+                let sign = if c >= 0.0 { "+" } else { "" };
+                let xsign = if x >= 0.0 { "+" } else { "" };
+                format!("${:.2} ({}{:.1}%, {}{:.1}% vs bench)", p, sign, c, xsign, x)
+            }
+            (Some(p), Some(c), None) => {
                 let sign = if c >= 0.0 { "+" } else { "" };
                 format!("${:.2} ({}{:.1}%)", p, sign, c)
             }
-            (Some(p), None) => format!("${:.2}", p),
+            (Some(p), None, _) => format!("${:.2}", p),
             _ => "N/A".to_string(),
         };
 
+        let date = match &corr.trading_date {
+            Some(trading) if *trading != corr.date => format!("{} -> {}", corr.date, trading),
+            _ => corr.date.clone(),
+        };
         println!(
             "[{}] {} | {} | {}",
-            corr.date, sentiment_str, price_str, corr.article_title
+            date, sentiment_str, price_str, corr.article_title
+        );
+    }
+}
+
+/// Draws a bar per day of `+` (positive), `-` (negative) and `.` (neutral)
+/// mentions, scaled as by [`analysis::histogram_bars`].
+fn print_mention_histogram(histogram: &BTreeMap<String, (usize, usize, usize)>) {
+    println!("Mentions per day:");
+    for (day, (pos, neg, neu)) in analysis::histogram_bars(histogram) {
+        let (total_pos, total_neg, total_neu) = histogram[day];
+        println!(
+            "  {:<10} {:>3} {}{}{}",
+            day,
+            total_pos + total_neg + total_neu,
+            style::green(&"+".repeat(pos)),
+            style::red(&"-".repeat(neg)),
+            style::dim(&".".repeat(neu))
+        );
+    }
+}
+
+/// One line on how `ticker`'s price moved after its news, the move being
+/// taken `lag` days after each article as in `analysis::correlate`.
+fn reaction_summary_line(ticker: &str, summary: &analysis::ReactionSummary, lag: i64) -> String {
+    let total = summary.positive + summary.negative + summary.neutral;
+    let verdict = match summary.dominant {
+        None => return format!("{}: no news/price data to summarize.", ticker),
+        Some(analysis::Sentiment::Positive) => {
+            format!("mostly positive news ({}/{})", summary.positive, total)
+        }
+        Some(analysis::Sentiment::Negative) => {
+            format!("mostly negative news ({}/{})", summary.negative, total)
+        }
+        Some(analysis::Sentiment::Neutral) if summary.neutral == total => {
+            format!("only neutral news ({}/{})", summary.neutral, total)
+        }
+        Some(analysis::Sentiment::Neutral) => format!(
+            "mixed news ({} positive, {} negative of {})",
+            summary.positive, summary.negative, total
+        ),
+    };
+
+    let format_change = |change: Option<f64>| match change {
+        Some(c) => format!("{}{:.1}%", if c >= 0.0 { "+" } else { "" }, c),
+        None => "N/A".to_string(),
+    };
+
+    let when = match lag {
+        0 => "same-day move".to_string(),
+        1 => "move a day later".to_string(),
+        _ => format!("move {} days later", lag),
+    };
+    format!(
+        "{}: {}, average {} {} on positive days, {} on negative days",
+        ticker,
+        verdict,
+        when,
+        format_change(summary.avg_change_positive),
+        format_change(summary.avg_change_negative)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use rss_reader::test_dir;
+
+    /// Feeds served from fixture files in a scratch directory, with the
+    /// config and state files kept there too.
+    struct Fixtures {
+        transport: FixtureTransport,
+        storage: storage::JsonStorage,
+        fetch: FetchOptions,
+    }
+
+    impl Fixtures {
+        /// `feeds` maps each URL to the feed document it serves.
+        fn new(name: &str, feeds: &[(&str, String)]) -> Self {
+            let dir = test_dir(name);
+            let mut manifest = serde_json::Map::new();
+            for (i, (url, body)) in feeds.iter().enumerate() {
+                let file = format!("feed{}.xml", i);
+                fs::write(dir.join(&file), body).unwrap();
+                manifest.insert(url.to_string(), file.into());
+            }
+            fs::write(
+                dir.join("manifest.json"),
+                serde_json::to_string(&manifest).unwrap(),
+            )
+            .unwrap();
+            Fixtures {
+                transport: FixtureTransport::load(&dir).unwrap(),
+                storage: storage::JsonStorage::new(dir.join("config.json"), None),
+                fetch: FetchOptions::default(),
+            }
+        }
+
+        fn ctx(&self) -> Context<'_> {
+            Context {
+                transport: &self.transport,
+                storage: &self.storage,
+                fetch: &self.fetch,
+                concurrency: 2,
+                cache: None,
+                out: Output {
+                    json: false,
+                    verbosity: Verbosity::Quiet,
+                },
+                report: None,
+            }
+        }
+    }
+
+    /// An RSS document with one item per title.
+    fn rss(titles: &[&str]) -> String {
+        let items: String = titles
+            .iter()
+            .map(|t| {
+                format!(
+                    "<item><title>{}</title><link>https://example.com/{}</link></item>",
+                    t,
+                    t.replace(' ', "-")
+                )
+            })
+            .collect();
+        format!(
+            r#"<?xml version="1.0"?><rss version="2.0"><channel><title>Test</title>{}</channel></rss>"#,
+            items
+        )
+    }
+
+    fn scan_args(args: &[&str]) -> ScanArgs {
+        let cli = Cli::try_parse_from(["aaron_rss", "scan"].iter().chain(args)).unwrap();
+        match cli.command {
+            Commands::Scan(args) => args,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn failed_feeds_set_the_exit_code() {
+        let feed_errors = ExitCode::from(EXIT_FEED_ERRORS);
+        assert_eq!(scan_args(&[]).failures.exit_code(0), ExitCode::SUCCESS);
+        assert_eq!(scan_args(&[]).failures.exit_code(3), feed_errors);
+        assert_eq!(
+            fetch_args(&["--fail-fast"]).failures.exit_code(1),
+            feed_errors
+        );
+        assert_eq!(
+            fetch_args(&["--ignore-errors"]).failures.exit_code(3),
+            ExitCode::SUCCESS
+        );
+        assert!(
+            Cli::try_parse_from(["aaron_rss", "fetch", "--fail-fast", "--ignore-errors"]).is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn histories_are_keyed_by_ticker_even_when_one_fails() {
+        let chart = |ticker: &str| {
+            format!(
+                "https://query1.finance.yahoo.com/v8/finance/chart/{}?range=1mo&interval=1d",
+                ticker
+            )
+        };
+        let history = |close: f64| {
+            format!(
+                r#"{{"chart": {{"result": [{{"meta": {{}}, "timestamp": [1791813600],
+                "indicators": {{"quote": [{{"close": [{}]}}]}}}}], "error": null}}}}"#,
+                close
+            )
+        };
+        let (aapl, msft) = (chart("AAPL"), chart("MSFT"));
+        let fixtures = Fixtures::new(
+            "analyze-histories",
+            &[(&aapl, history(231.5)), (&msft, history(512.25))],
+        );
+        let yahoo = stock::YahooProvider::new(&fixtures.transport);
+
+        let results = fetch_histories(&yahoo, &["AAPL", "NOPE", "MSFT"], 30).await;
+        let summary: Vec<_> = results
+            .iter()
+            .map(|(ticker, result)| (*ticker, result.as_ref().ok().map(|h| h.prices[0].close)))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("AAPL", Some(231.5)),
+                ("NOPE", None),
+                ("MSFT", Some(512.25))
+            ]
+        );
+    }
+
+    #[test]
+    fn local_documents_are_parsed_without_the_network() {
+        let body = rss(&["Piped story", "Second story"]);
+        let parse = |source: &feed::LocalSource, stdin: &[u8]| {
+            let body = read_local_feed(source, stdin).unwrap();
+            let result = feed::parse_feed(&body, "-", feed::DEFAULT_MAX_CONTENT_LEN).unwrap();
+            titles(&result.articles)
+                .into_iter()
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+
+        let stdin = feed::local_source("-").unwrap();
+        assert_eq!(
+            parse(&stdin, body.as_bytes()),
+            ["Piped story", "Second story"]
+        );
+
+        let path = test_dir("local-feed").join("feed.xml");
+        fs::write(&path, &body).unwrap();
+        let file_url = format!("file://{}", path.display());
+        for arg in [path.to_str().unwrap(), file_url.as_str()] {
+            let source = feed::local_source(arg).unwrap();
+            assert_eq!(parse(&source, b""), ["Piped story", "Second story"]);
+        }
+    }
+
+    #[tokio::test]
+    async fn a_corrupt_read_state_is_left_untouched() {
+        let fixtures = Fixtures::new("corrupt-seen", &[]);
+        let seen_file = fixtures.storage.state_dir().join("seen.json");
+        fs::write(&seen_file, "not json").unwrap();
+
+        assert_eq!(cmd_mark_read(fixtures.ctx(), None).await, ExitCode::FAILURE);
+        assert_eq!(
+            cmd_history(&fixtures.storage, HistoryAction::Compact { keep_days: 30 }),
+            ExitCode::FAILURE
+        );
+        let config = Config::default();
+        assert_eq!(watch_poll(fixtures.ctx(), &config, &[], None).await, None);
+        assert_eq!(fs::read_to_string(&seen_file).unwrap(), "not json");
+    }
+
+    #[tokio::test]
+    async fn a_corrupt_mention_history_is_reported_not_replaced() {
+        let url = "https://news.example.com/rss";
+        let fixtures = Fixtures::new("corrupt-history", &[(url, rss(&["AAPL rallies"]))]);
+        let mut config = Config::default();
+        config.upsert_feed(Feed::new(url), false);
+        config.upsert_investment(Investment::new("AAPL"), false);
+        fixtures.storage.save_config(&config).unwrap();
+        let history_file = fixtures.storage.state_dir().join("history.json");
+        fs::write(&history_file, "[broken").unwrap();
+
+        assert_eq!(
+            cmd_scan(fixtures.ctx(), scan_args(&[])).await,
+            ExitCode::FAILURE
+        );
+        assert_eq!(cmd_stats(&fixtures.storage, "AAPL", 7), ExitCode::FAILURE);
+        assert_eq!(fs::read_to_string(&history_file).unwrap(), "[broken");
+    }
+
+    #[tokio::test]
+    async fn a_failed_csv_write_fails_analyze() {
+        let url = "https://news.example.com/rss";
+        let fixtures = Fixtures::new("analyze-csv", &[(url, rss(&["AAPL surges"]))]);
+        let mut config = Config::default();
+        config.upsert_feed(Feed::new(url), false);
+        config.upsert_investment(Investment::new("AAPL"), false);
+        fixtures.storage.save_config(&config).unwrap();
+        let stock_opts = StockOptions {
+            provider: None,
+            cache: false,
+            refresh: false,
+        };
+        let analyze = |csv: &Path| {
+            let cli = Cli::try_parse_from([
+                "aaron_rss".as_ref(),
+                "analyze".as_ref(),
+                "AAPL".as_ref(),
+                "--no-benchmark".as_ref(),
+                "--csv".as_ref(),
+                csv.as_os_str(),
+            ])
+            .unwrap();
+            match cli.command {
+                Commands::Analyze(args) => cmd_analyze(fixtures.ctx(), stock_opts, args),
+                _ => unreachable!(),
+            }
+        };
+
+        let dir = fixtures.storage.state_dir();
+        assert_eq!(analyze(&dir.join("out.csv")).await, ExitCode::SUCCESS);
+        assert!(dir.join("out.csv").exists());
+        assert_eq!(
+            analyze(&dir.join("missing").join("out.csv")).await,
+            ExitCode::FAILURE
+        );
+    }
+
+    #[tokio::test]
+    async fn alerts_without_any_quote_fail_distinctly() {
+        let fixtures = Fixtures::new("alerts-outage", &[]);
+        let mut config = Config::default();
+        config.upsert_investment(Investment::new("AAPL"), false);
+        config.upsert_investment(Investment::new("MSFT"), false);
+        fixtures.storage.save_config(&config).unwrap();
+        let stock_opts = StockOptions {
+            provider: None,
+            cache: false,
+            refresh: false,
+        };
+        let status = cmd_stock(
+            fixtures.ctx(),
+            stock_opts,
+            StockAction::Alerts { threshold: None },
+        )
+        .await;
+        assert_eq!(status, ExitCode::from(EXIT_NO_QUOTES));
+    }
+
+    #[test]
+    fn alert_thresholds_must_be_non_negative_numbers() {
+        let parse = |args: &[&str]| Cli::try_parse_from(["aaron_rss", "stock"].iter().chain(args));
+        assert!(parse(&["alerts", "--threshold", "2.5"]).is_ok());
+        assert!(parse(&["alerts", "--threshold", "0"]).is_ok());
+        for bad in ["-1", "NaN", "inf", "five"] {
+            assert!(parse(&["alerts", "--threshold", bad]).is_err(), "{}", bad);
+            assert!(
+                parse(&["add", "AAPL", "--alert-pct", bad]).is_err(),
+                "{}",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn capped_scan_json_reports_the_mentions_left_out() {
+        let mentions = ["AAPL one", "AAPL two"];
+        assert_eq!(
+            scan_json("mentions", &mentions, None),
+            serde_json::json!(["AAPL one", "AAPL two"])
+        );
+        let overflow = [("AAPL".to_string(), 3)];
+        assert_eq!(
+            scan_json("mentions", &mentions, Some(&overflow)),
+            serde_json::json!({
+                "mentions": ["AAPL one", "AAPL two"],
+                "more": {"AAPL": 3}
+            })
+        );
+        assert_eq!(
+            scan_json("groups", &mentions, Some(&[])),
+            serde_json::json!({ "groups": ["AAPL one", "AAPL two"], "more": {} })
+        );
+    }
+
+    #[test]
+    fn the_reaction_summary_names_the_lag() {
+        let summary = analysis::ReactionSummary {
+            positive: 2,
+            negative: 1,
+            neutral: 0,
+            dominant: Some(analysis::Sentiment::Positive),
+            avg_change_positive: Some(1.5),
+            avg_change_negative: Some(-0.25),
+        };
+        assert_eq!(
+            reaction_summary_line("AAPL", &summary, 0),
+            "AAPL: mostly positive news (2/3), average same-day move +1.5% on positive days, \
+             -0.2% on negative days"
+        );
+        assert!(
+            reaction_summary_line("AAPL", &summary, 1).contains("average move a day later +1.5%")
+        );
+        assert!(reaction_summary_line("AAPL", &summary, 3).contains("average move 3 days later"));
+    }
+
+    /// Counts the requests made through the transport it wraps.
+    struct Counting<'a> {
+        inner: &'a dyn Transport,
+        requests: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl Transport for Counting<'_> {
+        async fn get(
+            &self,
+            url: &str,
+            headers: &[(&str, &str)],
+        ) -> Result<http::Response, http::TransportError> {
+            self.requests
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            self.inner.get(url, headers).await
+        }
+    }
+
+    #[tokio::test]
+    async fn adding_with_a_preview_fetches_the_feed_once() {
+        let url = "https://news.example.com/rss";
+        let fixtures = Fixtures::new("add-preview", &[(url, rss(&["Markets open"]))]);
+        let counting = Counting {
+            inner: &fixtures.transport,
+            requests: Default::default(),
+        };
+        let ctx = Context {
+            transport: &counting,
+            ..fixtures.ctx()
+        };
+        let cli = Cli::try_parse_from(["aaron_rss", "add", url, "--preview", "--yes"]).unwrap();
+        let Commands::Add(args) = cli.command else {
+            unreachable!()
+        };
+
+        assert_eq!(cmd_add(ctx, args).await, ExitCode::SUCCESS);
+        assert_eq!(counting.requests.into_inner(), 1);
+        let config = fixtures.storage.load_config().unwrap();
+        assert!(config.has_feed(url));
+    }
+
+    #[test]
+    fn analyze_lag_is_bounded() {
+        let parse = |lag: &str| Cli::try_parse_from(["aaron_rss", "analyze", "AAPL", "--lag", lag]);
+        assert!(parse("0").is_ok());
+        assert!(parse(&MAX_LAG_DAYS.to_string()).is_ok());
+        assert!(parse(&(MAX_LAG_DAYS + 1).to_string()).is_err());
+        assert!(parse("-1").is_err());
+        assert!(parse(&i64::MAX.to_string()).is_err());
+    }
+
+    fn fetch_args(args: &[&str]) -> FetchArgs {
+        let cli = Cli::try_parse_from(["aaron_rss", "fetch"].iter().chain(args)).unwrap();
+        match cli.command {
+            Commands::Fetch(args) => args,
+            _ => unreachable!(),
+        }
+    }
+
+    fn article(title: &str, day: u32, tag: &str) -> Article {
+        Article {
+            id: Some(title.to_string()),
+            title: title.to_string(),
+            link: Some(format!("https://example.com/{}", title)),
+            author: None,
+            categories: vec![tag.to_string()],
+            published: Utc.with_ymd_and_hms(2026, 10, day, 12, 0, 0).single(),
+            content: None,
+            enclosures: Vec::new(),
+            source: "https://example.com/feed.xml".to_string(),
+        }
+    }
+
+    fn titles(articles: &[Article]) -> Vec<&str> {
+        articles.iter().map(|a| a.title.as_str()).collect()
+    }
+
+    #[test]
+    fn limit_applies_after_tag_filter() {
+        let feed = Feed::new("https://example.com/feed.xml");
+        let mut articles = vec![
+            article("a", 5, "news"),
+            article("b", 4, "news"),
+            article("c", 3, "rust"),
+            article("d", 2, "rust"),
+            article("e", 1, "rust"),
+        ];
+        let args = fetch_args(&["--tag", "rust", "--limit", "2"]);
+        args.select(&feed, &SeenStore::default(), &mut articles);
+        assert_eq!(titles(&articles), ["c", "d"]);
+    }
+
+    #[test]
+    fn limit_applies_after_unread_filter() {
+        let feed = Feed::new("https://example.com/feed.xml");
+        let mut articles = vec![
+            article("a", 3, "news"),
+            article("b", 2, "news"),
+            article("c", 1, "news"),
+        ];
+        let mut seen = SeenStore::default();
+        seen.mark_read(&articles[..2]);
+
+        let args = fetch_args(&["--limit", "1"]);
+        let mut unread = articles.clone();
+        args.select(&feed, &seen, &mut unread);
+        assert_eq!(titles(&unread), ["c"]);
+
+        fetch_args(&["--limit", "1", "--all"]).select(&feed, &seen, &mut articles);
+        assert_eq!(titles(&articles), ["a"]);
+    }
+
+    #[test]
+    fn empty_results_leave_stdout_empty_unless_json() {
+        let output = |json, verbosity| Output { json, verbosity };
+        let message = "No articles found.";
+        assert_eq!(
+            output(false, Verbosity::Quiet).empty_lines(message),
+            (None, None)
+        );
+        assert_eq!(
+            output(false, Verbosity::Normal).empty_lines(message),
+            (None, Some(message))
+        );
+        assert_eq!(
+            output(true, Verbosity::Quiet).empty_lines(message),
+            (Some("[]"), None)
+        );
+    }
+
+    #[test]
+    fn quiet_mode_drops_progress_but_keeps_articles() {
+        let output = |verbosity| Output {
+            json: false,
+            verbosity,
+        };
+        let progress = "Fetching: https://example.com/feed.xml";
+        assert_eq!(
+            output(Verbosity::Normal).status_line(progress),
+            Some(progress)
+        );
+        assert_eq!(
+            output(Verbosity::Verbose).status_line(progress),
+            Some(progress)
+        );
+        assert_eq!(output(Verbosity::Quiet).status_line(progress), None);
+
+        assert_eq!(
+            article_lines(&article("quiet", 14, "news"), 3, true, false),
+            [
+                "\n  3. [2026-10-14 12:00] [NEW]",
+                "  quiet",
+                "  https://example.com/quiet"
+            ]
+        );
+    }
+
+    #[test]
+    fn user_agent_flag_overrides_the_config() {
+        let mut config = Config::default();
+        let options = |args: &[&str], config: &Config| {
+            let cli = Cli::try_parse_from(["aaron_rss", "list"].iter().chain(args)).unwrap();
+            cli.http.options(config)
+        };
+        assert_eq!(options(&[], &config).user_agent, http::DEFAULT_USER_AGENT);
+        config.user_agent = Some("FromConfig/1.0".to_string());
+        assert_eq!(options(&[], &config).user_agent, "FromConfig/1.0");
+        assert_eq!(
+            options(&["--user-agent", "FromFlag/1.0"], &config).user_agent,
+            "FromFlag/1.0"
+        );
+    }
+
+    #[test]
+    fn proxy_flag_overrides_the_config() {
+        let mut config = Config::default();
+        let options = |args: &[&str], config: &Config| {
+            let cli = Cli::try_parse_from(["aaron_rss", "list"].iter().chain(args)).unwrap();
+            cli.http.options(config)
+        };
+        assert_eq!(options(&[], &config).proxy, None);
+        config.proxy = Some("http://config-proxy:3128".to_string());
+        assert_eq!(
+            options(&[], &config).proxy.as_deref(),
+            Some("http://config-proxy:3128")
+        );
+        assert_eq!(
+            options(&["--proxy", "http://flag-proxy:8080"], &config)
+                .proxy
+                .as_deref(),
+            Some("http://flag-proxy:8080")
         );
     }
 }
@@ -0,0 +1,30 @@
+use crate::analysis::ArticleMention;
+use notify_rust::Notification;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set once a notification fails to show, so later mentions go straight to
+/// stderr instead of retrying a notification service that isn't there.
+static UNAVAILABLE: AtomicBool = AtomicBool::new(false);
+
+/// Shows a desktop notification per mention, titled with the ticker and
+/// sentiment and carrying the article title. Where notifications can't be
+/// shown (no notification service, e.g. over SSH) the mention is printed
+/// to stderr instead, keeping stdout to articles (and valid JSON with
+/// `--json`).
+pub fn mentions(mentions: &[ArticleMention]) {
+    for mention in mentions {
+        let summary = format!("{} {}", mention.ticker, mention.sentiment);
+        if !UNAVAILABLE.load(Ordering::Relaxed) {
+            let shown = Notification::new()
+                .appname("aaron_rss")
+                .summary(&summary)
+                .body(&mention.article.title)
+                .show();
+            match shown {
+                Ok(_) => continue,
+                Err(_) => UNAVAILABLE.store(true, Ordering::Relaxed),
+            }
+        }
+        eprintln!("Notification: {}: {}", summary, mention.article.title);
+    }
+}
@@ -0,0 +1,158 @@
+use crate::storage::Feed;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum OpmlError {
+    /// The document is not well-formed XML
+    Xml(String),
+    /// Well-formed XML without an `<opml>` root element
+    NotOpml,
+}
+
+impl fmt::Display for OpmlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OpmlError::Xml(msg) => write!(f, "invalid XML: {}", msg),
+            OpmlError::NotOpml => write!(f, "not an OPML document"),
+        }
+    }
+}
+
+impl Error for OpmlError {}
+
+/// Extracts a feed from every `<outline>` with an `xmlUrl` attribute.
+/// Outlines nested inside folders are flattened into a single list, and the
+/// outline's `title` (or `text`) becomes the feed name unless it merely
+/// repeats the URL.
+pub fn parse(xml: &str) -> Result<Vec<Feed>, OpmlError> {
+    let mut reader = Reader::from_str(xml);
+    let mut feeds = Vec::new();
+    let mut seen_root = false;
+
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| OpmlError::Xml(e.to_string()))?;
+        let element = match event {
+            Event::Start(e) | Event::Empty(e) => e,
+            Event::Eof => break,
+            _ => continue,
+        };
+
+        match element.name().as_ref() {
+            b"opml" => seen_root = true,
+            b"outline" => {
+                let mut url = None;
+                let mut title = None;
+                let mut text = None;
+                for attr in element.attributes() {
+                    let attr = attr.map_err(|e| OpmlError::Xml(e.to_string()))?;
+                    let value = attr
+                        .decode_and_unescape_value(reader.decoder())
+                        .map_err(|e| OpmlError::Xml(e.to_string()))?
+                        .trim()
+                        .to_string();
+                    match attr.key.as_ref() {
+                        b"xmlUrl" => url = Some(value),
+                        b"title" => title = Some(value),
+                        b"text" => text = Some(value),
+                        _ => {}
+                    }
+                }
+                if let Some(url) = url.filter(|u| !u.is_empty()) {
+                    feeds.push(Feed {
+                        name: title.or(text).filter(|n| !n.is_empty() && *n != url),
+                        ..Feed::new(&url)
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if !seen_root {
+        return Err(OpmlError::NotOpml);
+    }
+    Ok(feeds)
+}
+
+/// Renders feeds as an OPML 2.0 document with one `<outline>` per feed.
+/// Feeds without a name use their URL as the outline text, which OPML
+/// requires.
+pub fn render(feeds: &[Feed]) -> String {
+    let mut out = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <opml version=\"2.0\">\n  \
+         <head>\n    <title>aaron_rss subscriptions</title>\n  </head>\n  <body>\n",
+    );
+    for feed in feeds {
+        let url = escape(&feed.url);
+        let text = feed
+            .name
+            .as_deref()
+            .map(escape)
+            .unwrap_or_else(|| url.clone());
+        out.push_str(&format!(
+            "    <outline type=\"rss\" text=\"{}\" title=\"{}\" xmlUrl=\"{}\"/>\n",
+            text, text, url
+        ));
+    }
+    out.push_str("  </body>\n</opml>\n");
+    out
+}
+
+fn escape(value: &str) -> String {
+    quick_xml::escape::escape(value).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_then_parse_round_trips() {
+        let feeds = vec![
+            Feed {
+                name: Some("News & <Views>".to_string()),
+                ..Feed::new("https://example.com/feed.xml?a=1&b=2")
+            },
+            Feed::new("https://example.org/rss"),
+        ];
+        let parsed = parse(&render(&feeds)).unwrap();
+        let pairs: Vec<_> = parsed
+            .iter()
+            .map(|f| (f.url.as_str(), f.name.as_deref()))
+            .collect();
+        assert_eq!(
+            pairs,
+            [
+                (
+                    "https://example.com/feed.xml?a=1&b=2",
+                    Some("News & <Views>")
+                ),
+                ("https://example.org/rss", None)
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_flattens_folders_and_rejects_other_documents() {
+        let xml = r#"<opml version="1.0"><body>
+            <outline text="Tech">
+                <outline text="Blog" xmlUrl="https://blog.example.com/atom"/>
+            </outline>
+            <outline text="No feed here"/>
+        </body></opml>"#;
+        let feeds = parse(xml).unwrap();
+        assert_eq!(feeds.len(), 1);
+        assert_eq!(feeds[0].name.as_deref(), Some("Blog"));
+        assert!(matches!(parse("<rss/>"), Err(OpmlError::NotOpml)));
+        assert!(matches!(
+            parse("<opml><body></opml>"),
+            Err(OpmlError::Xml(_))
+        ));
+    }
+}
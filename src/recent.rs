@@ -0,0 +1,151 @@
+use crate::feed::Article;
+use crate::storage;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecentArticle {
+    pub title: String,
+    pub link: Option<String>,
+}
+
+/// The articles shown by the last `fetch`, in the order they were numbered,
+/// persisted in `last_fetch.json` so `open <n>` can find them.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RecentArticles {
+    articles: Vec<RecentArticle>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum OpenError {
+    /// No article has that number; holds how many there are
+    OutOfRange(usize),
+    /// The article exists but has no link; holds its title
+    NoLink(String),
+}
+
+impl fmt::Display for OpenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OpenError::OutOfRange(0) => {
+                write!(
+                    f,
+                    "no articles from the last fetch; run 'aaron_rss fetch' first"
+                )
+            }
+            OpenError::OutOfRange(count) => write!(
+                f,
+                "the last fetch showed {} articles; pick a number from 1 to {}",
+                count, count
+            ),
+            OpenError::NoLink(title) => write!(f, "\"{}\" has no link", title),
+        }
+    }
+}
+
+impl Error for OpenError {}
+
+impl RecentArticles {
+    pub fn load(dir: &Path) -> io::Result<Self> {
+        let path = recent_path(dir);
+        if !path.exists() {
+            return Ok(RecentArticles::default());
+        }
+        let content = fs::read_to_string(&path)?;
+        serde_json::from_str(&content).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn save(&self, dir: &Path) -> io::Result<()> {
+        let path = recent_path(dir);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string_pretty(self)?;
+        storage::write_atomic(&path, content.as_bytes())
+    }
+
+    /// Records `article` and returns the number it is shown with.
+    pub fn push(&mut self, article: &Article) -> usize {
+        self.articles.push(RecentArticle {
+            title: article.title.clone(),
+            link: article.link.clone(),
+        });
+        self.articles.len()
+    }
+
+    /// Link of the article numbered `number` (counting from 1).
+    pub fn link(&self, number: usize) -> Result<&str, OpenError> {
+        let article = number
+            .checked_sub(1)
+            .and_then(|i| self.articles.get(i))
+            .ok_or(OpenError::OutOfRange(self.articles.len()))?;
+        article
+            .link
+            .as_deref()
+            .ok_or_else(|| OpenError::NoLink(article.title.clone()))
+    }
+}
+
+fn recent_path(dir: &Path) -> PathBuf {
+    dir.join("last_fetch.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn article(title: &str, link: Option<&str>) -> Article {
+        Article {
+            id: None,
+            title: title.to_string(),
+            link: link.map(str::to_string),
+            author: None,
+            categories: Vec::new(),
+            published: None,
+            content: None,
+            enclosures: Vec::new(),
+            source: "https://example.com/feed.xml".to_string(),
+        }
+    }
+
+    #[test]
+    fn numbers_resolve_to_links_from_one() {
+        let mut recent = RecentArticles::default();
+        assert_eq!(
+            recent.push(&article("First", Some("https://example.com/1"))),
+            1
+        );
+        assert_eq!(recent.push(&article("Linkless", None)), 2);
+
+        assert_eq!(recent.link(1), Ok("https://example.com/1"));
+        assert_eq!(
+            recent.link(2),
+            Err(OpenError::NoLink("Linkless".to_string()))
+        );
+        assert_eq!(recent.link(0), Err(OpenError::OutOfRange(2)));
+        assert_eq!(recent.link(3), Err(OpenError::OutOfRange(2)));
+        assert_eq!(
+            OpenError::OutOfRange(2).to_string(),
+            "the last fetch showed 2 articles; pick a number from 1 to 2"
+        );
+        assert_eq!(
+            RecentArticles::default().link(1).unwrap_err().to_string(),
+            "no articles from the last fetch; run 'aaron_rss fetch' first"
+        );
+    }
+
+    #[test]
+    fn the_last_fetch_survives_a_round_trip() {
+        let dir = rss_reader::test_dir("recent");
+        assert!(RecentArticles::load(&dir).unwrap().articles.is_empty());
+        let mut recent = RecentArticles::default();
+        recent.push(&article("Saved", Some("https://example.com/saved")));
+        recent.save(&dir).unwrap();
+        let loaded = RecentArticles::load(&dir).unwrap();
+        assert_eq!(loaded.link(1), Ok("https://example.com/saved"));
+    }
+}
@@ -0,0 +1,226 @@
+use crate::analysis::{self, ArticleMention, Lexicon};
+use crate::feed::{self, Article, FeedError};
+use crate::fetcher::Fetcher;
+use crate::storage::{Config, Feed};
+use futures::StreamExt;
+use tokio::sync::mpsc;
+
+/// Number of fetched feeds allowed to queue up waiting for mention extraction.
+const CHANNEL_CAPACITY: usize = 4;
+
+/// How [`scan_mentions`] treats the articles it fetches.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ScanOptions {
+    /// Replace each article's content with the text of its web page first
+    pub full_text: bool,
+    /// Only match the config's `ambiguous_tickers` as cashtags like $ON or
+    /// by company name
+    pub strict: bool,
+    /// Stop at the first feed that can't be fetched
+    pub fail_fast: bool,
+}
+
+/// What [`scan_mentions`] found.
+#[derive(Debug, Default)]
+pub struct Scan {
+    pub mentions: Vec<ArticleMention>,
+    /// Feeds that couldn't be fetched, with why
+    pub failed: Vec<(String, FeedError)>,
+}
+
+/// Fetches the feeds in `config` concurrently and extracts mentions of its
+/// investments as each feed arrives, after `filter` has narrowed the feed's
+/// articles. A story carried by several feeds is only scanned once.
+///
+/// Parsed articles pass through a bounded channel and are dropped once their
+/// mentions have been extracted, so at most the fetcher's concurrency in
+/// flight plus `CHANNEL_CAPACITY` queued feeds are held in memory. Saving
+/// the fetcher's cache is left to the caller.
+pub async fn scan_mentions(
+    fetcher: Fetcher<'_>,
+    config: &Config,
+    lexicon: &Lexicon,
+    options: ScanOptions,
+    filter: impl Fn(&Feed, &mut Vec<Article>),
+) -> Scan {
+    let (tx, mut rx) = mpsc::channel::<Vec<Article>>(CHANNEL_CAPACITY);
+
+    let producer = async move {
+        let mut fetches =
+            fetcher.fetch_feeds(&config.feeds, Some(feed::DEFAULT_ARTICLE_LIMIT), false);
+        let mut failed = Vec::new();
+        while let Some((feed, result)) = fetches.next().await {
+            match result {
+                Ok(mut result) => {
+                    filter(feed, &mut result.articles);
+                    if options.full_text {
+                        fetcher.fill_full_text(feed, &mut result.articles).await;
+                    }
+                    if tx.send(result.articles).await.is_err() {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    failed.push((feed.url.clone(), e));
+                    if options.fail_fast {
+                        break;
+                    }
+                }
+            }
+        }
+        failed
+    };
+
+    let consumer = async {
+        let mut mentions = Vec::new();
+        // The same story often arrives through several feeds.
+        let mut dedup = feed::Deduplicator::default();
+        while let Some(mut articles) = rx.recv().await {
+            dedup.retain_new(&mut articles);
+            mentions.extend(analysis::find_mentions(
+                &articles,
+                &config.investments,
+                &config.ticker_boundaries,
+                lexicon,
+                options
+                    .strict
+                    .then_some(config.ambiguous_tickers.as_slice()),
+            ));
+        }
+        mentions
+    };
+
+    let (failed, mentions) = tokio::join!(producer, consumer);
+    Scan { mentions, failed }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feed::FetchOptions;
+    use crate::http::StubTransport;
+    use crate::storage::Investment;
+
+    /// An RSS document with one item per title.
+    fn rss(titles: &[&str]) -> String {
+        let items: String = titles
+            .iter()
+            .map(|t| {
+                format!(
+                    "<item><title>{}</title><link>https://example.com/{}</link></item>",
+                    t,
+                    t.replace(' ', "-")
+                )
+            })
+            .collect();
+        format!(
+            r#"<rss version="2.0"><channel><title>Test</title>{}</channel></rss>"#,
+            items
+        )
+    }
+
+    /// A config tracking AAPL in `feeds`, each served by `transport` as
+    /// given or left unanswered when its document is None.
+    fn subscribe(feeds: &[(&str, Option<String>)]) -> (Config, StubTransport) {
+        let mut config = Config::default();
+        config.upsert_investment(Investment::new("AAPL"), false);
+        let mut transport = StubTransport::default();
+        for (url, body) in feeds {
+            config.upsert_feed(Feed::new(url), false);
+            if let Some(body) = body {
+                transport = transport.respond(url, 200, body.as_str());
+            }
+        }
+        (config, transport)
+    }
+
+    #[tokio::test]
+    async fn scan_mentions_drains_more_feeds_than_the_channel_holds() {
+        let mut feeds: Vec<(String, Option<String>)> = (0..CHANNEL_CAPACITY * 3)
+            .map(|i| {
+                let title = format!("AAPL story {}", i);
+                (
+                    format!("https://feed{}.example.com/rss", i),
+                    Some(rss(&[&title])),
+                )
+            })
+            .collect();
+        feeds.push(("https://unmapped.example.com/rss".to_string(), None));
+        let feeds: Vec<(&str, Option<String>)> =
+            feeds.iter().map(|(u, b)| (u.as_str(), b.clone())).collect();
+        let (config, transport) = subscribe(&feeds);
+        let options = FetchOptions::default();
+        let fetcher = Fetcher {
+            concurrency: 2,
+            ..Fetcher::new(&transport, &options)
+        };
+
+        let scan = scan_mentions(
+            fetcher,
+            &config,
+            &Lexicon::default(),
+            ScanOptions::default(),
+            |_, _| {},
+        )
+        .await;
+        assert_eq!(scan.mentions.len(), feeds.len() - 1);
+        assert_eq!(scan.failed.len(), 1);
+        assert_eq!(scan.failed[0].0, "https://unmapped.example.com/rss");
+    }
+
+    #[tokio::test]
+    async fn scan_counts_failed_feeds_and_stops_early_with_fail_fast() {
+        let (config, transport) = subscribe(&[
+            ("https://ok.example.com/rss", Some(rss(&["AAPL rallies"]))),
+            ("https://gone1.example.com/rss", None),
+            ("https://gone2.example.com/rss", None),
+        ]);
+        let options = FetchOptions::default();
+        let fetcher = Fetcher::new(&transport, &options);
+        let lexicon = Lexicon::default();
+
+        let scan = scan_mentions(
+            fetcher,
+            &config,
+            &lexicon,
+            ScanOptions::default(),
+            |_, _| {},
+        )
+        .await;
+        assert_eq!(scan.failed.len(), 2);
+        let fail_fast = ScanOptions {
+            fail_fast: true,
+            ..ScanOptions::default()
+        };
+        let scan = scan_mentions(fetcher, &config, &lexicon, fail_fast, |_, _| {}).await;
+        assert_eq!(scan.failed.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn filtered_and_repeated_stories_are_not_scanned() {
+        let (config, transport) = subscribe(&[
+            (
+                "https://a.example.com/rss",
+                Some(rss(&["AAPL rallies", "AAPL recall"])),
+            ),
+            ("https://b.example.com/rss", Some(rss(&["AAPL rallies"]))),
+        ]);
+        let options = FetchOptions::default();
+        let fetcher = Fetcher::new(&transport, &options);
+
+        let scan = scan_mentions(
+            fetcher,
+            &config,
+            &Lexicon::default(),
+            ScanOptions::default(),
+            |_, articles| articles.retain(|a| !a.title.contains("recall")),
+        )
+        .await;
+        let titles: Vec<_> = scan
+            .mentions
+            .iter()
+            .map(|m| m.article.title.as_str())
+            .collect();
+        assert_eq!(titles, ["AAPL rallies"]);
+    }
+}
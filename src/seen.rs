@@ -0,0 +1,186 @@
+use crate::feed::Article;
+use crate::storage;
+use chrono::{Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Articles marked as read, persisted in `seen.json` next to the config.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SeenStore {
+    /// Article key to the date (YYYY-MM-DD) it was marked read
+    articles: HashMap<String, String>,
+}
+
+impl SeenStore {
+    pub fn load(dir: &Path) -> io::Result<Self> {
+        let path = seen_path(dir);
+        if !path.exists() {
+            return Ok(SeenStore::default());
+        }
+        let content = fs::read_to_string(&path)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+        serde_json::from_str(&content).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), e),
+            )
+        })
+    }
+
+    /// Writes the store to a temporary file and renames it into place.
+    pub fn save(&self, dir: &Path) -> io::Result<()> {
+        let path = seen_path(dir);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string_pretty(self)?;
+        storage::write_atomic(&path, content.as_bytes())
+    }
+
+    /// Every article key with the date it was marked read.
+    #[cfg(feature = "sqlite")]
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.articles
+            .iter()
+            .map(|(key, marked)| (key.as_str(), marked.as_str()))
+    }
+
+    pub fn is_read(&self, article: &Article) -> bool {
+        self.articles.contains_key(&article_key(article))
+    }
+
+    /// Marks articles as read and returns how many weren't already.
+    pub fn mark_read(&mut self, articles: &[Article]) -> usize {
+        let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+        let mut marked = 0;
+        for article in articles {
+            if let Entry::Vacant(entry) = self.articles.entry(article_key(article)) {
+                entry.insert(today.clone());
+                marked += 1;
+            }
+        }
+        marked
+    }
+
+    /// Keeps only the articles that aren't marked read yet, and marks those
+    /// read.
+    pub fn take_unread(&mut self, articles: &mut Vec<Article>) {
+        let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+        articles.retain(|article| match self.articles.entry(article_key(article)) {
+            Entry::Vacant(entry) => {
+                entry.insert(today.clone());
+                true
+            }
+            Entry::Occupied(_) => false,
+        });
+    }
+
+    /// Forgets articles marked read more than `keep_days` days before
+    /// `today`. Returns how many were removed.
+    pub fn compact(&mut self, keep_days: u32, today: NaiveDate) -> usize {
+        let cutoff = today - Duration::days(keep_days as i64);
+        let before = self.articles.len();
+        self.articles.retain(|_, marked| {
+            NaiveDate::parse_from_str(marked, "%Y-%m-%d").map_or(true, |d| d >= cutoff)
+        });
+        before - self.articles.len()
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl FromIterator<(String, String)> for SeenStore {
+    /// Builds a store from article keys and the dates they were marked read.
+    fn from_iter<I: IntoIterator<Item = (String, String)>>(entries: I) -> Self {
+        SeenStore {
+            articles: entries.into_iter().collect(),
+        }
+    }
+}
+
+/// Identifies an article across fetches: its id, or the feed URL and title
+/// for articles without one.
+fn article_key(article: &Article) -> String {
+    match &article.id {
+        Some(id) => id.clone(),
+        None => format!("{}#{}", article.source, article.title),
+    }
+}
+
+fn seen_path(dir: &Path) -> PathBuf {
+    dir.join("seen.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compact_forgets_articles_read_before_the_cutoff() {
+        let mut seen = SeenStore::default();
+        for (key, marked) in [
+            ("old", "2026-01-01"),
+            ("recent", "2026-10-10"),
+            ("odd", "?"),
+        ] {
+            seen.articles.insert(key.to_string(), marked.to_string());
+        }
+        let today = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+        assert_eq!(seen.compact(30, today), 1);
+        let mut keys: Vec<_> = seen.articles.keys().map(String::as_str).collect();
+        keys.sort();
+        assert_eq!(keys, ["odd", "recent"]);
+    }
+
+    fn article(id: Option<&str>, title: &str) -> Article {
+        Article {
+            id: id.map(str::to_string),
+            title: title.to_string(),
+            link: None,
+            author: None,
+            categories: Vec::new(),
+            published: None,
+            content: None,
+            enclosures: Vec::new(),
+            source: "https://example.com/feed.xml".to_string(),
+        }
+    }
+
+    fn titles(articles: &[Article]) -> Vec<&str> {
+        articles.iter().map(|a| a.title.as_str()).collect()
+    }
+
+    #[test]
+    fn successive_snapshots_only_yield_new_articles() {
+        let mut seen = SeenStore::default();
+        let mut first = vec![article(Some("1"), "One"), article(None, "Untracked")];
+        seen.take_unread(&mut first);
+        assert_eq!(titles(&first), ["One", "Untracked"]);
+
+        let mut second = vec![
+            article(Some("1"), "One (edited)"),
+            article(None, "Untracked"),
+            article(Some("2"), "Two"),
+        ];
+        seen.take_unread(&mut second);
+        assert_eq!(titles(&second), ["Two"]);
+        assert!(seen.is_read(&article(Some("2"), "Two")));
+
+        let mut third = vec![article(Some("2"), "Two")];
+        seen.take_unread(&mut third);
+        assert!(third.is_empty());
+    }
+
+    #[test]
+    fn a_corrupt_store_is_an_error_naming_the_file() {
+        let dir = crate::test_dir("seen-corrupt");
+        assert!(SeenStore::load(&dir).unwrap().articles.is_empty());
+        fs::write(seen_path(&dir), "{\"articles\": [").unwrap();
+        let err = SeenStore::load(&dir).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("seen.json"), "{}", err);
+    }
+}
@@ -1,7 +1,22 @@
-use serde::Deserialize;
+mod alphavantage;
+mod cache;
+mod coingecko;
+mod yahoo;
+
+use crate::http::{Response, Transport, TransportError};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+pub use alphavantage::AlphaVantageProvider;
+pub use cache::{CachingProvider, StockCache, StockCacheTtl};
+pub use coingecko::CoinGeckoProvider;
+pub use yahoo::YahooProvider;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StockQuote {
     pub ticker: String,
     pub price: f64,
@@ -10,10 +25,22 @@ pub struct StockQuote {
     pub date: String,
 }
 
-#[derive(Debug, Clone)]
+impl StockQuote {
+    /// Whether the price moved by at least `threshold` percent, up or down.
+    pub fn moved_at_least(&self, threshold: f64) -> bool {
+        self.change_percent.abs() >= threshold
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DailyPrice {
     pub date: String,
+    pub open: Option<f64>,
+    pub high: Option<f64>,
+    pub low: Option<f64>,
     pub close: f64,
+    /// Shares traded during the day
+    pub volume: Option<u64>,
 }
 
 #[derive(Debug)]
@@ -22,152 +49,571 @@ pub struct PriceHistory {
     pub prices: Vec<DailyPrice>,
 }
 
-#[derive(Deserialize)]
-struct YahooResponse {
-    chart: ChartResult,
+#[derive(Debug, thiserror::Error)]
+pub enum StockError {
+    #[error("network error: {0}")]
+    Network(String),
+    #[error("timed out after {}s", .0.as_secs())]
+    Timeout(Duration),
+    /// Unexpected HTTP status
+    #[error("HTTP status {0}")]
+    Http(u16),
+    /// HTTP 429
+    #[error("rate limited (HTTP 429)")]
+    RateLimited,
+    /// Unknown ticker or no data returned for it
+    #[error("no data returned for ticker")]
+    NotFound,
+    /// The response body could not be decoded
+    #[error("invalid response: {0}")]
+    Decode(String),
+    /// The provider reported an error of its own
+    #[error("{0}")]
+    Provider(String),
+    /// The provider needs an API key and none is configured
+    #[error(
+        "no API key configured (set {} or alphavantage_api_key in config.json)",
+        alphavantage::API_KEY_VAR
+    )]
+    MissingApiKey,
+}
+
+impl StockError {
+    pub(crate) fn from_status(status: u16) -> Self {
+        match status {
+            404 => StockError::NotFound,
+            429 => StockError::RateLimited,
+            _ => StockError::Http(status),
+        }
+    }
+}
+
+/// Longest excerpt of an unexpected response body quoted in errors.
+const BODY_SNIPPET_CHARS: usize = 80;
+
+/// Checks that `provider` answered with JSON. Rate limiting and outages tend
+/// to produce HTML block pages or empty bodies, which would otherwise surface
+/// as an opaque parse error.
+pub(crate) fn expect_json(provider: &str, response: &Response) -> Result<(), StockError> {
+    let declared_json = response
+        .header("Content-Type")
+        .is_none_or(|ct| ct.contains("json"));
+    let body = String::from_utf8_lossy(&response.body);
+    if declared_json && body.trim_start().starts_with(['{', '[']) {
+        return Ok(());
+    }
+
+    Err(match response.status {
+        404 => StockError::NotFound,
+        429 => StockError::RateLimited,
+        status => {
+            let text = crate::feed::strip_html(&body);
+            let snippet = if text.is_empty() {
+                "(empty body)".to_string()
+            } else {
+                text.chars().take(BODY_SNIPPET_CHARS).collect()
+            };
+            StockError::Provider(format!(
+                "{} returned non-JSON (status {}), possibly rate-limited: {}",
+                provider, status, snippet
+            ))
+        }
+    })
 }
 
-#[derive(Deserialize)]
-struct ChartResult {
-    result: Option<Vec<ChartData>>,
-    error: Option<YahooError>,
+impl From<TransportError> for StockError {
+    fn from(e: TransportError) -> Self {
+        match e {
+            TransportError::Timeout(after) => StockError::Timeout(after),
+            TransportError::Network(msg) => StockError::Network(msg),
+        }
+    }
 }
 
-#[derive(Deserialize)]
-struct YahooError {
-    description: String,
+#[derive(Debug, PartialEq)]
+pub enum TickerError {
+    Empty,
+    /// A character that can't appear in a ticker symbol
+    InvalidChar(char),
 }
 
-#[derive(Deserialize)]
-struct ChartData {
-    meta: MetaData,
-    timestamp: Option<Vec<i64>>,
-    indicators: Indicators,
+impl fmt::Display for TickerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TickerError::Empty => write!(f, "ticker is empty"),
+            TickerError::InvalidChar(c) => write!(
+                f,
+                "'{}' is not allowed in a ticker (use letters, digits, '.' and '-')",
+                c
+            ),
+        }
+    }
 }
 
-#[derive(Deserialize)]
-struct MetaData {
-    #[serde(rename = "regularMarketPrice")]
-    regular_market_price: Option<f64>,
-    #[serde(rename = "previousClose")]
-    previous_close: Option<f64>,
+impl Error for TickerError {}
+
+/// Checks that `s` looks like a ticker symbol and returns it normalized as
+/// by [`normalize_ticker`]. Letters, digits, `.` and `-` are allowed
+/// (`BRK.B`, `RDS-A`), plus `=` for currencies and futures (`EURUSD=X`) and
+/// a leading `^` for indices (`^GSPC`).
+pub fn validate_ticker(s: &str) -> Result<String, TickerError> {
+    let ticker = strip_cashtag(s);
+    let symbol = ticker.strip_prefix('^').unwrap_or(ticker);
+    if symbol.is_empty() {
+        return Err(TickerError::Empty);
+    }
+    if let Some(c) = symbol
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '=')))
+    {
+        return Err(TickerError::InvalidChar(c));
+    }
+    Ok(ticker.to_uppercase())
 }
 
-#[derive(Deserialize)]
-struct Indicators {
-    quote: Vec<QuoteData>,
+/// The form tickers are stored and compared in: surrounding whitespace and a
+/// leading cashtag `$` removed, then uppercased, so `" aapl "`, `$AAPL` and
+/// `aapl` are all `AAPL`.
+///
+/// ```
+/// use rss_reader::stock::normalize_ticker;
+///
+/// for typed in [" aapl ", "$AAPL", "aapl"] {
+///     assert_eq!(normalize_ticker(typed), "AAPL");
+/// }
+/// ```
+pub fn normalize_ticker(s: &str) -> String {
+    strip_cashtag(s).to_uppercase()
 }
 
-#[derive(Deserialize)]
-struct QuoteData {
-    close: Option<Vec<Option<f64>>>,
+fn strip_cashtag(s: &str) -> &str {
+    let s = s.trim();
+    s.strip_prefix('$').unwrap_or(s)
 }
 
-pub async fn fetch_quote(ticker: &str) -> Result<StockQuote, Box<dyn Error>> {
-    let url = format!(
-        "https://query1.finance.yahoo.com/v8/finance/chart/{}?range=1d&interval=1d",
-        ticker.to_uppercase()
-    );
+/// A source of stock quotes and daily closing prices.
+#[async_trait]
+pub trait StockProvider: Send + Sync {
+    /// Latest price and the change since the previous close.
+    async fn fetch_quote(&self, ticker: &str) -> Result<StockQuote, StockError>;
 
-    let client = reqwest::Client::new();
-    let response = client
-        .get(&url)
-        .header("User-Agent", "Mozilla/5.0")
-        .send()
-        .await?;
+    /// Daily closes covering roughly the last `days` days, oldest first.
+    async fn fetch_history(&self, ticker: &str, days: u32) -> Result<PriceHistory, StockError>;
 
-    let data: YahooResponse = response.json().await?;
+    /// Name of the service that answers for `ticker`, e.g. "yahoo".
+    fn source(&self, ticker: &str) -> &str;
+}
+
+/// Sends crypto tickers to one provider and everything else to another.
+pub struct AssetRouter<'a> {
+    equities: Box<dyn StockProvider + 'a>,
+    crypto: Box<dyn StockProvider + 'a>,
+    /// Uppercased tickers routed to `crypto`
+    crypto_tickers: HashSet<String>,
+}
 
-    if let Some(error) = data.chart.error {
-        return Err(format!("Yahoo Finance error: {}", error.description).into());
+impl<'a> AssetRouter<'a> {
+    pub fn new(
+        equities: Box<dyn StockProvider + 'a>,
+        crypto: Box<dyn StockProvider + 'a>,
+        crypto_tickers: HashSet<String>,
+    ) -> Self {
+        AssetRouter {
+            equities,
+            crypto,
+            crypto_tickers,
+        }
     }
 
-    let result = data
-        .chart
-        .result
-        .and_then(|r| r.into_iter().next())
-        .ok_or("No data returned for ticker")?;
+    fn route(&self, ticker: &str) -> &dyn StockProvider {
+        if self.crypto_tickers.contains(&ticker.to_uppercase()) {
+            self.crypto.as_ref()
+        } else {
+            self.equities.as_ref()
+        }
+    }
+}
 
-    let price = result.meta.regular_market_price.unwrap_or(0.0);
-    let previous_close = result.meta.previous_close.unwrap_or(price);
-    let change = price - previous_close;
-    let change_percent = if previous_close > 0.0 {
-        (change / previous_close) * 100.0
-    } else {
-        0.0
+#[async_trait]
+impl StockProvider for AssetRouter<'_> {
+    async fn fetch_quote(&self, ticker: &str) -> Result<StockQuote, StockError> {
+        self.route(ticker).fetch_quote(ticker).await
+    }
+
+    async fn fetch_history(&self, ticker: &str, days: u32) -> Result<PriceHistory, StockError> {
+        self.route(ticker).fetch_history(ticker, days).await
+    }
+
+    fn source(&self, ticker: &str) -> &str {
+        self.route(ticker).source(ticker)
+    }
+}
+
+/// Stock data providers selectable with `--provider`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderKind {
+    /// Yahoo Finance's chart API (no key needed)
+    #[default]
+    Yahoo,
+    /// Alpha Vantage (needs an API key)
+    #[value(name = "alphavantage")]
+    AlphaVantage,
+}
+
+impl ProviderKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ProviderKind::Yahoo => "yahoo",
+            ProviderKind::AlphaVantage => "alphavantage",
+        }
+    }
+}
+
+/// Creates the provider of the given kind, sending `crypto_tickers` to
+/// CoinGecko instead. `api_key` is only used by providers that need one; the
+/// environment variable takes precedence.
+pub fn provider<'a>(
+    kind: ProviderKind,
+    transport: &'a dyn Transport,
+    api_key: Option<&str>,
+    crypto_tickers: HashSet<String>,
+) -> Result<Box<dyn StockProvider + 'a>, StockError> {
+    let equities: Box<dyn StockProvider + 'a> = match kind {
+        ProviderKind::Yahoo => Box::new(YahooProvider::new(transport)),
+        ProviderKind::AlphaVantage => {
+            let api_key = std::env::var(alphavantage::API_KEY_VAR)
+                .ok()
+                .or(api_key.map(str::to_string))
+                .filter(|k| !k.is_empty())
+                .ok_or(StockError::MissingApiKey)?;
+            Box::new(AlphaVantageProvider::new(transport, api_key))
+        }
     };
+    if crypto_tickers.is_empty() {
+        return Ok(equities);
+    }
+    Ok(Box::new(AssetRouter::new(
+        equities,
+        Box::new(CoinGeckoProvider::new(transport)),
+        crypto_tickers,
+    )))
+}
 
-    let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+/// Simple moving average of the last `window` closes, or of all of them
+/// when there are fewer. None for an empty series.
+pub fn sma(prices: &[DailyPrice], window: usize) -> Option<f64> {
+    let window = window.min(prices.len());
+    if window == 0 {
+        return None;
+    }
+    let recent = &prices[prices.len() - window..];
+    Some(recent.iter().map(|p| p.close).sum::<f64>() / window as f64)
+}
 
-    Ok(StockQuote {
-        ticker: ticker.to_uppercase(),
-        price,
-        change,
-        change_percent,
-        date,
-    })
+/// Percent change from the first close to the last.
+pub fn percent_change(prices: &[DailyPrice]) -> Option<f64> {
+    let first = prices.first()?.close;
+    let last = prices.last()?.close;
+    (first != 0.0).then(|| (last - first) / first * 100.0)
 }
 
-pub async fn fetch_history(ticker: &str, days: u32) -> Result<PriceHistory, Box<dyn Error>> {
-    let range = if days <= 5 {
-        "5d"
-    } else if days <= 30 {
-        "1mo"
-    } else if days <= 90 {
-        "3mo"
+/// Formats quotes as a table with right-aligned numbers. Tickers whose quote
+/// failed get a row with the error instead.
+pub fn quote_table(quotes: &[(String, Result<StockQuote, StockError>)]) -> Vec<String> {
+    let cells: Vec<(&str, Result<[String; 3], String>)> = quotes
+        .iter()
+        .map(|(ticker, result)| {
+            let cells = match result {
+                Ok(q) => Ok([
+                    format!("${:.2}", q.price),
+                    format!("{:+.2}", q.change),
+                    format!("{:+.2}%", q.change_percent),
+                ]),
+                Err(e) => Err(format!("error: {}", e)),
+            };
+            (ticker.as_str(), cells)
+        })
+        .collect();
+
+    let headers = ["TICKER", "PRICE", "CHANGE", "CHANGE %"];
+    let mut widths = headers.map(|h| h.len());
+    for (ticker, row) in &cells {
+        widths[0] = widths[0].max(ticker.len());
+        if let Ok(values) = row {
+            for (width, value) in widths[1..].iter_mut().zip(values) {
+                *width = (*width).max(value.len());
+            }
+        }
+    }
+
+    let mut lines = vec![format!(
+        "{:<w0$}  {:>w1$}  {:>w2$}  {:>w3$}",
+        headers[0],
+        headers[1],
+        headers[2],
+        headers[3],
+        w0 = widths[0],
+        w1 = widths[1],
+        w2 = widths[2],
+        w3 = widths[3]
+    )];
+    for (ticker, row) in cells {
+        lines.push(match row {
+            Ok([price, change, percent]) => format!(
+                "{:<w0$}  {:>w1$}  {:>w2$}  {:>w3$}",
+                ticker,
+                price,
+                change,
+                percent,
+                w0 = widths[0],
+                w1 = widths[1],
+                w2 = widths[2],
+                w3 = widths[3]
+            ),
+            Err(e) => format!("{:<w0$}  {}", ticker, e, w0 = widths[0]),
+        });
+    }
+    lines
+}
+
+/// Timestamps at or above this magnitude are taken to be in milliseconds;
+/// as seconds they would fall after the year 5000.
+const MILLIS_THRESHOLD: i64 = 100_000_000_000;
+
+/// Converts a Unix timestamp in seconds or milliseconds to a `YYYY-MM-DD` date.
+pub(crate) fn timestamp_to_date(ts: i64) -> Option<String> {
+    let secs = if ts.abs() >= MILLIS_THRESHOLD {
+        ts / 1000
     } else {
-        "6mo"
+        ts
     };
+    chrono::DateTime::from_timestamp(secs, 0).map(|dt| dt.format("%Y-%m-%d").to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timestamps_in_seconds_or_milliseconds() {
+        assert_eq!(timestamp_to_date(1791813600).as_deref(), Some("2026-10-12"));
+        assert_eq!(
+            timestamp_to_date(1791813600000).as_deref(),
+            Some("2026-10-12")
+        );
+        assert_eq!(timestamp_to_date(0).as_deref(), Some("1970-01-01"));
+        assert_eq!(timestamp_to_date(i64::MAX), None);
+    }
+
+    fn quote(ticker: &str, price: f64, change: f64, change_percent: f64) -> StockQuote {
+        StockQuote {
+            ticker: ticker.to_string(),
+            price,
+            change,
+            change_percent,
+            date: "2026-10-16".to_string(),
+        }
+    }
+
+    #[test]
+    fn quote_table_aligns_columns() {
+        let quotes = vec![
+            (
+                "BRK.A".to_string(),
+                Ok(quote("BRK.A", 712345.5, -1234.5, -0.17)),
+            ),
+            ("F".to_string(), Ok(quote("F", 9.87, 0.12, 1.23))),
+            ("NOPE".to_string(), Err(StockError::NotFound)),
+        ];
+        assert_eq!(
+            quote_table(&quotes),
+            [
+                "TICKER       PRICE    CHANGE  CHANGE %",
+                "BRK.A   $712345.50  -1234.50    -0.17%",
+                "F            $9.87     +0.12    +1.23%",
+                "NOPE    error: no data returned for ticker",
+            ]
+        );
+    }
+
+    #[test]
+    fn statuses_map_to_error_variants() {
+        assert!(matches!(StockError::from_status(404), StockError::NotFound));
+        assert!(matches!(
+            StockError::from_status(429),
+            StockError::RateLimited
+        ));
+        assert!(matches!(
+            StockError::from_status(503),
+            StockError::Http(503)
+        ));
+    }
+
+    #[test]
+    fn transport_errors_keep_their_kind() {
+        let timeout = StockError::from(TransportError::Timeout(Duration::from_secs(15)));
+        assert!(matches!(timeout, StockError::Timeout(_)));
+        assert_eq!(timeout.to_string(), "timed out after 15s");
+        let network = StockError::from(TransportError::Network("refused".to_string()));
+        assert_eq!(network.to_string(), "network error: refused");
+    }
+
+    #[test]
+    fn html_responses_are_not_decoded_as_json() {
+        let page = |status| Response {
+            url: String::new(),
+            status,
+            headers: vec![("Content-Type".to_string(), "text/html".to_string())],
+            body: b"<html><body><h1>Too many requests</h1></body></html>".to_vec(),
+        };
+        assert!(matches!(
+            expect_json("Yahoo", &page(429)),
+            Err(StockError::RateLimited)
+        ));
+        match expect_json("Yahoo", &page(503)) {
+            Err(StockError::Provider(msg)) => {
+                assert!(msg.contains("status 503"), "{}", msg);
+                assert!(msg.contains("Too many requests"), "{}", msg);
+            }
+            other => panic!("expected a provider error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn provider_selects_the_configured_source() {
+        let transport = crate::http::StubTransport::default();
+        let yahoo = provider(ProviderKind::Yahoo, &transport, None, HashSet::new()).unwrap();
+        assert_eq!(yahoo.source("AAPL"), "yahoo");
+        let alpha = provider(
+            ProviderKind::AlphaVantage,
+            &transport,
+            Some("demo"),
+            HashSet::new(),
+        )
+        .unwrap();
+        assert_eq!(alpha.source("AAPL"), "alphavantage");
+
+        let routed = provider(
+            ProviderKind::Yahoo,
+            &transport,
+            None,
+            HashSet::from(["BTC".to_string()]),
+        )
+        .unwrap();
+        assert_eq!(routed.source("btc"), "coingecko");
+        assert_eq!(routed.source("AAPL"), "yahoo");
+    }
+
+    #[test]
+    fn alpha_vantage_needs_an_api_key() {
+        if std::env::var_os(alphavantage::API_KEY_VAR).is_some() {
+            return;
+        }
+        let transport = crate::http::StubTransport::default();
+        for key in [None, Some("")] {
+            assert!(matches!(
+                provider(ProviderKind::AlphaVantage, &transport, key, HashSet::new()),
+                Err(StockError::MissingApiKey)
+            ));
+        }
+    }
 
-    let url = format!(
-        "https://query1.finance.yahoo.com/v8/finance/chart/{}?range={}&interval=1d",
-        ticker.to_uppercase(),
-        range
-    );
-
-    let client = reqwest::Client::new();
-    let response = client
-        .get(&url)
-        .header("User-Agent", "Mozilla/5.0")
-        .send()
-        .await?;
-
-    let data: YahooResponse = response.json().await?;
-
-    if let Some(error) = data.chart.error {
-        return Err(format!("Yahoo Finance error: {}", error.description).into());
-    }
-
-    let result = data
-        .chart
-        .result
-        .and_then(|r| r.into_iter().next())
-        .ok_or("No data returned for ticker")?;
-
-    let timestamps = result.timestamp.unwrap_or_default();
-    let closes = result
-        .indicators
-        .quote
-        .first()
-        .and_then(|q| q.close.as_ref())
-        .cloned()
-        .unwrap_or_default();
-
-    let prices: Vec<DailyPrice> = timestamps
-        .into_iter()
-        .zip(closes.into_iter())
-        .filter_map(|(ts, close)| {
-            close.map(|c| {
-                let date = chrono::DateTime::from_timestamp(ts, 0)
-                    .map(|dt| dt.format("%Y-%m-%d").to_string())
-                    .unwrap_or_else(|| "Unknown".to_string());
-                DailyPrice { date, close: c }
+    #[test]
+    fn moves_at_or_beyond_the_threshold_alert_either_way() {
+        let quote = |change_percent| StockQuote {
+            ticker: "AAPL".to_string(),
+            price: 100.0,
+            change: change_percent,
+            change_percent,
+            date: "2026-10-16".to_string(),
+        };
+        assert!(quote(5.0).moved_at_least(5.0));
+        assert!(quote(-7.5).moved_at_least(5.0));
+        assert!(!quote(4.99).moved_at_least(5.0));
+        assert!(!quote(-4.99).moved_at_least(5.0));
+        assert!(quote(0.0).moved_at_least(0.0));
+    }
+
+    #[test]
+    fn validate_ticker_accepts_symbols_and_uppercases() {
+        for (typed, expected) in [
+            ("AAPL", "AAPL"),
+            ("brk.b", "BRK.B"),
+            ("RDS-A", "RDS-A"),
+            (" $tsla ", "TSLA"),
+            ("^GSPC", "^GSPC"),
+            ("EURUSD=X", "EURUSD=X"),
+        ] {
+            assert_eq!(validate_ticker(typed).as_deref(), Ok(expected));
+        }
+    }
+
+    #[test]
+    fn validate_ticker_rejects_empty_and_invalid_symbols() {
+        assert_eq!(validate_ticker(""), Err(TickerError::Empty));
+        assert_eq!(validate_ticker(" $ "), Err(TickerError::Empty));
+        assert_eq!(validate_ticker("^"), Err(TickerError::Empty));
+        assert_eq!(
+            validate_ticker("hello world"),
+            Err(TickerError::InvalidChar(' '))
+        );
+        assert_eq!(validate_ticker("12 34"), Err(TickerError::InvalidChar(' ')));
+        assert_eq!(validate_ticker("AA/PL"), Err(TickerError::InvalidChar('/')));
+    }
+
+    #[tokio::test]
+    async fn crypto_tickers_bypass_the_stock_provider() {
+        let coingecko = "https://api.coingecko.com/api/v3/simple/price?ids=bitcoin&vs_currencies=usd&include_24hr_change=true";
+        let transport = crate::http::StubTransport::default().respond(
+            coingecko,
+            200,
+            r#"{"bitcoin": {"usd": 66000.0, "usd_24h_change": 1.5}}"#,
+        );
+        let stocks = provider(
+            ProviderKind::Yahoo,
+            &transport,
+            None,
+            HashSet::from(["BTC".to_string()]),
+        )
+        .unwrap();
+        let quote = stocks.fetch_quote("btc").await.unwrap();
+        assert_eq!((quote.ticker.as_str(), quote.price), ("BTC", 66000.0));
+        // Only CoinGecko is stubbed, so an equity reaches Yahoo and fails.
+        assert!(matches!(
+            stocks.fetch_quote("AAPL").await,
+            Err(StockError::Network(_))
+        ));
+    }
+
+    fn closes(values: &[f64]) -> Vec<DailyPrice> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, &close)| DailyPrice {
+                date: format!("2026-10-{:02}", i + 1),
+                open: None,
+                high: None,
+                low: None,
+                close,
+                volume: None,
             })
-        })
-        .collect();
+            .collect()
+    }
 
-    Ok(PriceHistory {
-        ticker: ticker.to_uppercase(),
-        prices,
-    })
+    #[test]
+    fn sma_averages_the_last_closes_and_shrinks_the_window() {
+        let prices = closes(&[1.0, 2.0, 3.0, 4.0, 5.0]);
+        assert_eq!(sma(&prices, 2), Some(4.5));
+        assert_eq!(sma(&prices, 30), Some(3.0));
+        assert_eq!(sma(&prices, 0), None);
+        assert_eq!(sma(&[], 7), None);
+    }
+
+    #[test]
+    fn percent_change_spans_the_whole_series() {
+        assert_eq!(percent_change(&closes(&[50.0, 70.0, 60.0])), Some(20.0));
+        assert_eq!(percent_change(&closes(&[0.0, 1.0])), None);
+        assert_eq!(percent_change(&[]), None);
+    }
 }
@@ -0,0 +1,233 @@
+use super::{DailyPrice, PriceHistory, StockError, StockProvider, StockQuote};
+use crate::http::Transport;
+use async_trait::async_trait;
+use chrono::{Duration, NaiveDate, Utc};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// Environment variable holding the Alpha Vantage API key.
+pub const API_KEY_VAR: &str = "ALPHAVANTAGE_API_KEY";
+
+const BASE_URL: &str = "https://www.alphavantage.co/query";
+
+/// Alpha Vantage's quote and daily time series endpoints.
+pub struct AlphaVantageProvider<'a> {
+    transport: &'a dyn Transport,
+    api_key: String,
+}
+
+impl<'a> AlphaVantageProvider<'a> {
+    pub fn new(transport: &'a dyn Transport, api_key: String) -> Self {
+        AlphaVantageProvider { transport, api_key }
+    }
+
+    /// Requests `function` for `ticker` and decodes the JSON body. Alpha
+    /// Vantage answers errors and rate limiting with HTTP 200 and a message
+    /// field instead of the requested data.
+    async fn query<T: for<'de> Deserialize<'de>>(
+        &self,
+        function: &str,
+        ticker: &str,
+    ) -> Result<T, StockError> {
+        let url = format!(
+            "{}?function={}&symbol={}&apikey={}",
+            BASE_URL,
+            function,
+            ticker.to_uppercase(),
+            self.api_key
+        );
+        let response = self.transport.get(&url, &[]).await?;
+        if !response.is_success() {
+            return Err(StockError::from_status(response.status));
+        }
+        super::expect_json("Alpha Vantage", &response)?;
+
+        let messages: Messages = serde_json::from_slice(&response.body)
+            .map_err(|e| StockError::Decode(e.to_string()))?;
+        if let Some(msg) = messages.error {
+            return Err(if msg.starts_with("Invalid API call") {
+                StockError::NotFound
+            } else {
+                StockError::Provider(format!("Alpha Vantage error: {}", msg))
+            });
+        }
+        if messages.note.is_some() || messages.information.is_some() {
+            return Err(StockError::RateLimited);
+        }
+
+        serde_json::from_slice(&response.body).map_err(|e| StockError::Decode(e.to_string()))
+    }
+}
+
+#[derive(Deserialize)]
+struct Messages {
+    #[serde(rename = "Error Message")]
+    error: Option<String>,
+    /// Sent when the per-minute request limit is hit
+    #[serde(rename = "Note")]
+    note: Option<String>,
+    /// Sent when the daily request limit is hit
+    #[serde(rename = "Information")]
+    information: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct QuoteResponse {
+    #[serde(rename = "Global Quote")]
+    quote: HashMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct DailyResponse {
+    #[serde(rename = "Time Series (Daily)")]
+    series: BTreeMap<String, HashMap<String, String>>,
+}
+
+/// Parses the numeric field named `name` (e.g. "05. price"), ignoring the
+/// numbered prefix Alpha Vantage puts on every key.
+fn field(values: &HashMap<String, String>, name: &str) -> Option<f64> {
+    values
+        .iter()
+        .find(|(key, _)| key.split_once(". ").map_or(key.as_str(), |(_, k)| k) == name)
+        .and_then(|(_, value)| value.trim_end_matches('%').parse().ok())
+}
+
+#[async_trait]
+impl StockProvider for AlphaVantageProvider<'_> {
+    async fn fetch_quote(&self, ticker: &str) -> Result<StockQuote, StockError> {
+        let response: QuoteResponse = self.query("GLOBAL_QUOTE", ticker).await?;
+        let quote = response.quote;
+        // Unknown symbols come back as an empty quote object.
+        let price = field(&quote, "price").ok_or(StockError::NotFound)?;
+
+        Ok(StockQuote {
+            ticker: ticker.to_uppercase(),
+            price,
+            change: field(&quote, "change").unwrap_or(0.0),
+            change_percent: field(&quote, "change percent").unwrap_or(0.0),
+            date: quote
+                .iter()
+                .find(|(key, _)| key.ends_with("latest trading day"))
+                .map(|(_, date)| date.clone())
+                .unwrap_or_else(|| chrono::Local::now().format("%Y-%m-%d").to_string()),
+        })
+    }
+
+    async fn fetch_history(&self, ticker: &str, days: u32) -> Result<PriceHistory, StockError> {
+        let response: DailyResponse = self.query("TIME_SERIES_DAILY", ticker).await?;
+        let start = Utc::now().date_naive() - Duration::days(days as i64);
+
+        // BTreeMap keys are YYYY-MM-DD dates, so iteration is oldest first.
+        let prices = response
+            .series
+            .iter()
+            .filter(|(date, _)| {
+                NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok_and(|d| d >= start)
+            })
+            .filter_map(|(date, values)| {
+                Some(DailyPrice {
+                    date: date.clone(),
+                    open: field(values, "open"),
+                    high: field(values, "high"),
+                    low: field(values, "low"),
+                    close: field(values, "close")?,
+                    volume: field(values, "volume").map(|v| v as u64),
+                })
+            })
+            .collect();
+
+        Ok(PriceHistory {
+            ticker: ticker.to_uppercase(),
+            prices,
+        })
+    }
+
+    fn source(&self, _ticker: &str) -> &str {
+        "alphavantage"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::StubTransport;
+
+    fn url(function: &str) -> String {
+        format!("{}?function={}&symbol=IBM&apikey=demo", BASE_URL, function)
+    }
+
+    fn provider(transport: &StubTransport) -> AlphaVantageProvider<'_> {
+        AlphaVantageProvider::new(transport, "demo".to_string())
+    }
+
+    #[tokio::test]
+    async fn parses_a_global_quote() {
+        let body = r#"{"Global Quote": {"01. symbol": "IBM", "05. price": "231.4500",
+            "07. latest trading day": "2026-10-15", "09. change": "-2.1500",
+            "10. change percent": "-0.9204%"}}"#;
+        let transport = StubTransport::default().respond(&url("GLOBAL_QUOTE"), 200, body);
+        let quote = provider(&transport).fetch_quote("ibm").await.unwrap();
+        assert_eq!(quote.ticker, "IBM");
+        assert_eq!(quote.price, 231.45);
+        assert_eq!(quote.change, -2.15);
+        assert_eq!(quote.change_percent, -0.9204);
+        assert_eq!(quote.date, "2026-10-15");
+    }
+
+    #[tokio::test]
+    async fn parses_the_daily_series_oldest_first() {
+        let today = Utc::now().date_naive();
+        let day = |ago: i64| (today - Duration::days(ago)).format("%Y-%m-%d").to_string();
+        let body = format!(
+            r#"{{"Time Series (Daily)": {{
+                "{}": {{"1. open": "101", "2. high": "103", "3. low": "100", "4. close": "102", "5. volume": "5000"}},
+                "{}": {{"1. open": "99", "4. close": "100.5"}},
+                "{}": {{"4. close": "90"}}
+            }}}}"#,
+            day(1),
+            day(3),
+            day(60)
+        );
+        let transport = StubTransport::default().respond(&url("TIME_SERIES_DAILY"), 200, body);
+        let history = provider(&transport).fetch_history("IBM", 30).await.unwrap();
+        let prices: Vec<_> = history
+            .prices
+            .iter()
+            .map(|p| (p.date.clone(), p.close, p.high, p.volume))
+            .collect();
+        assert_eq!(
+            prices,
+            [
+                (day(3), 100.5, None, None),
+                (day(1), 102.0, Some(103.0), Some(5000))
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn messages_map_to_errors() {
+        let cases = [
+            (
+                r#"{"Error Message": "Invalid API call. Please retry."}"#,
+                "not found",
+            ),
+            (
+                r#"{"Note": "Thank you for using Alpha Vantage!"}"#,
+                "rate limited",
+            ),
+            (r#"{"Information": "Daily limit reached."}"#, "rate limited"),
+            (r#"{"Error Message": "Something else"}"#, "provider"),
+            (r#"{"Global Quote": {}}"#, "not found"),
+        ];
+        for (body, expected) in cases {
+            let transport = StubTransport::default().respond(&url("GLOBAL_QUOTE"), 200, body);
+            let kind = match provider(&transport).fetch_quote("IBM").await {
+                Err(StockError::NotFound) => "not found",
+                Err(StockError::RateLimited) => "rate limited",
+                Err(StockError::Provider(_)) => "provider",
+                other => panic!("unexpected result for {}: {:?}", body, other),
+            };
+            assert_eq!(kind, expected, "{}", body);
+        }
+    }
+}
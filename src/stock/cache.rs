@@ -0,0 +1,310 @@
+use super::{DailyPrice, PriceHistory, StockError, StockProvider, StockQuote};
+use crate::storage;
+use async_trait::async_trait;
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// How long cached stock data stays fresh.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StockCacheTtl {
+    pub quote_secs: u64,
+    pub history_secs: u64,
+}
+
+impl Default for StockCacheTtl {
+    fn default() -> Self {
+        StockCacheTtl {
+            quote_secs: 5 * 60,
+            history_secs: 60 * 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Cached<T> {
+    fetched_at: DateTime<Utc>,
+    value: T,
+}
+
+impl<T: Clone> Cached<T> {
+    fn fresh(&self, ttl_secs: u64, now: DateTime<Utc>) -> Option<T> {
+        let ttl = TimeDelta::seconds(ttl_secs.try_into().unwrap_or(i64::MAX));
+        (now - self.fetched_at < ttl).then(|| self.value.clone())
+    }
+}
+
+/// Quotes and price histories persisted in `stock_cache.json` next to the
+/// config, keyed by provider and ticker (and range for histories).
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StockCache {
+    #[serde(default)]
+    quotes: HashMap<String, Cached<StockQuote>>,
+    #[serde(default)]
+    histories: HashMap<String, Cached<Vec<DailyPrice>>>,
+}
+
+impl StockCache {
+    pub fn load(dir: &Path) -> io::Result<Self> {
+        let path = cache_path(dir);
+        if !path.exists() {
+            return Ok(StockCache::default());
+        }
+        let content = fs::read_to_string(&path)?;
+        serde_json::from_str(&content).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Writes the cache to a temporary file and renames it into place.
+    pub fn save(&self, dir: &Path) -> io::Result<()> {
+        let path = cache_path(dir);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string(self)?;
+        storage::write_atomic(&path, content.as_bytes())
+    }
+}
+
+fn cache_path(dir: &Path) -> PathBuf {
+    dir.join("stock_cache.json")
+}
+
+/// Serves quotes and histories from a `StockCache` while they are fresh,
+/// fetching from the wrapped provider otherwise. What is fetched is saved
+/// once, when the provider is dropped.
+pub struct CachingProvider<'a> {
+    inner: Box<dyn StockProvider + 'a>,
+    ttl: StockCacheTtl,
+    cache: Mutex<StockCache>,
+    /// Whether `cache` has entries that aren't saved yet
+    dirty: AtomicBool,
+    /// Where the cache is saved
+    dir: PathBuf,
+    /// Ignore cached data, but still store what is fetched
+    refresh: bool,
+}
+
+impl<'a> CachingProvider<'a> {
+    pub fn new(
+        inner: Box<dyn StockProvider + 'a>,
+        ttl: StockCacheTtl,
+        cache: StockCache,
+        dir: PathBuf,
+        refresh: bool,
+    ) -> Self {
+        CachingProvider {
+            inner,
+            ttl,
+            cache: Mutex::new(cache),
+            dirty: AtomicBool::new(false),
+            dir,
+            refresh,
+        }
+    }
+
+    /// Cache key for `ticker`, naming the service that answers for it so a
+    /// ticker priced by different providers doesn't share entries.
+    fn key(&self, ticker: &str) -> String {
+        format!("{}:{}", self.inner.source(ticker), ticker.to_uppercase())
+    }
+
+    /// Saves the cache if anything was fetched since it was last saved.
+    pub fn flush(&self) {
+        if !self.dirty.swap(false, Ordering::Relaxed) {
+            return;
+        }
+        let cache = self.cache.lock().unwrap();
+        if let Err(e) = cache.save(&self.dir) {
+            eprintln!("Warning: could not save stock cache: {}", e);
+        }
+    }
+}
+
+impl Drop for CachingProvider<'_> {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+#[async_trait]
+impl StockProvider for CachingProvider<'_> {
+    async fn fetch_quote(&self, ticker: &str) -> Result<StockQuote, StockError> {
+        let key = self.key(ticker);
+        if !self.refresh {
+            let cache = self.cache.lock().unwrap();
+            let fresh = cache
+                .quotes
+                .get(&key)
+                .and_then(|c| c.fresh(self.ttl.quote_secs, Utc::now()));
+            if let Some(quote) = fresh {
+                return Ok(quote);
+            }
+        }
+
+        let quote = self.inner.fetch_quote(ticker).await?;
+        self.cache.lock().unwrap().quotes.insert(
+            key,
+            Cached {
+                fetched_at: Utc::now(),
+                value: quote.clone(),
+            },
+        );
+        self.dirty.store(true, Ordering::Relaxed);
+        Ok(quote)
+    }
+
+    async fn fetch_history(&self, ticker: &str, days: u32) -> Result<PriceHistory, StockError> {
+        let key = format!("{}:{}d", self.key(ticker), days);
+        if !self.refresh {
+            let cache = self.cache.lock().unwrap();
+            let fresh = cache
+                .histories
+                .get(&key)
+                .and_then(|c| c.fresh(self.ttl.history_secs, Utc::now()));
+            if let Some(prices) = fresh {
+                return Ok(PriceHistory {
+                    ticker: ticker.to_uppercase(),
+                    prices,
+                });
+            }
+        }
+
+        let history = self.inner.fetch_history(ticker, days).await?;
+        self.cache.lock().unwrap().histories.insert(
+            key,
+            Cached {
+                fetched_at: Utc::now(),
+                value: history.prices.clone(),
+            },
+        );
+        self.dirty.store(true, Ordering::Relaxed);
+        Ok(history)
+    }
+
+    fn source(&self, ticker: &str) -> &str {
+        self.inner.source(ticker)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stock::AssetRouter;
+    use std::collections::HashSet;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+
+    /// Quotes every ticker at a fixed price, counting requests.
+    struct FixedProvider {
+        source: &'static str,
+        price: f64,
+        calls: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl StockProvider for FixedProvider {
+        async fn fetch_quote(&self, ticker: &str) -> Result<StockQuote, StockError> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            Ok(StockQuote {
+                ticker: ticker.to_uppercase(),
+                price: self.price,
+                change: 0.0,
+                change_percent: 0.0,
+                date: "2026-10-16".to_string(),
+            })
+        }
+
+        async fn fetch_history(
+            &self,
+            ticker: &str,
+            _days: u32,
+        ) -> Result<PriceHistory, StockError> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            Ok(PriceHistory {
+                ticker: ticker.to_uppercase(),
+                prices: Vec::new(),
+            })
+        }
+
+        fn source(&self, _ticker: &str) -> &str {
+            self.source
+        }
+    }
+
+    fn fixed(source: &'static str, price: f64, calls: &Arc<AtomicUsize>) -> Box<FixedProvider> {
+        Box::new(FixedProvider {
+            source,
+            price,
+            calls: calls.clone(),
+        })
+    }
+
+    #[tokio::test]
+    async fn fresh_entries_are_served_from_the_cache() {
+        let dir = crate::test_dir("stock-cache-fresh");
+        let calls = Arc::new(AtomicUsize::new(0));
+        let provider = CachingProvider::new(
+            fixed("yahoo", 10.0, &calls),
+            StockCacheTtl::default(),
+            StockCache::default(),
+            dir.clone(),
+            false,
+        );
+        provider.fetch_quote("aapl").await.unwrap();
+        provider.fetch_quote("AAPL").await.unwrap();
+        provider.fetch_history("AAPL", 30).await.unwrap();
+        provider.fetch_history("AAPL", 30).await.unwrap();
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn saves_once_when_dropped() {
+        let dir = crate::test_dir("stock-cache-drop");
+        let calls = Arc::new(AtomicUsize::new(0));
+        let provider = CachingProvider::new(
+            fixed("yahoo", 10.0, &calls),
+            StockCacheTtl::default(),
+            StockCache::default(),
+            dir.clone(),
+            false,
+        );
+        provider.fetch_quote("AAPL").await.unwrap();
+        assert!(!cache_path(&dir).exists());
+        drop(provider);
+
+        let cache = StockCache::load(&dir).unwrap();
+        assert!(cache.quotes.contains_key("yahoo:AAPL"));
+    }
+
+    #[tokio::test]
+    async fn routed_tickers_are_keyed_by_the_provider_used() {
+        let dir = crate::test_dir("stock-cache-routed");
+        let calls = Arc::new(AtomicUsize::new(0));
+        let router = AssetRouter::new(
+            fixed("yahoo", 10.0, &calls),
+            fixed("coingecko", 60000.0, &calls),
+            HashSet::from(["BTC".to_string()]),
+        );
+        let provider = CachingProvider::new(
+            Box::new(router),
+            StockCacheTtl::default(),
+            StockCache::default(),
+            dir.clone(),
+            false,
+        );
+        assert_eq!(provider.fetch_quote("btc").await.unwrap().price, 60000.0);
+        provider.fetch_quote("AAPL").await.unwrap();
+        drop(provider);
+
+        let cache = StockCache::load(&dir).unwrap();
+        let mut keys: Vec<_> = cache.quotes.keys().cloned().collect();
+        keys.sort();
+        assert_eq!(keys, ["coingecko:BTC", "yahoo:AAPL"]);
+    }
+}
@@ -0,0 +1,195 @@
+use super::{timestamp_to_date, DailyPrice, PriceHistory, StockError, StockProvider, StockQuote};
+use crate::http::Transport;
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+const BASE_URL: &str = "https://api.coingecko.com/api/v3";
+
+/// CoinGecko identifiers of common coins by ticker. Other tickers are tried
+/// as identifiers themselves, so `stock quote dogecoin --crypto` also works.
+const COIN_IDS: &[(&str, &str)] = &[
+    ("BTC", "bitcoin"),
+    ("ETH", "ethereum"),
+    ("USDT", "tether"),
+    ("BNB", "binancecoin"),
+    ("SOL", "solana"),
+    ("XRP", "ripple"),
+    ("USDC", "usd-coin"),
+    ("ADA", "cardano"),
+    ("DOGE", "dogecoin"),
+    ("TRX", "tron"),
+    ("DOT", "polkadot"),
+    ("LTC", "litecoin"),
+    ("AVAX", "avalanche-2"),
+    ("LINK", "chainlink"),
+    ("XLM", "stellar"),
+];
+
+fn coin_id(ticker: &str) -> String {
+    let ticker = ticker.to_uppercase();
+    COIN_IDS
+        .iter()
+        .find(|(symbol, _)| *symbol == ticker)
+        .map(|(_, id)| id.to_string())
+        .unwrap_or_else(|| ticker.to_lowercase())
+}
+
+/// CoinGecko's public price API, quoted in US dollars.
+pub struct CoinGeckoProvider<'a> {
+    transport: &'a dyn Transport,
+}
+
+impl<'a> CoinGeckoProvider<'a> {
+    pub fn new(transport: &'a dyn Transport) -> Self {
+        CoinGeckoProvider { transport }
+    }
+
+    async fn get<T: for<'de> Deserialize<'de>>(&self, url: &str) -> Result<T, StockError> {
+        let response = self.transport.get(url, &[]).await?;
+        if !response.is_success() {
+            return Err(StockError::from_status(response.status));
+        }
+        super::expect_json("CoinGecko", &response)?;
+        serde_json::from_slice(&response.body).map_err(|e| StockError::Decode(e.to_string()))
+    }
+}
+
+#[derive(Deserialize)]
+struct SimplePrice {
+    usd: Option<f64>,
+    usd_24h_change: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct MarketChart {
+    /// `[unix millis, price]` pairs, oldest first
+    prices: Vec<(i64, f64)>,
+}
+
+#[async_trait]
+impl StockProvider for CoinGeckoProvider<'_> {
+    async fn fetch_quote(&self, ticker: &str) -> Result<StockQuote, StockError> {
+        let id = coin_id(ticker);
+        let url = format!(
+            "{}/simple/price?ids={}&vs_currencies=usd&include_24hr_change=true",
+            BASE_URL, id
+        );
+        // Unknown ids are left out of the response object.
+        let mut prices: HashMap<String, SimplePrice> = self.get(&url).await?;
+        let coin = prices.remove(&id).ok_or(StockError::NotFound)?;
+        let price = coin.usd.ok_or(StockError::NotFound)?;
+
+        let change_percent = coin.usd_24h_change.unwrap_or(0.0);
+        let previous = price / (1.0 + change_percent / 100.0);
+
+        Ok(StockQuote {
+            ticker: ticker.to_uppercase(),
+            price,
+            change: price - previous,
+            change_percent,
+            date: chrono::Local::now().format("%Y-%m-%d").to_string(),
+        })
+    }
+
+    async fn fetch_history(&self, ticker: &str, days: u32) -> Result<PriceHistory, StockError> {
+        let url = format!(
+            "{}/coins/{}/market_chart?vs_currency=usd&days={}&interval=daily",
+            BASE_URL,
+            coin_id(ticker),
+            days
+        );
+        let chart: MarketChart = self.get(&url).await?;
+
+        // The last point is the current price, which can share a date with
+        // the previous day's close; keep the latest price for each date.
+        let mut prices: Vec<DailyPrice> = Vec::new();
+        for (ts, close) in chart.prices {
+            let Some(date) = timestamp_to_date(ts) else {
+                continue;
+            };
+            let price = DailyPrice {
+                date,
+                open: None,
+                high: None,
+                low: None,
+                close,
+                volume: None,
+            };
+            match prices.last_mut() {
+                Some(last) if last.date == price.date => *last = price,
+                _ => prices.push(price),
+            }
+        }
+
+        Ok(PriceHistory {
+            ticker: ticker.to_uppercase(),
+            prices,
+        })
+    }
+
+    fn source(&self, _ticker: &str) -> &str {
+        "coingecko"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::StubTransport;
+
+    const PRICE_URL: &str = "https://api.coingecko.com/api/v3/simple/price?ids=bitcoin&vs_currencies=usd&include_24hr_change=true";
+
+    #[tokio::test]
+    async fn quote_reads_price_and_daily_change() {
+        let body = r#"{"bitcoin": {"usd": 66000.0, "usd_24h_change": 10.0}}"#;
+        let transport = StubTransport::default().respond(PRICE_URL, 200, body);
+        let quote = CoinGeckoProvider::new(&transport)
+            .fetch_quote("btc")
+            .await
+            .unwrap();
+        assert_eq!(quote.ticker, "BTC");
+        assert_eq!(quote.price, 66000.0);
+        assert_eq!(quote.change_percent, 10.0);
+        assert!((quote.change - 6000.0).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn unknown_coin_is_not_found() {
+        let url = "https://api.coingecko.com/api/v3/simple/price?ids=notacoin&vs_currencies=usd&include_24hr_change=true";
+        let transport = StubTransport::default().respond(url, 200, "{}");
+        assert!(matches!(
+            CoinGeckoProvider::new(&transport)
+                .fetch_quote("notacoin")
+                .await,
+            Err(StockError::NotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn history_keeps_the_latest_price_per_day() {
+        let url = "https://api.coingecko.com/api/v3/coins/ethereum/market_chart?vs_currency=usd&days=2&interval=daily";
+        let body = r#"{"prices": [[1791763200000, 2400.5], [1791849600000, 2500.0],
+            [1791900000000, 2550.25]]}"#;
+        let transport = StubTransport::default().respond(url, 200, body);
+        let history = CoinGeckoProvider::new(&transport)
+            .fetch_history("ETH", 2)
+            .await
+            .unwrap();
+        let closes: Vec<_> = history
+            .prices
+            .iter()
+            .map(|p| (p.date.as_str(), p.close))
+            .collect();
+        assert_eq!(closes, [("2026-10-12", 2400.5), ("2026-10-13", 2550.25)]);
+    }
+
+    #[tokio::test]
+    async fn rate_limiting_is_reported() {
+        let transport = StubTransport::default().respond(PRICE_URL, 429, "Throttled");
+        assert!(matches!(
+            CoinGeckoProvider::new(&transport).fetch_quote("BTC").await,
+            Err(StockError::RateLimited)
+        ));
+    }
+}
@@ -0,0 +1,299 @@
+use super::{timestamp_to_date, DailyPrice, PriceHistory, StockError, StockProvider, StockQuote};
+use crate::http::Transport;
+use async_trait::async_trait;
+use serde::Deserialize;
+
+/// Yahoo Finance's undocumented chart endpoint.
+pub struct YahooProvider<'a> {
+    transport: &'a dyn Transport,
+}
+
+impl<'a> YahooProvider<'a> {
+    pub fn new(transport: &'a dyn Transport) -> Self {
+        YahooProvider { transport }
+    }
+}
+
+#[derive(Deserialize)]
+struct YahooResponse {
+    chart: ChartResult,
+}
+
+#[derive(Deserialize)]
+struct ChartResult {
+    result: Option<Vec<ChartData>>,
+    error: Option<YahooError>,
+}
+
+#[derive(Deserialize)]
+struct YahooError {
+    code: Option<String>,
+    description: String,
+}
+
+#[derive(Deserialize)]
+struct ChartData {
+    meta: MetaData,
+    timestamp: Option<Vec<i64>>,
+    indicators: Indicators,
+}
+
+#[derive(Deserialize)]
+struct MetaData {
+    #[serde(rename = "regularMarketPrice")]
+    regular_market_price: Option<f64>,
+    #[serde(rename = "previousClose")]
+    previous_close: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct Indicators {
+    quote: Vec<QuoteData>,
+}
+
+#[derive(Deserialize)]
+struct QuoteData {
+    open: Option<Vec<Option<f64>>>,
+    high: Option<Vec<Option<f64>>>,
+    low: Option<Vec<Option<f64>>>,
+    close: Option<Vec<Option<f64>>>,
+    volume: Option<Vec<Option<u64>>>,
+}
+
+/// The `i`th entry of an indicator series, if present and not null.
+fn nth<T: Copy>(series: &Option<Vec<Option<T>>>, i: usize) -> Option<T> {
+    series.as_ref()?.get(i).copied().flatten()
+}
+
+/// Requests a Yahoo chart URL and returns its first result.
+async fn fetch_chart(transport: &dyn Transport, url: &str) -> Result<ChartData, StockError> {
+    let response = transport.get(url, &[]).await?;
+    super::expect_json("Yahoo", &response)?;
+
+    // Yahoo reports unknown tickers as a JSON error body with a 404 status,
+    // so only fall back to the status when the body isn't chart JSON.
+    let data: YahooResponse = match serde_json::from_slice(&response.body) {
+        Ok(data) => data,
+        Err(_) if !response.is_success() => return Err(StockError::from_status(response.status)),
+        Err(e) => return Err(StockError::Decode(e.to_string())),
+    };
+
+    if let Some(error) = data.chart.error {
+        return Err(match error.code.as_deref() {
+            Some("Not Found") => StockError::NotFound,
+            _ => StockError::Provider(format!("Yahoo Finance error: {}", error.description)),
+        });
+    }
+
+    data.chart
+        .result
+        .and_then(|r| r.into_iter().next())
+        .ok_or(StockError::NotFound)
+}
+
+#[async_trait]
+impl StockProvider for YahooProvider<'_> {
+    async fn fetch_quote(&self, ticker: &str) -> Result<StockQuote, StockError> {
+        let url = format!(
+            "https://query1.finance.yahoo.com/v8/finance/chart/{}?range=1d&interval=1d",
+            ticker.to_uppercase()
+        );
+
+        let result = fetch_chart(self.transport, &url).await?;
+
+        let price = result.meta.regular_market_price.unwrap_or(0.0);
+        let previous_close = result.meta.previous_close.unwrap_or(price);
+        let change = price - previous_close;
+        let change_percent = if previous_close > 0.0 {
+            (change / previous_close) * 100.0
+        } else {
+            0.0
+        };
+
+        let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+
+        Ok(StockQuote {
+            ticker: ticker.to_uppercase(),
+            price,
+            change,
+            change_percent,
+            date,
+        })
+    }
+
+    async fn fetch_history(&self, ticker: &str, days: u32) -> Result<PriceHistory, StockError> {
+        let range = if days <= 5 {
+            "5d"
+        } else if days <= 30 {
+            "1mo"
+        } else if days <= 90 {
+            "3mo"
+        } else {
+            "6mo"
+        };
+
+        let url = format!(
+            "https://query1.finance.yahoo.com/v8/finance/chart/{}?range={}&interval=1d",
+            ticker.to_uppercase(),
+            range
+        );
+
+        let result = fetch_chart(self.transport, &url).await?;
+
+        let timestamps = result.timestamp.unwrap_or_default();
+        let Some(quote) = result.indicators.quote.first() else {
+            return Ok(PriceHistory {
+                ticker: ticker.to_uppercase(),
+                prices: Vec::new(),
+            });
+        };
+
+        let prices: Vec<DailyPrice> = timestamps
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, ts)| {
+                let close = nth(&quote.close, i)?;
+                match timestamp_to_date(ts) {
+                    Some(date) => Some(DailyPrice {
+                        date,
+                        open: nth(&quote.open, i),
+                        high: nth(&quote.high, i),
+                        low: nth(&quote.low, i),
+                        close,
+                        volume: nth(&quote.volume, i),
+                    }),
+                    None => {
+                        eprintln!("Warning: skipping price with invalid timestamp {}", ts);
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        Ok(PriceHistory {
+            ticker: ticker.to_uppercase(),
+            prices,
+        })
+    }
+
+    fn source(&self, _ticker: &str) -> &str {
+        "yahoo"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::StubTransport;
+
+    const QUOTE_URL: &str =
+        "https://query1.finance.yahoo.com/v8/finance/chart/AAPL?range=1d&interval=1d";
+
+    async fn quote(transport: &StubTransport) -> Result<StockQuote, StockError> {
+        YahooProvider::new(transport).fetch_quote("aapl").await
+    }
+
+    #[tokio::test]
+    async fn history_reads_second_and_millisecond_timestamps() {
+        let url = "https://query1.finance.yahoo.com/v8/finance/chart/AAPL?range=1mo&interval=1d";
+        let body = r#"{"chart": {"result": [{"meta": {"regularMarketPrice": 104},
+            "timestamp": [1791813600, 1791900000000, 1791986400, 99999999999999999],
+            "indicators": {"quote": [{"close": [100, 101, null, 105],
+                "open": [99, 100.5, 101, 104], "volume": [1000, null, 5, 6]}]}}],
+            "error": null}}"#;
+        let transport = StubTransport::default().respond(url, 200, body);
+        let history = YahooProvider::new(&transport)
+            .fetch_history("aapl", 30)
+            .await
+            .unwrap();
+        assert_eq!(history.ticker, "AAPL");
+        let days: Vec<_> = history
+            .prices
+            .iter()
+            .map(|p| (p.date.as_str(), p.close, p.open, p.volume))
+            .collect();
+        assert_eq!(
+            days,
+            [
+                ("2026-10-12", 100.0, Some(99.0), Some(1000)),
+                ("2026-10-13", 101.0, Some(100.5), None)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn unknown_ticker_is_not_found() {
+        let body = r#"{"chart":{"result":null,"error":{"code":"Not Found","description":"No data found, symbol may be delisted"}}}"#;
+        let transport = StubTransport::default().respond(QUOTE_URL, 404, body);
+        assert!(matches!(quote(&transport).await, Err(StockError::NotFound)));
+    }
+
+    #[tokio::test]
+    async fn html_404_is_not_found() {
+        let transport = StubTransport::default().respond(QUOTE_URL, 404, "<html>Not Found</html>");
+        assert!(matches!(quote(&transport).await, Err(StockError::NotFound)));
+    }
+
+    #[tokio::test]
+    async fn bad_json_is_a_decode_error() {
+        let transport = StubTransport::default().respond(QUOTE_URL, 200, r#"{"chart": [1, 2"#);
+        assert!(matches!(
+            quote(&transport).await,
+            Err(StockError::Decode(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn provider_error_is_reported() {
+        let body = r#"{"chart":{"result":null,"error":{"code":"Bad Request","description":"Invalid range"}}}"#;
+        let transport = StubTransport::default().respond(QUOTE_URL, 400, body);
+        match quote(&transport).await {
+            Err(StockError::Provider(msg)) => assert!(msg.contains("Invalid range")),
+            other => panic!("expected a provider error, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn history_keeps_ohlc_and_volume() {
+        let url = "https://query1.finance.yahoo.com/v8/finance/chart/MSFT?range=5d&interval=1d";
+        let body = r#"{"chart":{"result":[{"meta":{"currency":"USD","symbol":"MSFT",
+            "regularMarketPrice":512.3,"previousClose":509.1},
+            "timestamp":[1791813600,1791900000],
+            "indicators":{"quote":[{"open":[508.1,510.5],"high":[511.9,514.2],
+            "low":[505.0,509.8],"close":[509.1,512.3],"volume":[18233400,21456700]}],
+            "adjclose":[{"adjclose":[509.1,512.3]}]}}],"error":null}}"#;
+        let transport = StubTransport::default().respond(url, 200, body);
+        let history = YahooProvider::new(&transport)
+            .fetch_history("MSFT", 5)
+            .await
+            .unwrap();
+        let last = history.prices.last().unwrap();
+        assert_eq!(history.prices.len(), 2);
+        assert_eq!(last.date, "2026-10-13");
+        assert_eq!(
+            (last.open, last.high, last.low, last.close, last.volume),
+            (Some(510.5), Some(514.2), Some(509.8), 512.3, Some(21456700))
+        );
+    }
+
+    #[tokio::test]
+    async fn non_json_bodies_get_a_friendly_error() {
+        let page = "<html><body><p>Will be right back soon</p></body></html>";
+        let transport = StubTransport::default().respond(QUOTE_URL, 200, page);
+        match quote(&transport).await {
+            Err(StockError::Provider(msg)) => assert_eq!(
+                msg,
+                "Yahoo returned non-JSON (status 200), possibly rate-limited: Will be right back soon"
+            ),
+            other => panic!("expected a provider error, got {:?}", other),
+        }
+
+        let transport = StubTransport::default().respond(QUOTE_URL, 503, "");
+        match quote(&transport).await {
+            Err(StockError::Provider(msg)) => {
+                assert!(msg.ends_with("(status 503), possibly rate-limited: (empty body)"))
+            }
+            other => panic!("expected a provider error, got {:?}", other),
+        }
+    }
+}
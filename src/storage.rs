@@ -1,73 +1,554 @@
-use serde::{Deserialize, Serialize};
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+use crate::analysis::{Lexicon, TickerBoundaries};
+use crate::cache::FeedCache;
+use crate::feed::same_feed_url;
+use crate::history::HistoryLimits;
+use crate::seen::SeenStore;
+use crate::stock::{self, ProviderKind, StockCacheTtl};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::{DateTime, TimeDelta, Utc, Weekday};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
 use std::fs;
-use std::io;
-use std::path::PathBuf;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Investment {
     pub ticker: String,
     pub name: Option<String>,
+    /// International Securities Identification Number, e.g. US0378331005
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub isin: Option<String>,
+    /// CUSIP identifier, e.g. 037833100
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cusip: Option<String>,
+    /// Daily move (in percent, either direction) that `stock alerts` flags
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alert_pct: Option<f64>,
+    #[serde(default, skip_serializing_if = "AssetKind::is_equity")]
+    pub kind: AssetKind,
+}
+
+impl AssetKind {
+    fn is_equity(&self) -> bool {
+        *self == AssetKind::Equity
+    }
+}
+
+impl Investment {
+    pub fn new(ticker: &str) -> Self {
+        Investment {
+            ticker: stock::normalize_ticker(ticker),
+            name: None,
+            isin: None,
+            cusip: None,
+            alert_pct: None,
+            kind: AssetKind::Equity,
+        }
+    }
+
+    /// Copies every optional field that is set on `other` onto this
+    /// investment. Returns true if anything changed.
+    fn merge(&mut self, other: &Investment) -> bool {
+        let mut changed = false;
+        for (field, value) in [
+            (&mut self.name, &other.name),
+            (&mut self.isin, &other.isin),
+            (&mut self.cusip, &other.cusip),
+        ] {
+            if value.is_some() && field != value {
+                *field = value.clone();
+                changed = true;
+            }
+        }
+        if other.alert_pct.is_some() && other.alert_pct != self.alert_pct {
+            self.alert_pct = other.alert_pct;
+            changed = true;
+        }
+        if other.kind != AssetKind::Equity && other.kind != self.kind {
+            self.kind = other.kind;
+            changed = true;
+        }
+        changed
+    }
+}
+
+/// What kind of asset an investment is, which decides where its prices
+/// come from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AssetKind {
+    #[default]
+    Equity,
+    /// Priced through CoinGecko instead of the stock provider
+    Crypto,
+}
+
+/// A subscribed feed and its optional metadata.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Feed {
+    pub url: String,
+    /// Human-friendly display name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Only keep articles published on these days of the week
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weekdays: Option<Vec<Weekday>>,
+    /// Folder the feed is filed under, e.g. "finance"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// Login for feeds behind HTTP basic authentication
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<FeedAuth>,
+    /// Minutes `watch` waits between fetches of this feed instead of its
+    /// `--interval`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interval_mins: Option<u64>,
+    /// Extra request headers, e.g. a `Cookie` or `User-Agent` some sites
+    /// insist on
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+}
+
+/// Username and password sent with every request for a feed.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeedAuth {
+    pub username: String,
+    pub password: String,
+}
+
+impl FeedAuth {
+    /// Value of the `Authorization` header for these credentials.
+    pub fn header(&self) -> String {
+        let credentials = format!("{}:{}", self.username, self.password);
+        format!("Basic {}", BASE64.encode(credentials))
+    }
+}
+
+/// Leaves the password out so credentials can't leak into debug output.
+impl fmt::Debug for FeedAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FeedAuth")
+            .field("username", &self.username)
+            .field("password", &"<hidden>")
+            .finish()
+    }
+}
+
+impl Feed {
+    pub fn new(url: &str) -> Self {
+        Feed {
+            url: url.to_string(),
+            name: None,
+            weekdays: None,
+            group: None,
+            auth: None,
+            interval_mins: None,
+            headers: BTreeMap::new(),
+        }
+    }
+
+    /// Headers sent when fetching this feed: its own `headers` plus an
+    /// `Authorization` header for its login, if any.
+    pub fn request_headers(&self) -> Vec<(String, String)> {
+        let mut headers: Vec<(String, String)> = self
+            .headers
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        if let Some(auth) = &self.auth {
+            headers.push(("Authorization".to_string(), auth.header()));
+        }
+        headers
+    }
+
+    /// Days of the week whose articles should be shown, combining this feed's
+    /// own setting with a global Monday-to-Friday restriction. None means all.
+    pub fn allowed_weekdays(&self, weekdays_only: bool) -> Option<Vec<Weekday>> {
+        let is_weekday = |d: &Weekday| !matches!(d, Weekday::Sat | Weekday::Sun);
+        match &self.weekdays {
+            Some(days) if weekdays_only => Some(days.iter().copied().filter(is_weekday).collect()),
+            Some(days) => Some(days.clone()),
+            None if weekdays_only => Some(
+                [
+                    Weekday::Mon,
+                    Weekday::Tue,
+                    Weekday::Wed,
+                    Weekday::Thu,
+                    Weekday::Fri,
+                ]
+                .to_vec(),
+            ),
+            None => None,
+        }
+    }
+
+    /// Whether the feed is filed under `group`, ignoring case.
+    pub fn in_group(&self, group: &str) -> bool {
+        self.group
+            .as_deref()
+            .is_some_and(|g| g.eq_ignore_ascii_case(group))
+    }
+
+    /// Time between fetches in watch mode: the feed's own `interval_mins`,
+    /// or `default` when it has none or it is too large to represent.
+    pub fn refresh_interval(&self, default: TimeDelta) -> TimeDelta {
+        self.interval_mins
+            .and_then(|mins| TimeDelta::try_minutes(i64::try_from(mins).ok()?))
+            .unwrap_or(default)
+    }
+
+    /// Whether the feed should be fetched again at `now`, given when it was
+    /// last fetched (None if never) and the interval for feeds without their
+    /// own.
+    ///
+    /// ```
+    /// use chrono::{TimeDelta, Utc};
+    /// use rss_reader::Feed;
+    ///
+    /// let now = Utc::now();
+    /// let hourly = Feed { interval_mins: Some(60), ..Feed::new("https://example.com/feed") };
+    /// let default = TimeDelta::minutes(15);
+    /// assert!(hourly.is_due(None, default, now));
+    /// assert!(!hourly.is_due(Some(now - TimeDelta::minutes(30)), default, now));
+    /// assert!(hourly.is_due(Some(now - TimeDelta::minutes(60)), default, now));
+    ///
+    /// let plain = Feed::new("https://example.com/other");
+    /// assert!(plain.is_due(Some(now - TimeDelta::minutes(30)), default, now));
+    /// ```
+    pub fn is_due(
+        &self,
+        last_fetched: Option<DateTime<Utc>>,
+        default_interval: TimeDelta,
+        now: DateTime<Utc>,
+    ) -> bool {
+        last_fetched.is_none_or(|last| now - last >= self.refresh_interval(default_interval))
+    }
+
+    /// Copies every metadata field that is set on `other` onto this feed.
+    /// Returns true if anything changed.
+    fn merge(&mut self, other: &Feed) -> bool {
+        let mut changed = false;
+        if other.name.is_some() && other.name != self.name {
+            self.name = other.name.clone();
+            changed = true;
+        }
+        if other.weekdays.is_some() && other.weekdays != self.weekdays {
+            self.weekdays = other.weekdays.clone();
+            changed = true;
+        }
+        if other.group.is_some() && other.group != self.group {
+            self.group = other.group.clone();
+            changed = true;
+        }
+        if other.auth.is_some() && other.auth != self.auth {
+            self.auth = other.auth.clone();
+            changed = true;
+        }
+        if other.interval_mins.is_some() && other.interval_mins != self.interval_mins {
+            self.interval_mins = other.interval_mins;
+            changed = true;
+        }
+        for (name, value) in &other.headers {
+            if self.headers.get(name) != Some(value) {
+                self.headers.insert(name.clone(), value.clone());
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+/// Older configs stored feeds as bare URL strings.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum FeedEntry {
+    Url(String),
+    Feed(Feed),
+}
+
+fn deserialize_feeds<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Feed>, D::Error> {
+    let entries = Vec::<FeedEntry>::deserialize(deserializer)?;
+    Ok(entries
+        .into_iter()
+        .map(|entry| match entry {
+            FeedEntry::Url(url) => Feed::new(&url),
+            FeedEntry::Feed(feed) => feed,
+        })
+        .collect())
+}
+
+/// Normalizes the tickers of investments read from the config, merging
+/// entries that only differed in case, spacing or a leading `$`.
+fn deserialize_investments<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Investment>, D::Error> {
+    let mut investments: Vec<Investment> = Vec::new();
+    for mut investment in Vec::<Investment>::deserialize(deserializer)? {
+        investment.ticker = stock::normalize_ticker(&investment.ticker);
+        match investments
+            .iter_mut()
+            .find(|i| i.ticker == investment.ticker)
+        {
+            Some(existing) => {
+                existing.merge(&investment);
+            }
+            None => investments.push(investment),
+        }
+    }
+    Ok(investments)
+}
+
+/// Result of adding a feed or investment that may already exist.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AddOutcome {
+    Added,
+    /// Already present; the supplied metadata was applied
+    Updated,
+    /// Already present and left as is
+    Unchanged,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub struct Config {
-    pub feeds: Vec<String>,
-    #[serde(default)]
+    #[serde(deserialize_with = "deserialize_feeds")]
+    pub feeds: Vec<Feed>,
+    #[serde(default, deserialize_with = "deserialize_investments")]
     pub investments: Vec<Investment>,
+    /// Characters accepted around a ticker when matching it in article text
+    #[serde(default)]
+    pub ticker_boundaries: TickerBoundaries,
+    /// Tickers that `scan --strict` only matches as cashtags or by company
+    /// name, in addition to those shorter than three letters
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ambiguous_tickers: Vec<String>,
+    /// Size and age limits for the mention history
+    #[serde(default)]
+    pub history: HistoryLimits,
+    /// Where quotes and price history come from unless `--provider` is given
+    #[serde(default)]
+    pub stock_provider: ProviderKind,
+    /// API key for the Alpha Vantage provider
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alphavantage_api_key: Option<String>,
+    /// How long fetched quotes and price histories are reused
+    #[serde(default)]
+    pub stock_cache: StockCacheTtl,
+    /// User-Agent sent with feed and stock requests instead of the default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    /// Proxy for all requests, overriding HTTP_PROXY/HTTPS_PROXY
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    /// Characters of article content kept when parsing feeds (0 for no
+    /// limit) unless `--max-content-len` is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_content_len: Option<usize>,
+    /// Extra words counted as positive by sentiment analysis
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub positive_words: Vec<String>,
+    /// Extra words counted as negative by sentiment analysis
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub negative_words: Vec<String>,
+    /// JSON file with further `positive_words` / `negative_words` lists
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lexicon_file: Option<PathBuf>,
+    /// Use only the configured words instead of adding them to the built-in
+    /// lists
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub replace_default_words: bool,
+}
+
+/// Word lists read from `Config::lexicon_file`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct LexiconFile {
+    positive_words: Vec<String>,
+    negative_words: Vec<String>,
 }
 
 impl Config {
-    pub fn load() -> io::Result<Self> {
-        let path = config_path()?;
+    /// Loads the config at `path`, or the default when there is no file
+    /// there yet. A file that exists but can't be read or parsed is an error
+    /// naming the file, never a silent reset.
+    pub fn load_from(path: &Path, format: ConfigFormat) -> io::Result<Self> {
         if !path.exists() {
             return Ok(Config::default());
         }
-        let content = fs::read_to_string(&path)?;
-        serde_json::from_str(&content)
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        let content = fs::read_to_string(path)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+        let parsed = match format {
+            ConfigFormat::Json => serde_json::from_str(&content).map_err(|e| e.to_string()),
+            ConfigFormat::Toml => toml::from_str(&content).map_err(|e| e.to_string()),
+        };
+        parsed.map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), e),
+            )
+        })
     }
 
-    pub fn save(&self) -> io::Result<()> {
-        let path = config_path()?;
-        if let Some(parent) = path.parent() {
+    /// Saves atomically so an interrupted write never costs the existing
+    /// subscriptions.
+    pub fn save_to(&self, path: &Path, format: ConfigFormat) -> io::Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
             fs::create_dir_all(parent)?;
         }
-        let content = serde_json::to_string_pretty(self)?;
-        fs::write(&path, content)
+        let content = match format {
+            ConfigFormat::Json => serde_json::to_string_pretty(self)?,
+            ConfigFormat::Toml => toml::to_string_pretty(self)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+        };
+        write_atomic(path, content.as_bytes())
     }
 
-    pub fn add_feed(&mut self, url: &str) -> bool {
-        if self.feeds.contains(&url.to_string()) {
-            return false;
+    /// Builds the sentiment lexicon from the built-in word lists (unless
+    /// replaced), the lexicon file and the words listed in the config.
+    pub fn lexicon(&self) -> io::Result<Lexicon> {
+        let mut lexicon = if self.replace_default_words {
+            Lexicon::new([], [])
+        } else {
+            Lexicon::default()
+        };
+        if let Some(path) = &self.lexicon_file {
+            let content = fs::read_to_string(path)?;
+            let file: LexiconFile = serde_json::from_str(&content)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            lexicon.extend(file.positive_words, file.negative_words);
         }
-        self.feeds.push(url.to_string());
-        true
+        lexicon.extend(
+            self.positive_words.iter().cloned(),
+            self.negative_words.iter().cloned(),
+        );
+        Ok(lexicon)
     }
 
-    pub fn remove_feed(&mut self, url: &str) -> bool {
-        if let Some(pos) = self.feeds.iter().position(|f| f == url) {
-            self.feeds.remove(pos);
-            true
-        } else {
-            false
+    /// Index of the feed with URL `key`, or else of the feed named `key`
+    /// (ignoring case), so feeds can be referred to by their display name.
+    fn feed_index(&self, key: &str) -> Option<usize> {
+        self.feeds
+            .iter()
+            .position(|f| same_feed_url(&f.url, key))
+            .or_else(|| {
+                self.feeds.iter().position(|f| {
+                    f.name
+                        .as_deref()
+                        .is_some_and(|n| n.eq_ignore_ascii_case(key))
+                })
+            })
+    }
+
+    /// Feeds in `group` (ignoring case), or all feeds when it is None.
+    pub fn feeds_in_group(&self, group: Option<&str>) -> Vec<Feed> {
+        self.feeds
+            .iter()
+            .filter(|f| group.is_none_or(|g| f.in_group(g)))
+            .cloned()
+            .collect()
+    }
+
+    /// Distinct group names with their number of feeds, sorted by name.
+    /// Ungrouped feeds aren't counted.
+    pub fn groups(&self) -> Vec<(String, usize)> {
+        let mut groups: Vec<(String, usize)> = Vec::new();
+        for group in self.feeds.iter().filter_map(|f| f.group.as_ref()) {
+            match groups
+                .iter_mut()
+                .find(|(g, _)| g.eq_ignore_ascii_case(group))
+            {
+                Some((_, count)) => *count += 1,
+                None => groups.push((group.clone(), 1)),
+            }
         }
+        groups.sort_by_key(|(g, _)| g.to_lowercase());
+        groups
+    }
+
+    /// Finds a feed by URL or name.
+    pub fn find_feed(&self, key: &str) -> Option<&Feed> {
+        self.feed_index(key).map(|i| &self.feeds[i])
     }
 
-    pub fn add_investment(&mut self, ticker: &str, name: Option<String>) -> bool {
-        let ticker_upper = ticker.to_uppercase();
-        if self.investments.iter().any(|i| i.ticker == ticker_upper) {
-            return false;
+    pub fn has_feed(&self, url: &str) -> bool {
+        self.feeds.iter().any(|f| same_feed_url(&f.url, url))
+    }
+
+    /// Adds a feed, or merges its metadata into an existing entry with the
+    /// same URL when `update` is set.
+    pub fn upsert_feed(&mut self, feed: Feed, update: bool) -> AddOutcome {
+        match self
+            .feeds
+            .iter_mut()
+            .find(|f| same_feed_url(&f.url, &feed.url))
+        {
+            Some(existing) => {
+                if update && existing.merge(&feed) {
+                    AddOutcome::Updated
+                } else {
+                    AddOutcome::Unchanged
+                }
+            }
+            None => {
+                self.feeds.push(feed);
+                AddOutcome::Added
+            }
+        }
+    }
+
+    /// Removes a feed by URL or name, returning it if it was subscribed.
+    pub fn remove_feed(&mut self, key: &str) -> Option<Feed> {
+        let pos = self.feed_index(key)?;
+        Some(self.feeds.remove(pos))
+    }
+
+    /// Returns the 1-based position of a feed, matching `list` numbering.
+    pub fn feed_position(&self, key: &str) -> Option<usize> {
+        self.feed_index(key).map(|i| i + 1)
+    }
+
+    /// Moves a feed to a 1-based position, clamped to the ends of the list.
+    /// Returns the feed's new position, or None if it isn't subscribed.
+    pub fn move_feed(&mut self, key: &str, position: usize) -> Option<usize> {
+        let from = self.feed_index(key)?;
+        let to = position.clamp(1, self.feeds.len()) - 1;
+        let feed = self.feeds.remove(from);
+        self.feeds.insert(to, feed);
+        Some(to + 1)
+    }
+
+    /// Adds an investment, or merges its optional fields into an existing
+    /// entry with the same normalized ticker when `update` is set.
+    pub fn upsert_investment(&mut self, mut investment: Investment, update: bool) -> AddOutcome {
+        investment.ticker = stock::normalize_ticker(&investment.ticker);
+        match self
+            .investments
+            .iter_mut()
+            .find(|i| i.ticker == investment.ticker)
+        {
+            Some(existing) => {
+                if update && existing.merge(&investment) {
+                    AddOutcome::Updated
+                } else {
+                    AddOutcome::Unchanged
+                }
+            }
+            None => {
+                self.investments.push(investment);
+                AddOutcome::Added
+            }
         }
-        self.investments.push(Investment {
-            ticker: ticker_upper,
-            name,
-        });
-        true
     }
 
     pub fn remove_investment(&mut self, ticker: &str) -> bool {
-        let ticker_upper = ticker.to_uppercase();
-        if let Some(pos) = self.investments.iter().position(|i| i.ticker == ticker_upper) {
+        let ticker = stock::normalize_ticker(ticker);
+        if let Some(pos) = self.investments.iter().position(|i| i.ticker == ticker) {
             self.investments.remove(pos);
             true
         } else {
@@ -76,8 +557,627 @@ impl Config {
     }
 }
 
-fn config_path() -> io::Result<PathBuf> {
+/// Directory holding config.json and the other files the reader persists.
+pub fn data_dir() -> io::Result<PathBuf> {
     let config_dir = dirs::config_dir()
         .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Config directory not found"))?;
-    Ok(config_dir.join("rss-reader").join("config.json"))
+    Ok(config_dir.join("rss-reader"))
+}
+
+/// Writes `content` to a temporary file next to `path`, flushes it to disk
+/// and renames it over `path`. Either the old file or the complete new one
+/// is left behind, never a truncated mix. The temporary name is unique to
+/// the process and call, so concurrent writers don't clobber each other's
+/// half-written files.
+pub fn write_atomic(path: &Path, content: &[u8]) -> io::Result<()> {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(format!(
+        ".{}.{}.tmp",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let tmp = PathBuf::from(tmp);
+    let result = fs::File::create(&tmp)
+        .and_then(|mut file| {
+            file.write_all(content)?;
+            file.sync_all()
+        })
+        .and_then(|()| fs::rename(&tmp, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result?;
+    sync_parent(path)
+}
+
+/// Flushes the directory entry for `path`, so a completed rename survives a
+/// crash.
+#[cfg(unix)]
+fn sync_parent(path: &Path) -> io::Result<()> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    fs::File::open(parent)?.sync_all()
+}
+
+/// Directories can't be opened for syncing here; the rename is as durable as
+/// the platform makes it.
+#[cfg(not(unix))]
+fn sync_parent(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// Environment variable naming the config file when `--config` isn't given.
+const CONFIG_PATH_VAR: &str = "AARON_RSS_CONFIG";
+
+/// File formats the config can be stored in.
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+}
+
+impl ConfigFormat {
+    /// The format implied by the file extension: TOML for `.toml`, JSON
+    /// for anything else.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension() {
+            Some(ext) if ext.eq_ignore_ascii_case("toml") => ConfigFormat::Toml,
+            _ => ConfigFormat::Json,
+        }
+    }
+}
+
+/// The config file to use: `explicit` (given with `--config`), then the
+/// one named by `AARON_RSS_CONFIG`, otherwise config.json in [`data_dir`].
+pub fn config_path(explicit: Option<PathBuf>) -> io::Result<PathBuf> {
+    if let Some(path) = explicit {
+        return Ok(path);
+    }
+    match std::env::var_os(CONFIG_PATH_VAR).filter(|p| !p.is_empty()) {
+        Some(path) => Ok(PathBuf::from(path)),
+        None => Ok(data_dir()?.join("config.json")),
+    }
+}
+
+/// Storage backends selectable with `--storage`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, clap::ValueEnum)]
+pub enum StorageKind {
+    /// JSON files: the config, with the other stores next to it
+    #[default]
+    Json,
+    /// A single SQLite database next to the config file (needs the `sqlite`
+    /// build feature)
+    Sqlite,
+}
+
+/// Where the config, the feed cache and the read state are kept.
+pub trait Storage: Send + Sync {
+    fn load_config(&self) -> io::Result<Config>;
+    fn save_config(&self, config: &Config) -> io::Result<()>;
+    fn load_cache(&self) -> io::Result<FeedCache>;
+    fn save_cache(&self, cache: &FeedCache) -> io::Result<()>;
+    fn load_seen(&self) -> io::Result<SeenStore>;
+    fn save_seen(&self, seen: &SeenStore) -> io::Result<()>;
+    /// Directory of the config, where the mention history, the last fetch
+    /// and the stock cache are kept too.
+    fn state_dir(&self) -> &Path;
+}
+
+/// One JSON file per store: the config at the given path, cache.json and
+/// seen.json in the same directory.
+pub struct JsonStorage {
+    config_path: PathBuf,
+    format: ConfigFormat,
+    dir: PathBuf,
+}
+
+impl JsonStorage {
+    /// Keeps the config at `config_path` in `format`, or the format its
+    /// extension implies.
+    pub fn new(config_path: PathBuf, format: Option<ConfigFormat>) -> Self {
+        let format = format.unwrap_or_else(|| ConfigFormat::from_path(&config_path));
+        let dir = match config_path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        JsonStorage {
+            config_path,
+            format,
+            dir,
+        }
+    }
+}
+
+impl Storage for JsonStorage {
+    fn load_config(&self) -> io::Result<Config> {
+        Config::load_from(&self.config_path, self.format)
+    }
+
+    fn save_config(&self, config: &Config) -> io::Result<()> {
+        config.save_to(&self.config_path, self.format)
+    }
+
+    fn load_cache(&self) -> io::Result<FeedCache> {
+        FeedCache::load(&self.dir)
+    }
+
+    fn save_cache(&self, cache: &FeedCache) -> io::Result<()> {
+        cache.save(&self.dir)
+    }
+
+    fn load_seen(&self) -> io::Result<SeenStore> {
+        SeenStore::load(&self.dir)
+    }
+
+    fn save_seen(&self, seen: &SeenStore) -> io::Result<()> {
+        seen.save(&self.dir)
+    }
+
+    fn state_dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+/// The JSON stores imported into a newly created database.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Migration {
+    /// Database the config, feed cache and read state were imported into
+    pub database: PathBuf,
+    /// Why the feed cache was left behind, if it couldn't be read
+    pub cache_error: Option<String>,
+}
+
+/// Opens the backend of the given kind for the config at `config_path`
+/// (see [`config_path`]); every other store is kept in its directory.
+/// `format` overrides the config format implied by the extension. Also
+/// returns what was imported if this created the backend from the JSON
+/// files.
+pub fn open(
+    kind: StorageKind,
+    config_path: PathBuf,
+    format: Option<ConfigFormat>,
+) -> io::Result<(Box<dyn Storage>, Option<Migration>)> {
+    let json = JsonStorage::new(config_path, format);
+    match kind {
+        StorageKind::Json => Ok((Box::new(json), None)),
+        #[cfg(feature = "sqlite")]
+        StorageKind::Sqlite => {
+            let (storage, migration) = sqlite::SqliteStorage::open(&json)?;
+            Ok((Box::new(storage), migration))
+        }
+        #[cfg(not(feature = "sqlite"))]
+        StorageKind::Sqlite => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "built without SQLite support (rebuild with --features sqlite)",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::Sentiment;
+
+    fn config_with_feeds(urls: &[&str]) -> Config {
+        let mut config = Config::default();
+        for url in urls {
+            config.upsert_feed(Feed::new(url), false);
+        }
+        config
+    }
+
+    fn urls(config: &Config) -> Vec<&str> {
+        config.feeds.iter().map(|f| f.url.as_str()).collect()
+    }
+
+    #[test]
+    fn re_adding_a_feed_updates_only_the_given_fields() {
+        let url = "https://example.com/feed.xml";
+        let mut config = Config::default();
+        let first = Feed {
+            name: Some("Example".to_string()),
+            ..Feed::new(url)
+        };
+        assert_eq!(config.upsert_feed(first, true), AddOutcome::Added);
+
+        let grouped = Feed {
+            group: Some("news".to_string()),
+            ..Feed::new(url)
+        };
+        assert_eq!(
+            config.upsert_feed(grouped.clone(), true),
+            AddOutcome::Updated
+        );
+        assert_eq!(config.upsert_feed(grouped, true), AddOutcome::Unchanged);
+        assert_eq!(config.feeds.len(), 1);
+        assert_eq!(config.feeds[0].group.as_deref(), Some("news"));
+        assert_eq!(config.feeds[0].name.as_deref(), Some("Example"));
+    }
+
+    #[test]
+    fn re_adding_without_update_leaves_the_feed_alone() {
+        let url = "https://example.com/feed.xml";
+        let mut config = config_with_feeds(&[url]);
+        let grouped = Feed {
+            group: Some("news".to_string()),
+            ..Feed::new(url)
+        };
+        assert_eq!(config.upsert_feed(grouped, false), AddOutcome::Unchanged);
+        assert_eq!(config.feeds[0].group, None);
+    }
+
+    #[test]
+    fn re_adding_an_investment_merges_optional_fields() {
+        let mut config = Config::default();
+        assert_eq!(
+            config.upsert_investment(Investment::new("aapl"), true),
+            AddOutcome::Added
+        );
+        let named = Investment {
+            name: Some("Apple".to_string()),
+            ..Investment::new("AAPL")
+        };
+        assert_eq!(config.upsert_investment(named, true), AddOutcome::Updated);
+        assert_eq!(config.investments.len(), 1);
+        assert_eq!(config.investments[0].name.as_deref(), Some("Apple"));
+    }
+
+    #[test]
+    fn spellings_of_a_ticker_are_upserted_as_one_investment() {
+        let mut config = Config::default();
+        for ticker in [" aapl ", "$AAPL", "aapl"] {
+            let raw = Investment {
+                ticker: ticker.to_string(),
+                ..Investment::new("AAPL")
+            };
+            config.upsert_investment(raw, false);
+        }
+        let tickers: Vec<_> = config
+            .investments
+            .iter()
+            .map(|i| i.ticker.as_str())
+            .collect();
+        assert_eq!(tickers, ["AAPL"]);
+    }
+
+    #[test]
+    fn spellings_of_a_ticker_in_the_config_are_merged_on_load() {
+        let config: Config = serde_json::from_str(
+            r#"{"feeds": [], "investments": [
+                {"ticker": " aapl ", "name": null},
+                {"ticker": "$AAPL", "name": "Apple"},
+                {"ticker": "aapl", "name": null, "alert_pct": 3.0}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(config.investments.len(), 1);
+        let apple = &config.investments[0];
+        assert_eq!(apple.ticker, "AAPL");
+        assert_eq!(apple.name.as_deref(), Some("Apple"));
+        assert_eq!(apple.alert_pct, Some(3.0));
+    }
+
+    #[test]
+    fn weekdays_only_narrows_the_feed_setting() {
+        let weekend = Feed {
+            weekdays: Some(vec![Weekday::Fri, Weekday::Sat, Weekday::Sun]),
+            ..Feed::new("https://example.com/feed.xml")
+        };
+        assert_eq!(
+            weekend.allowed_weekdays(false),
+            Some(vec![Weekday::Fri, Weekday::Sat, Weekday::Sun])
+        );
+        assert_eq!(weekend.allowed_weekdays(true), Some(vec![Weekday::Fri]));
+
+        let any_day = Feed::new("https://example.com/feed.xml");
+        assert_eq!(any_day.allowed_weekdays(false), None);
+        let weekdays = any_day.allowed_weekdays(true).unwrap();
+        assert!(!weekdays.contains(&Weekday::Sat) && !weekdays.contains(&Weekday::Sun));
+        assert_eq!(weekdays.len(), 5);
+    }
+
+    #[test]
+    fn move_feed_reorders_and_clamps() {
+        let mut config = config_with_feeds(&["https://a.com/", "https://b.com/", "https://c.com/"]);
+        assert_eq!(config.move_feed("https://c.com/", 1), Some(1));
+        assert_eq!(
+            urls(&config),
+            ["https://c.com/", "https://a.com/", "https://b.com/"]
+        );
+        assert_eq!(config.move_feed("https://c.com/", 99), Some(3));
+        assert_eq!(config.move_feed("https://a.com/", 0), Some(1));
+        assert_eq!(
+            urls(&config),
+            ["https://a.com/", "https://b.com/", "https://c.com/"]
+        );
+        assert_eq!(config.feed_position("https://b.com/"), Some(2));
+        assert_eq!(config.move_feed("https://missing.com/", 1), None);
+    }
+
+    #[test]
+    fn refresh_interval_falls_back_on_out_of_range_values() {
+        let default = TimeDelta::minutes(15);
+        let feed = |interval_mins| Feed {
+            interval_mins,
+            ..Feed::new("https://example.com/feed.xml")
+        };
+        assert_eq!(feed(None).refresh_interval(default), default);
+        assert_eq!(
+            feed(Some(60)).refresh_interval(default),
+            TimeDelta::hours(1)
+        );
+        assert_eq!(feed(Some(u64::MAX)).refresh_interval(default), default);
+        assert_eq!(
+            feed(Some(i64::MAX as u64)).refresh_interval(default),
+            default
+        );
+    }
+
+    #[test]
+    fn write_atomic_replaces_the_file_without_leftovers() {
+        let dir = crate::test_dir("write-atomic");
+        let path = dir.join("config.json");
+        write_atomic(&path, b"old").unwrap();
+        write_atomic(&path, b"new").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"new");
+        let names: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(names, ["config.json"]);
+    }
+
+    #[test]
+    fn write_atomic_fails_without_touching_other_files() {
+        let dir = crate::test_dir("write-atomic-failed");
+        let original = dir.join("config.json");
+        write_atomic(&original, b"old").unwrap();
+        // A directory in the way makes the final rename fail, even as root.
+        let path = dir.join("seen.json");
+        fs::create_dir(&path).unwrap();
+        fs::write(path.join("inside"), b"kept").unwrap();
+
+        assert!(write_atomic(&path, b"new").is_err());
+        assert_eq!(fs::read(&original).unwrap(), b"old");
+        assert_eq!(fs::read(path.join("inside")).unwrap(), b"kept");
+        let mut names: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["config.json", "seen.json"]);
+    }
+
+    #[test]
+    fn lexicon_combines_defaults_file_and_config_words() {
+        let dir = crate::test_dir("lexicon-file");
+        let file = dir.join("words.json");
+        fs::write(&file, r#"{"positive_words": ["approval"]}"#).unwrap();
+        let config = Config {
+            negative_words: vec!["recall".to_string()],
+            lexicon_file: Some(file),
+            ..Config::default()
+        };
+        let lexicon = config.lexicon().unwrap();
+        let sentiment = |text| crate::analysis::score_sentiment(text, &lexicon).sentiment();
+        assert_eq!(sentiment("approval"), Sentiment::Positive);
+        assert_eq!(sentiment("recall"), Sentiment::Negative);
+        assert_eq!(sentiment("profits"), Sentiment::Positive);
+
+        let replaced = Config {
+            replace_default_words: true,
+            ..Config::default()
+        };
+        let lexicon = replaced.lexicon().unwrap();
+        assert_eq!(
+            crate::analysis::score_sentiment("profits", &lexicon).sentiment(),
+            Sentiment::Neutral
+        );
+    }
+
+    #[test]
+    fn lexicon_reports_a_missing_file() {
+        let config = Config {
+            lexicon_file: Some(crate::test_dir("lexicon-missing").join("words.json")),
+            ..Config::default()
+        };
+        assert!(config.lexicon().is_err());
+    }
+
+    #[test]
+    fn asset_kind_defaults_to_equity_and_is_omitted() {
+        let json = r#"{"feeds": [], "investments": [
+            {"ticker": "AAPL", "name": null},
+            {"ticker": "BTC", "name": null, "kind": "crypto"}
+        ]}"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        let kinds: Vec<_> = config.investments.iter().map(|i| i.kind).collect();
+        assert_eq!(kinds, [AssetKind::Equity, AssetKind::Crypto]);
+        let json = serde_json::to_string(&config.investments).unwrap();
+        assert_eq!(json.matches("kind").count(), 1);
+    }
+
+    #[test]
+    fn old_configs_with_plain_url_feeds_still_load() {
+        let json = r#"{"feeds": [
+            "https://example.com/feed.xml",
+            {"url": "https://example.org/rss", "name": "Org News"}
+        ], "investments": []}"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            urls(&config),
+            ["https://example.com/feed.xml", "https://example.org/rss"]
+        );
+        assert_eq!(config.feeds[0].name, None);
+        assert_eq!(config.feeds[1].name.as_deref(), Some("Org News"));
+
+        // Saved back in the struct form.
+        let saved = serde_json::to_value(&config).unwrap();
+        assert_eq!(
+            saved["feeds"][0],
+            serde_json::json!({"url": "https://example.com/feed.xml"})
+        );
+    }
+
+    #[test]
+    fn feeds_are_found_by_url_or_name() {
+        let mut config = config_with_feeds(&["https://example.com/feed.xml"]);
+        config.upsert_feed(
+            Feed {
+                name: Some("NYT Tech".to_string()),
+                ..Feed::new("https://nytimes.com/tech.xml")
+            },
+            false,
+        );
+        let found = |key| config.find_feed(key).map(|f| f.url.as_str());
+        assert_eq!(found("nyt tech"), Some("https://nytimes.com/tech.xml"));
+        assert_eq!(
+            found("https://example.com/feed.xml"),
+            Some("https://example.com/feed.xml")
+        );
+        assert_eq!(found("Unknown"), None);
+    }
+
+    fn grouped(url: &str, group: Option<&str>) -> Feed {
+        Feed {
+            group: group.map(str::to_string),
+            ..Feed::new(url)
+        }
+    }
+
+    #[test]
+    fn feeds_filter_by_group_ignoring_case() {
+        let mut config = Config::default();
+        for feed in [
+            grouped("https://a.com/", Some("finance")),
+            grouped("https://b.com/", Some("Tech")),
+            grouped("https://c.com/", None),
+            grouped("https://d.com/", Some("Finance")),
+        ] {
+            config.upsert_feed(feed, false);
+        }
+        let in_group = |group| {
+            config
+                .feeds_in_group(group)
+                .into_iter()
+                .map(|f| f.url)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            in_group(Some("FINANCE")),
+            ["https://a.com/", "https://d.com/"]
+        );
+        assert_eq!(in_group(Some("local")), Vec::<String>::new());
+        assert_eq!(in_group(None).len(), 4);
+        assert_eq!(
+            config.groups(),
+            [("finance".to_string(), 2), ("Tech".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn a_corrupt_config_is_an_error_not_the_default() {
+        let path = crate::test_dir("corrupt-config").join("config.json");
+        let storage = JsonStorage::new(path.clone(), None);
+        assert!(storage.load_config().unwrap().feeds.is_empty());
+
+        fs::write(&path, "{\"feeds\": [").unwrap();
+        let err = storage.load_config().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("config.json"));
+    }
+
+    #[test]
+    fn config_path_prefers_the_flag_then_the_env_var() {
+        let dir = crate::test_dir("config-path");
+        let from_env = dir.join("profile.json");
+        std::env::set_var(CONFIG_PATH_VAR, &from_env);
+        let explicit = dir.join("explicit.json");
+        assert_eq!(config_path(Some(explicit.clone())).unwrap(), explicit);
+        let path = config_path(None).unwrap();
+        std::env::remove_var(CONFIG_PATH_VAR);
+        assert_eq!(path, from_env);
+
+        let config = config_with_feeds(&["https://example.com/feed.xml"]);
+        let storage = JsonStorage::new(path, None);
+        storage.save_config(&config).unwrap();
+        assert!(from_env.exists());
+        assert_eq!(
+            urls(&storage.load_config().unwrap()),
+            ["https://example.com/feed.xml"]
+        );
+    }
+
+    #[test]
+    fn toml_config_round_trips() {
+        let mut config = config_with_feeds(&["https://example.com/plain.xml"]);
+        config.feeds.push(Feed {
+            name: Some("Markets".to_string()),
+            group: Some("finance".to_string()),
+            weekdays: Some(vec![Weekday::Mon, Weekday::Fri]),
+            interval_mins: Some(30),
+            auth: Some(FeedAuth {
+                username: "reader".to_string(),
+                password: "secret".to_string(),
+            }),
+            headers: BTreeMap::from([("Cookie".to_string(), "consent=yes".to_string())]),
+            ..Feed::new("https://example.com/markets.xml")
+        });
+        config.investments.push(Investment {
+            name: Some("Apple".to_string()),
+            alert_pct: Some(2.5),
+            ..Investment::new("AAPL")
+        });
+        config.investments.push(Investment {
+            kind: AssetKind::Crypto,
+            ..Investment::new("BTC")
+        });
+        config.positive_words.push("moonshot".to_string());
+
+        let path = crate::test_dir("toml-config").join("config.toml");
+        let storage = JsonStorage::new(path.clone(), None);
+        storage.save_config(&config).unwrap();
+        assert!(fs::read_to_string(&path).unwrap().contains("[[feeds]]"));
+
+        let loaded = storage.load_config().unwrap();
+        assert_eq!(
+            serde_json::to_value(&loaded).unwrap(),
+            serde_json::to_value(&config).unwrap()
+        );
+    }
+
+    #[test]
+    fn feeds_are_deduplicated_by_normalized_url() {
+        let mut config = config_with_feeds(&["http://x.com/"]);
+        assert!(config.has_feed("http://x.com"));
+        assert_eq!(
+            config.upsert_feed(Feed::new("HTTP://X.COM"), false),
+            AddOutcome::Unchanged
+        );
+        assert_eq!(urls(&config), ["http://x.com/"]);
+        assert!(config.remove_feed("http://x.com").is_some());
+        assert!(config.feeds.is_empty());
+    }
+
+    #[test]
+    fn feed_logins_never_show_the_password() {
+        let auth = FeedAuth {
+            username: "reader".to_string(),
+            password: "s3cret".to_string(),
+        };
+        assert_eq!(auth.header(), "Basic cmVhZGVyOnMzY3JldA==");
+        let feed = Feed {
+            auth: Some(auth),
+            ..Feed::new("https://example.com/private.xml")
+        };
+        let debug = format!("{:?}", feed);
+        assert!(
+            debug.contains("reader") && !debug.contains("s3cret"),
+            "{}",
+            debug
+        );
+    }
 }
@@ -0,0 +1,344 @@
+use super::{Config, JsonStorage, Migration, Storage};
+use crate::cache::{CachedFeed, FeedCache};
+use crate::seen::SeenStore;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::Value;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Name of the database file, kept next to the config.
+pub const DATABASE_FILE: &str = "reader.db";
+
+/// Feeds and investments get a row each so large lists stay queryable; the
+/// remaining config fields are kept together as one JSON document.
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS settings (
+        id INTEGER PRIMARY KEY CHECK (id = 1),
+        data TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS feeds (
+        position INTEGER PRIMARY KEY,
+        url TEXT NOT NULL UNIQUE,
+        data TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS investments (
+        position INTEGER PRIMARY KEY,
+        ticker TEXT NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS feed_cache (
+        url TEXT PRIMARY KEY,
+        data TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS seen (
+        key TEXT PRIMARY KEY,
+        marked TEXT NOT NULL
+    );
+";
+
+/// Everything in one SQLite database.
+pub struct SqliteStorage {
+    conn: Mutex<Connection>,
+    dir: PathBuf,
+}
+
+fn sql_error(e: rusqlite::Error) -> io::Error {
+    io::Error::other(e)
+}
+
+fn json_error(e: serde_json::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+impl SqliteStorage {
+    /// Opens (creating if needed) the database in the directory of `json`.
+    /// A new database is filled from those JSON files so switching backends
+    /// keeps every feed, cached copy and read mark; the import is returned
+    /// for the caller to report.
+    pub fn open(json: &JsonStorage) -> io::Result<(Self, Option<Migration>)> {
+        let dir = json.state_dir();
+        fs::create_dir_all(dir)?;
+        let path = dir.join(DATABASE_FILE);
+        let conn = Connection::open(&path).map_err(sql_error)?;
+        conn.execute_batch(SCHEMA).map_err(sql_error)?;
+        let storage = SqliteStorage {
+            conn: Mutex::new(conn),
+            dir: dir.to_path_buf(),
+        };
+        let migration = if storage.has_settings()? {
+            None
+        } else {
+            Some(Migration {
+                database: path,
+                cache_error: storage.migrate_from(json)?,
+            })
+        };
+        Ok((storage, migration))
+    }
+
+    fn has_settings(&self) -> io::Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row("SELECT 1 FROM settings", [], |_| Ok(()))
+            .optional()
+            .map(|row| row.is_some())
+            .map_err(sql_error)
+    }
+
+    /// Copies everything from `source`. The config goes last: the settings
+    /// row it writes marks the migration as done, so an interrupted one is
+    /// simply redone on the next run. The feed cache only saves bandwidth,
+    /// so one that can't be read is left behind rather than failing; its
+    /// error is returned instead.
+    fn migrate_from(&self, source: &dyn Storage) -> io::Result<Option<String>> {
+        let (cache, cache_error) = match source.load_cache() {
+            Ok(cache) => (cache, None),
+            Err(e) => (FeedCache::default(), Some(e.to_string())),
+        };
+        self.save_cache(&cache)?;
+        self.save_seen(&source.load_seen()?)?;
+        self.save_config(&source.load_config()?)?;
+        Ok(cache_error)
+    }
+}
+
+/// The JSON documents stored in `table`, in position order.
+fn load_rows(conn: &Connection, table: &str) -> io::Result<Vec<Value>> {
+    let mut stmt = conn
+        .prepare(&format!("SELECT data FROM {} ORDER BY position", table))
+        .map_err(sql_error)?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(sql_error)?;
+    rows.map(|data| serde_json::from_str(&data.map_err(sql_error)?).map_err(json_error))
+        .collect()
+}
+
+impl Storage for SqliteStorage {
+    fn load_config(&self) -> io::Result<Config> {
+        let conn = self.conn.lock().unwrap();
+        let settings: Option<String> = conn
+            .query_row("SELECT data FROM settings", [], |row| row.get(0))
+            .optional()
+            .map_err(sql_error)?;
+        let Some(settings) = settings else {
+            return Ok(Config::default());
+        };
+        let mut config: Value = serde_json::from_str(&settings).map_err(json_error)?;
+        config["feeds"] = Value::Array(load_rows(&conn, "feeds")?);
+        config["investments"] = Value::Array(load_rows(&conn, "investments")?);
+        serde_json::from_value(config).map_err(json_error)
+    }
+
+    fn save_config(&self, config: &Config) -> io::Result<()> {
+        let mut settings = serde_json::to_value(config).map_err(json_error)?;
+        let fields = settings
+            .as_object_mut()
+            .expect("config serializes to an object");
+        fields.remove("feeds");
+        fields.remove("investments");
+
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction().map_err(sql_error)?;
+        tx.execute("DELETE FROM feeds", []).map_err(sql_error)?;
+        for (position, feed) in config.feeds.iter().enumerate() {
+            let data = serde_json::to_string(feed).map_err(json_error)?;
+            tx.execute(
+                "INSERT INTO feeds (position, url, data) VALUES (?1, ?2, ?3)",
+                params![position as i64, feed.url, data],
+            )
+            .map_err(sql_error)?;
+        }
+        tx.execute("DELETE FROM investments", [])
+            .map_err(sql_error)?;
+        for (position, investment) in config.investments.iter().enumerate() {
+            let data = serde_json::to_string(investment).map_err(json_error)?;
+            tx.execute(
+                "INSERT INTO investments (position, ticker, data) VALUES (?1, ?2, ?3)",
+                params![position as i64, investment.ticker, data],
+            )
+            .map_err(sql_error)?;
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO settings (id, data) VALUES (1, ?1)",
+            params![settings.to_string()],
+        )
+        .map_err(sql_error)?;
+        tx.commit().map_err(sql_error)
+    }
+
+    fn load_cache(&self) -> io::Result<FeedCache> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare("SELECT url, data FROM feed_cache")
+            .map_err(sql_error)?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(sql_error)?;
+        let mut cache = FeedCache::default();
+        for row in rows {
+            let (url, data) = row.map_err(sql_error)?;
+            let feed: CachedFeed = serde_json::from_str(&data).map_err(json_error)?;
+            cache.insert(&url, feed);
+        }
+        Ok(cache)
+    }
+
+    /// Upserts each cached feed; feeds are never dropped from the cache.
+    fn save_cache(&self, cache: &FeedCache) -> io::Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction().map_err(sql_error)?;
+        for (url, feed) in cache.iter() {
+            let data = serde_json::to_string(feed).map_err(json_error)?;
+            tx.execute(
+                "INSERT OR REPLACE INTO feed_cache (url, data) VALUES (?1, ?2)",
+                params![url, data],
+            )
+            .map_err(sql_error)?;
+        }
+        tx.commit().map_err(sql_error)
+    }
+
+    fn load_seen(&self) -> io::Result<SeenStore> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare("SELECT key, marked FROM seen")
+            .map_err(sql_error)?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(sql_error)?;
+        rows.collect::<Result<_, _>>().map_err(sql_error)
+    }
+
+    /// Replaces the stored read marks, so marks dropped by compaction go too.
+    fn save_seen(&self, seen: &SeenStore) -> io::Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction().map_err(sql_error)?;
+        tx.execute("DELETE FROM seen", []).map_err(sql_error)?;
+        for (key, marked) in seen.entries() {
+            tx.execute(
+                "INSERT INTO seen (key, marked) VALUES (?1, ?2)",
+                params![key, marked],
+            )
+            .map_err(sql_error)?;
+        }
+        tx.commit().map_err(sql_error)
+    }
+
+    fn state_dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{Feed, Investment};
+
+    fn urls(config: &Config) -> Vec<&str> {
+        config.feeds.iter().map(|f| f.url.as_str()).collect()
+    }
+
+    #[test]
+    fn feeds_and_investments_are_added_removed_and_listed() {
+        let dir = crate::test_dir("sqlite-crud");
+        let (storage, _) =
+            SqliteStorage::open(&JsonStorage::new(dir.join("config.json"), None)).unwrap();
+        assert!(storage.load_config().unwrap().feeds.is_empty());
+
+        let mut config = storage.load_config().unwrap();
+        for url in [
+            "https://a.example/feed",
+            "https://b.example/feed",
+            "https://c.example/feed",
+        ] {
+            config.upsert_feed(Feed::new(url), false);
+        }
+        config.upsert_investment(Investment::new("msft"), false);
+        config.upsert_investment(Investment::new("AAPL"), false);
+        storage.save_config(&config).unwrap();
+
+        let mut config = storage.load_config().unwrap();
+        assert_eq!(
+            urls(&config),
+            [
+                "https://a.example/feed",
+                "https://b.example/feed",
+                "https://c.example/feed"
+            ]
+        );
+        assert!(config.remove_feed("https://b.example/feed").is_some());
+        assert!(config.remove_investment("MSFT"));
+        storage.save_config(&config).unwrap();
+
+        let config = storage.load_config().unwrap();
+        assert_eq!(
+            urls(&config),
+            ["https://a.example/feed", "https://c.example/feed"]
+        );
+        let tickers: Vec<_> = config
+            .investments
+            .iter()
+            .map(|i| i.ticker.as_str())
+            .collect();
+        assert_eq!(tickers, ["AAPL"]);
+    }
+
+    #[test]
+    fn a_new_database_imports_the_json_stores_once() {
+        let dir = crate::test_dir("sqlite-migrate");
+        let json = JsonStorage::new(dir.join("config.json"), None);
+        let mut config = Config::default();
+        config.upsert_feed(Feed::new("https://a.example/feed"), false);
+        config.upsert_investment(Investment::new("AAPL"), false);
+        config.user_agent = Some("reader/1.0".to_string());
+        json.save_config(&config).unwrap();
+        fs::write(
+            dir.join("seen.json"),
+            r#"{"articles": {"https://a.example/1": "2026-10-15"}}"#,
+        )
+        .unwrap();
+
+        let (storage, migration) = SqliteStorage::open(&json).unwrap();
+        assert_eq!(
+            migration,
+            Some(Migration {
+                database: dir.join(DATABASE_FILE),
+                cache_error: None,
+            })
+        );
+        let migrated = storage.load_config().unwrap();
+        assert_eq!(urls(&migrated), ["https://a.example/feed"]);
+        assert_eq!(migrated.investments[0].ticker, "AAPL");
+        assert_eq!(migrated.user_agent.as_deref(), Some("reader/1.0"));
+        let seen = storage.load_seen().unwrap();
+        let entries: Vec<_> = seen.entries().collect();
+        assert_eq!(entries, [("https://a.example/1", "2026-10-15")]);
+        drop(storage);
+
+        // Later changes to the JSON files are not imported again.
+        config.upsert_feed(Feed::new("https://b.example/feed"), false);
+        json.save_config(&config).unwrap();
+        let (storage, migration) = SqliteStorage::open(&json).unwrap();
+        assert_eq!(migration, None);
+        assert_eq!(
+            urls(&storage.load_config().unwrap()),
+            ["https://a.example/feed"]
+        );
+    }
+
+    #[test]
+    fn an_unreadable_feed_cache_is_reported_not_imported() {
+        let dir = crate::test_dir("sqlite-migrate-cache");
+        let json = JsonStorage::new(dir.join("config.json"), None);
+        json.save_config(&Config::default()).unwrap();
+        fs::write(dir.join("cache.json"), "[broken").unwrap();
+
+        let (storage, migration) = SqliteStorage::open(&json).unwrap();
+        assert!(migration.unwrap().cache_error.is_some());
+        assert_eq!(storage.load_cache().unwrap().iter().count(), 0);
+    }
+}
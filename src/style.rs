@@ -0,0 +1,80 @@
+use owo_colors::OwoColorize;
+use std::io::{self, IsTerminal};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether stdout and stderr get colors; off until `init` says otherwise.
+static STDOUT_COLOR: AtomicBool = AtomicBool::new(false);
+static STDERR_COLOR: AtomicBool = AtomicBool::new(false);
+
+/// Turns colors on for whichever of stdout and stderr is a terminal, unless
+/// `disabled` (`--no-color`) or `NO_COLOR` says otherwise.
+pub fn init(disabled: bool) {
+    let allowed = !disabled && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty());
+    STDOUT_COLOR.store(allowed && io::stdout().is_terminal(), Ordering::Relaxed);
+    STDERR_COLOR.store(allowed && io::stderr().is_terminal(), Ordering::Relaxed);
+}
+
+fn stdout_color() -> bool {
+    STDOUT_COLOR.load(Ordering::Relaxed)
+}
+
+/// Each helper returns `text` unchanged when stdout isn't colored.
+pub fn bold(text: &str) -> String {
+    if stdout_color() {
+        text.bold().to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+pub fn green(text: &str) -> String {
+    if stdout_color() {
+        text.green().to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+pub fn red(text: &str) -> String {
+    if stdout_color() {
+        text.red().to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+pub fn dim(text: &str) -> String {
+    if stdout_color() {
+        text.dimmed().to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+/// An error line for stderr, red when stderr is colored.
+pub fn error(text: &str) -> String {
+    if STDERR_COLOR.load(Ordering::Relaxed) {
+        text.red().to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_escape_codes_when_color_is_disabled() {
+        init(true);
+        let styled = [
+            bold("== Feed =="),
+            green("+"),
+            red("-"),
+            dim("~"),
+            error("Error: failed"),
+        ];
+        assert_eq!(styled, ["== Feed ==", "+", "-", "~", "Error: failed"]);
+        assert!(styled.iter().all(|s| !s.contains('\x1b')));
+    }
+}
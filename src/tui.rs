@@ -0,0 +1,297 @@
+use crate::feed::{self, Article, FeedError, FeedResult, SortOrder};
+use crate::storage::Feed;
+use crossterm::event::{Event, EventStream, KeyCode, KeyEvent, KeyEventKind};
+use futures::stream::{BoxStream, StreamExt};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Text};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use std::io;
+
+enum FeedState {
+    Loading,
+    Loaded(Vec<Article>),
+    Failed(String),
+}
+
+struct FeedPane {
+    feed: Feed,
+    state: FeedState,
+}
+
+impl FeedPane {
+    fn articles(&self) -> &[Article] {
+        match &self.state {
+            FeedState::Loaded(articles) => articles,
+            _ => &[],
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Focus {
+    Feeds,
+    Articles,
+    /// Reading the selected article, scrolled down this many lines
+    Detail(u16),
+}
+
+struct App {
+    feeds: Vec<FeedPane>,
+    feed_list: ListState,
+    article_list: ListState,
+    focus: Focus,
+    /// Shown in the bottom line, e.g. after trying to open a link
+    status: Option<String>,
+}
+
+impl App {
+    fn new(feeds: &[Feed]) -> Self {
+        let mut feed_list = ListState::default();
+        feed_list.select((!feeds.is_empty()).then_some(0));
+        App {
+            feeds: feeds
+                .iter()
+                .map(|feed| FeedPane {
+                    feed: feed.clone(),
+                    state: FeedState::Loading,
+                })
+                .collect(),
+            feed_list,
+            article_list: ListState::default(),
+            focus: Focus::Feeds,
+            status: None,
+        }
+    }
+
+    fn selected_feed(&self) -> Option<&FeedPane> {
+        self.feeds.get(self.feed_list.selected()?)
+    }
+
+    fn selected_article(&self) -> Option<&Article> {
+        self.selected_feed()?
+            .articles()
+            .get(self.article_list.selected()?)
+    }
+
+    fn loaded(&mut self, url: &str, result: Result<FeedResult, FeedError>) {
+        let Some(pane) = self.feeds.iter_mut().find(|p| p.feed.url == url) else {
+            return;
+        };
+        pane.state = match result {
+            Ok(mut result) => {
+                feed::sort_articles(&mut result.articles, SortOrder::Newest);
+                if pane.feed.name.is_none() {
+                    pane.feed.name = Some(result.title);
+                }
+                FeedState::Loaded(result.articles)
+            }
+            Err(e) => FeedState::Failed(e.to_string()),
+        };
+    }
+
+    /// Handles a key press. Returns false when the reader should quit.
+    fn key(&mut self, key: KeyEvent) -> bool {
+        self.status = None;
+        match (self.focus, key.code) {
+            (_, KeyCode::Char('q')) => return false,
+            (_, KeyCode::Char('o')) => self.open_selected(),
+            (Focus::Feeds, KeyCode::Up) => step(&mut self.feed_list, self.feeds.len(), -1),
+            (Focus::Feeds, KeyCode::Down) => step(&mut self.feed_list, self.feeds.len(), 1),
+            (Focus::Feeds, KeyCode::Right | KeyCode::Enter) => {
+                let count = self.selected_feed().map_or(0, |p| p.articles().len());
+                if count > 0 {
+                    self.article_list.select(Some(0));
+                    self.focus = Focus::Articles;
+                }
+            }
+            (Focus::Articles, KeyCode::Up | KeyCode::Down) => {
+                let count = self.selected_feed().map_or(0, |p| p.articles().len());
+                let delta = if key.code == KeyCode::Up { -1 } else { 1 };
+                step(&mut self.article_list, count, delta);
+            }
+            (Focus::Articles, KeyCode::Enter | KeyCode::Right) => self.focus = Focus::Detail(0),
+            (Focus::Articles, KeyCode::Left | KeyCode::Esc) => {
+                self.article_list.select(None);
+                self.focus = Focus::Feeds;
+            }
+            (Focus::Detail(scroll), KeyCode::Up) => {
+                self.focus = Focus::Detail(scroll.saturating_sub(1))
+            }
+            (Focus::Detail(scroll), KeyCode::Down) => {
+                self.focus = Focus::Detail(scroll.saturating_add(1))
+            }
+            (Focus::Detail(_), KeyCode::Left | KeyCode::Esc | KeyCode::Enter) => {
+                self.focus = Focus::Articles
+            }
+            _ => {}
+        }
+        true
+    }
+
+    fn open_selected(&mut self) {
+        let Some(article) = self.selected_article() else {
+            return;
+        };
+        self.status = Some(match &article.link {
+            Some(link) => match open::that_detached(link) {
+                Ok(()) => format!("Opened {}", link),
+                Err(e) => format!("Could not open {}: {}", link, e),
+            },
+            None => "This article has no link.".to_string(),
+        });
+    }
+}
+
+/// Moves the selection of a list with `len` items by `delta`, staying in
+/// bounds.
+fn step(state: &mut ListState, len: usize, delta: isize) {
+    if len == 0 {
+        return;
+    }
+    let current = state.selected().unwrap_or(0);
+    state.select(Some(current.saturating_add_signed(delta).min(len - 1)));
+}
+
+/// Runs the reader until the user quits, filling in each feed as its fetch
+/// in `fetches` completes.
+pub async fn run<'a>(
+    feeds: &[Feed],
+    mut fetches: BoxStream<'a, (&'a Feed, Result<FeedResult, FeedError>)>,
+) -> io::Result<()> {
+    let mut terminal = ratatui::try_init()?;
+    let result = event_loop(&mut terminal, App::new(feeds), &mut fetches).await;
+    ratatui::restore();
+    result
+}
+
+async fn event_loop<'a>(
+    terminal: &mut DefaultTerminal,
+    mut app: App,
+    fetches: &mut BoxStream<'a, (&'a Feed, Result<FeedResult, FeedError>)>,
+) -> io::Result<()> {
+    let mut events = EventStream::new();
+    let mut fetching = true;
+    loop {
+        terminal.draw(|frame| draw(frame, &mut app))?;
+        tokio::select! {
+            event = events.next() => match event {
+                Some(Ok(Event::Key(key))) if key.kind == KeyEventKind::Press => {
+                    if !app.key(key) {
+                        return Ok(());
+                    }
+                }
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(e),
+                None => return Ok(()),
+            },
+            fetched = fetches.next(), if fetching => match fetched {
+                Some((feed, result)) => app.loaded(&feed.url, result),
+                None => fetching = false,
+            },
+        }
+    }
+}
+
+fn draw(frame: &mut Frame, app: &mut App) {
+    let [main, status] =
+        Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+    let [left, right] =
+        Layout::horizontal([Constraint::Percentage(30), Constraint::Percentage(70)]).areas(main);
+    let highlight = Style::default().add_modifier(Modifier::REVERSED);
+
+    let feed_items: Vec<ListItem> = app
+        .feeds
+        .iter()
+        .map(|pane| {
+            let name = pane.feed.name.as_deref().unwrap_or(&pane.feed.url);
+            let suffix = match &pane.state {
+                FeedState::Loading => " …".to_string(),
+                FeedState::Loaded(articles) => format!(" ({})", articles.len()),
+                FeedState::Failed(_) => " (error)".to_string(),
+            };
+            ListItem::new(format!("{}{}", name, suffix))
+        })
+        .collect();
+    let feeds = List::new(feed_items)
+        .block(pane_block("Feeds", app.focus == Focus::Feeds))
+        .highlight_style(highlight);
+    frame.render_stateful_widget(feeds, left, &mut app.feed_list);
+
+    if let Focus::Detail(scroll) = app.focus {
+        let detail = app.selected_article().map(article_text).unwrap_or_default();
+        let paragraph = Paragraph::new(detail)
+            .block(pane_block("Article", true))
+            .wrap(Wrap { trim: false })
+            .scroll((scroll, 0));
+        frame.render_widget(paragraph, right);
+    } else {
+        let block = pane_block("Articles", app.focus == Focus::Articles);
+        match app.selected_feed().map(|p| &p.state) {
+            Some(FeedState::Failed(e)) => {
+                let error = Paragraph::new(format!("Error fetching feed: {}", e))
+                    .block(block)
+                    .wrap(Wrap { trim: true });
+                frame.render_widget(error, right);
+            }
+            Some(FeedState::Loading) => {
+                frame.render_widget(Paragraph::new("Loading…").block(block), right);
+            }
+            _ => {
+                let items: Vec<ListItem> = app
+                    .selected_feed()
+                    .map(FeedPane::articles)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|article| {
+                        let date = article.published_display().unwrap_or_default();
+                        ListItem::new(format!("{:<16}  {}", date, article.title))
+                    })
+                    .collect();
+                let articles = List::new(items).block(block).highlight_style(highlight);
+                frame.render_stateful_widget(articles, right, &mut app.article_list);
+            }
+        }
+    }
+
+    let help = "↑/↓ move  →/Enter open  ←/Esc back  o open link  q quit";
+    let line = app.status.as_deref().unwrap_or(help);
+    frame.render_widget(Paragraph::new(line), status);
+}
+
+fn pane_block(title: &str, focused: bool) -> Block<'_> {
+    let style = if focused {
+        Style::default().add_modifier(Modifier::BOLD)
+    } else {
+        Style::default()
+    };
+    Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .border_style(style)
+}
+
+fn article_text(article: &Article) -> Text<'static> {
+    let mut lines = vec![Line::styled(
+        article.title.clone(),
+        Style::default().add_modifier(Modifier::BOLD),
+    )];
+    if let Some(date) = article.published_display() {
+        lines.push(Line::from(date));
+    }
+    if let Some(author) = &article.author {
+        lines.push(Line::from(format!("by {}", author)));
+    }
+    if let Some(link) = &article.link {
+        lines.push(Line::from(link.clone()));
+    }
+    lines.push(Line::default());
+    let body = article
+        .content
+        .as_deref()
+        .map(feed::strip_html)
+        .unwrap_or_default();
+    lines.extend(body.lines().map(|l| Line::from(l.to_string())));
+    Text::from(lines)
+}
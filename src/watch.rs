@@ -0,0 +1,168 @@
+use crate::analysis::{self, ArticleMention, Lexicon};
+use crate::feed::{self, Article, FeedError, FeedResult};
+use crate::fetcher::Fetcher;
+use crate::seen::SeenStore;
+use crate::storage::{Config, Feed};
+use futures::StreamExt;
+
+/// Fetches `feeds` once and hands each to `on_feed` with the articles not
+/// marked read in `seen` yet, oldest first, marking those read so the next
+/// poll only sees what's new since. A story carried by several feeds is
+/// handed over once. Feeds that can't be fetched are handed their error.
+/// Returns how many failed; saving `seen` and the fetcher's cache is left
+/// to the caller.
+pub async fn poll(
+    fetcher: Fetcher<'_>,
+    feeds: &[Feed],
+    seen: &mut SeenStore,
+    mut on_feed: impl FnMut(&Feed, Result<FeedResult, FeedError>),
+) -> usize {
+    let mut dedup = feed::Deduplicator::default();
+    let mut failed = 0;
+    let mut fetches = fetcher.fetch_feeds(feeds, None, false);
+    while let Some((feed, result)) = fetches.next().await {
+        let result = result.map(|mut result| {
+            dedup.retain_new(&mut result.articles);
+            seen.take_unread(&mut result.articles);
+            feed::sort_articles(&mut result.articles, feed::SortOrder::Oldest);
+            result
+        });
+        failed += result.is_err() as usize;
+        on_feed(feed, result);
+    }
+    failed
+}
+
+/// The mentions `watch --notify` raises for `articles`, which a [`poll`]
+/// has already narrowed to those not seen before: one per tracked
+/// investment each article mentions.
+pub fn notifications(
+    config: &Config,
+    lexicon: &Lexicon,
+    articles: &[Article],
+) -> Vec<ArticleMention> {
+    analysis::find_mentions(
+        articles,
+        &config.investments,
+        &config.ticker_boundaries,
+        lexicon,
+        None,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feed::FetchOptions;
+    use crate::http::StubTransport;
+    use crate::storage::Investment;
+
+    const URLS: [&str; 2] = ["https://a.example.com/rss", "https://b.example.com/rss"];
+
+    /// An RSS document with one item per title, dated a day apart, oldest
+    /// last.
+    fn rss(titles: &[&str]) -> String {
+        let items: String = titles
+            .iter()
+            .enumerate()
+            .map(|(i, t)| {
+                format!(
+                    "<item><title>{}</title><link>https://example.com/{}</link>\
+                     <pubDate>{:02} Oct 2026 12:00:00 GMT</pubDate></item>",
+                    t,
+                    t.replace(' ', "-"),
+                    16 - i
+                )
+            })
+            .collect();
+        format!(
+            r#"<rss version="2.0"><channel><title>Test</title>{}</channel></rss>"#,
+            items
+        )
+    }
+
+    /// What each poll of both feeds hands over: `ticker sentiment: title`
+    /// for each notification, or the title of each new article.
+    async fn polls(documents: &[[&str; 2]], notify: bool) -> Vec<Vec<String>> {
+        let mut transport = StubTransport::default();
+        for [a, b] in documents {
+            transport = transport
+                .respond(URLS[0], 200, *a)
+                .respond(URLS[1], 200, *b);
+        }
+        let options = FetchOptions::default();
+        let fetcher = Fetcher {
+            concurrency: 1,
+            ..Fetcher::new(&transport, &options)
+        };
+        let feeds: Vec<Feed> = URLS.iter().map(|url| Feed::new(url)).collect();
+        let mut config = Config::default();
+        config.upsert_investment(Investment::new("AAPL"), false);
+        let lexicon = Lexicon::default();
+        let mut seen = SeenStore::default();
+
+        let mut handed = Vec::new();
+        for _ in documents {
+            let mut lines = Vec::new();
+            poll(fetcher, &feeds, &mut seen, |_, result| {
+                let articles = result.unwrap().articles;
+                if notify {
+                    lines.extend(
+                        notifications(&config, &lexicon, &articles)
+                            .iter()
+                            .map(|m| format!("{} {}: {}", m.ticker, m.sentiment, m.article.title)),
+                    );
+                } else {
+                    lines.extend(articles.into_iter().map(|a| a.title));
+                }
+            })
+            .await;
+            handed.push(lines);
+        }
+        handed
+    }
+
+    #[tokio::test]
+    async fn each_poll_hands_over_unread_articles_oldest_first() {
+        let first = rss(&["Weather today", "Markets open"]);
+        let second = rss(&["Markets close", "Weather today", "Markets open"]);
+        assert_eq!(
+            polls(&[[&first, &rss(&[])], [&second, &rss(&[])]], false).await,
+            [vec!["Markets open", "Weather today"], vec!["Markets close"]]
+        );
+    }
+
+    #[tokio::test]
+    async fn watch_notifies_once_for_new_articles_mentioning_investments() {
+        let first = rss(&["AAPL surges", "Weather today"]);
+        let second = rss(&["AAPL plunges", "AAPL surges", "Weather today"]);
+        assert_eq!(
+            polls(&[[&first, &first], [&second, &first]], true).await,
+            [
+                vec!["AAPL Positive: AAPL surges"],
+                vec!["AAPL Negative: AAPL plunges"]
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_feeds_are_counted_and_handed_their_error() {
+        let transport = StubTransport::default().respond(URLS[0], 200, rss(&["Markets open"]));
+        let options = FetchOptions::default();
+        let feeds: Vec<Feed> = URLS.iter().map(|url| Feed::new(url)).collect();
+        let mut errors = Vec::new();
+        let failed = poll(
+            Fetcher::new(&transport, &options),
+            &feeds,
+            &mut SeenStore::default(),
+            |feed, result| {
+                if result.is_err() {
+                    errors.push(feed.url.clone());
+                }
+            },
+        )
+        .await;
+        assert_eq!(failed, 1);
+        assert_eq!(errors, [URLS[1]]);
+    }
+}